@@ -15,26 +15,35 @@ impl super::Generator for DartGenerator {
     async fn generate(
         &self,
         spec: &oas3::Spec,
+        raw: &serde_json::Value,
         args: GenerationArgs,
-    ) -> Result<Vec<super::File>, String> {
+    ) -> Result<super::Generated, String> {
         let class_prefix = "BEAM";
         let class_suffix = "Model";
         let mut out = Vec::new();
         serde::add_serde_utils(&mut out);
         println!("parsing spec to intermediate");
-        let intermediate = match intermediate::parse(
-            &spec,
+        let document = intermediate::SpecDocument::new(spec, raw);
+        let intermediate = match intermediate::parse_document(
+            &document,
             IntermediateArgs {
                 ignore_deprecated_fields: args.ignore_deprecated_fields,
+                strict: args.strict,
             },
         ) {
             Ok(intermediate) => intermediate,
             Err(e) => {
-                println!("parsing spec to intermediate error: {:?}", e);
-                return Err(format!("parsing spec to intermediate error: {:?}", e));
+                println!("parsing spec to intermediate error: {}", e);
+                return Err(format!("parsing spec to intermediate error: {}", e));
             }
         };
-        let mut scheme_adder = schemes::SchemeAdder::new(class_prefix, class_suffix, false);
+        let mut scheme_adder = schemes::SchemeAdder::new(
+            class_prefix,
+            class_suffix,
+            false,
+            args.generate_validators,
+            args.read_write_models,
+        );
         scheme_adder.set_complete_iast(&intermediate);
         let endpoint_adder = endpoints::EndpointAdder::new(&scheme_adder, &intermediate);
         let mut scheme_files = Vec::new();
@@ -49,6 +58,9 @@ impl super::Generator for DartGenerator {
         });
         out.extend(scheme_files);
         out.extend(endpoint_files);
-        Ok(out)
+        Ok(super::Generated {
+            files: out,
+            diagnostics: intermediate.diagnostics,
+        })
     }
 }
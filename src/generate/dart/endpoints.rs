@@ -5,10 +5,14 @@ use crate::{
         },
         File, GeneratedCode,
     },
-    parse::intermediate::{self, Route, RouteFragmentLeafData},
+    parse::intermediate::{self, strip_ref_prefix, Route, RouteFragmentLeafData},
 };
 
-use super::schemes;
+use super::schemes::{
+    self, dart_string_literal, escape_dart_string, Collection, Direction, FormatCodec,
+};
+use intermediate::{FormStyle, PayloadFormat};
+use std::collections::{BTreeMap, HashMap};
 
 /// Result of [`EndpointAdder::generate_route_fragment`]: the generated
 /// fragment class, its own class name (so the parent can reference it)
@@ -19,11 +23,87 @@ struct GeneratedFragment {
     files: Vec<File>,
 }
 
-/// Result of [`mk_params`]: the Dart `typedef` for the params record and
-/// the snippet that builds the `paramsJson` map from those params.
+/// Result of [`EndpointAdder::mk_params`]: the Dart `typedef` for the
+/// params record (followed by any enum classes generated for inline enum
+/// params), the snippet that builds the `paramsJson` map from those params
+/// and the imports of the schemes referenced by them.
 struct ParamsCode {
     typedef: String,
     as_json_body: String,
+    imports: String,
+}
+
+/// Result of [`EndpointAdder::mk_form`]: the record typedef of the
+/// form's fields (followed by any enum classes generated for them), the
+/// statements turning such a record into the body named `formBody` and the imports of the referenced schemes.
+struct FormCode {
+    record_name: String,
+    typedef: String,
+    as_form_body: String,
+    imports: String,
+}
+
+/// Dart type of a single query/header parameter, together with how a value
+/// of that type is rendered into the `Map<String, String>` that is handed to
+/// the request handler.
+struct ParamType {
+    typ: String,
+    encoding: ParamEncoding,
+}
+
+enum ParamEncoding {
+    /// `String`, `int`, `num`, `bool` and typedefs thereof: `.toString()`.
+    Primitive,
+    /// A generated enum class: its JSON value, stringified.
+    Enum,
+    /// A generated class (or `UnknownBEAMObject`): JSON encoded.
+    Class,
+    /// A raw `dynamic` value: JSON encoded as is.
+    Json,
+    /// A formatted primitive (e.g. `DateTime`): its JSON string.
+    Format(FormatCodec),
+    /// `List<T>`: the encoded elements joined by `,` (OpenAPI `form` style
+    /// without `explode`), as the params map holds a single value per key.
+    /// Params join them after their `style`, see [`ParamType::encode_param`].
+    List(Box<ParamType>),
+}
+
+impl ParamType {
+    /// Dart expression rendering `expr` (a non-null value of this type) as
+    /// a query/header string.
+    fn encode(&self, expr: &str) -> String {
+        match &self.encoding {
+            ParamEncoding::Primitive if self.typ == "String" => expr.to_string(),
+            ParamEncoding::Primitive => format!("{}.toString()", expr),
+            ParamEncoding::Enum => format!("{}.toJson().toString()", expr),
+            ParamEncoding::Class => format!("jsonEncode({}.toJson())", expr),
+            ParamEncoding::Json => format!("jsonEncode({})", expr),
+            ParamEncoding::Format(codec) => codec.encode(expr, false),
+            ParamEncoding::List(inner) => {
+                format!("{}.map((e) => {}).join(',')", expr, inner.encode("e"))
+            }
+        }
+    }
+
+    /// Like [`encode`](Self::encode), but joins the elements of lists with
+    /// the delimiter of the param's `style`.
+    fn encode_param(&self, expr: &str, style: intermediate::ParamStyle) -> String {
+        use intermediate::ParamStyle;
+        let delimiter = match style {
+            ParamStyle::SpaceDelimited => " ",
+            ParamStyle::PipeDelimited => "|",
+            _ => ",",
+        };
+        match &self.encoding {
+            ParamEncoding::List(inner) => format!(
+                "{}.map((e) => {}).join('{}')",
+                expr,
+                inner.encode("e"),
+                delimiter
+            ),
+            _ => self.encode(expr),
+        }
+    }
 }
 
 /// Classification of a request body, driving how it is serialized before
@@ -32,14 +112,25 @@ struct ParamsCode {
 struct BodyClass {
     decl: Option<String>,
     is_primitive: bool,
-    list_inner_type: Option<String>,
+    /// The collection the body is and its element type, for lists and maps.
+    collection: Option<(Collection, String)>,
+    /// Codec of the body (or of its elements, for collections) if it is a
+    /// formatted primitive.
+    codec: Option<FormatCodec>,
+    /// Dart statements building `formBody` out of `body`, for form bodies,
+    /// which are sent as `formBody` instead.
+    form: Option<String>,
 }
 
 /// Classification of a response value, driving how it is decoded.
 struct ResponseClass {
     type_str: String,
     is_primitive: bool,
-    list_inner_type: Option<String>,
+    /// The collection the value is and its element type, for lists and maps.
+    collection: Option<(Collection, String)>,
+    /// Codec of the value (or of its elements, for collections) if it is a
+    /// formatted primitive.
+    codec: Option<FormatCodec>,
     is_binary: bool,
     /// True when `type_str` is a multi-status [`BeamStatusCodeResponse`]
     /// union exposing a `static fromCode(int, json)` factory. The endpoint
@@ -49,11 +140,255 @@ struct ResponseClass {
     is_status_code_union: bool,
 }
 
+impl ResponseClass {
+    /// Dart expression decoding the JSON value `expr` into `type_str`. For
+    /// status code unions, this is the status-code-less `fromJson`.
+    fn decode(&self, expr: &str) -> String {
+        match (self.is_primitive, &self.collection, self.codec) {
+            (_, None, Some(codec)) => codec.decode(expr, false),
+            (_, Some((collection, _)), Some(codec)) => {
+                collection.decode(expr, &codec.decode("e", false))
+            }
+            (true, None, None) => expr.to_string(),
+            (true, Some((Collection::List, _)), None) => expr.to_string(),
+            // a decoded JSON object is a `Map<String, dynamic>`, so its values
+            // need a cast
+            (true, Some((Collection::Map, inner_type)), None) => {
+                Collection::Map.decode(expr, &format!("e as {}", inner_type))
+            }
+            (false, Some((collection, inner_type)), None) => {
+                collection.decode(expr, &format!("{}.fromJson(e)", inner_type))
+            }
+            (false, None, None) => format!("{}.fromJson({})", self.type_str, expr),
+        }
+    }
+}
+
+/// One generated Dart method of an endpoint: a choice of request body and
+/// response media types. The default variant (named after the HTTP method)
+/// uses the preferred media types, see [`intermediate::preferred_media_type`];
+/// every other offered media type gets a dedicated method.
+struct MediaVariant<'e, 'a> {
+    name: String,
+    request: Option<&'e intermediate::Payload<'a>>,
+    responses: BTreeMap<&'a String, &'e intermediate::IAST<'a>>,
+    /// The request `Content-Type`, unless it is JSON (which handlers assume).
+    content_type: Option<&'a str>,
+    /// The `Accept` header value, unless only JSON is accepted.
+    accept: Option<String>,
+}
+
+impl MediaVariant<'_, '_> {
+    /// The extra named arguments passed on to `handleCached`.
+    fn handle_args(&self) -> String {
+        let mut args = String::new();
+        if let Some(content_type) = self.content_type {
+            args.push_str(&format!(", contentType: '{}'", content_type));
+        }
+        if let Some(accept) = &self.accept {
+            args.push_str(&format!(", accept: '{}'", accept));
+        }
+        args
+    }
+}
+
+fn media_variants<'e, 'a>(endpoint: &'e intermediate::Endpoint<'a>) -> Vec<MediaVariant<'e, 'a>> {
+    let verb = endpoint.method.string();
+    let default_request = endpoint
+        .request
+        .as_ref()
+        .and_then(intermediate::preferred_media_type);
+    let default_responses: BTreeMap<&'a String, (&'a str, &'e intermediate::Payload<'a>)> =
+        endpoint
+            .responses
+            .iter()
+            .filter_map(|(code, content)| {
+                intermediate::preferred_media_type(content).map(|preferred| (*code, preferred))
+            })
+            .collect();
+    let variant =
+        |name: String,
+         request: Option<(&'a str, &'e intermediate::Payload<'a>)>,
+         responses: BTreeMap<&'a String, (&'a str, &'e intermediate::Payload<'a>)>| {
+            let mut accepted: Vec<&str> = responses
+                .values()
+                .map(|(media_type, _)| *media_type)
+                .collect();
+            accepted.sort();
+            accepted.dedup();
+            MediaVariant {
+                name,
+                request: request.map(|(_, payload)| payload),
+                content_type: request
+                    .map(|(media_type, _)| media_type)
+                    .filter(|media_type| !intermediate::is_json_media_type(media_type)),
+                accept: if accepted.iter().all(|m| intermediate::is_json_media_type(m)) {
+                    None
+                } else {
+                    Some(accepted.join(", "))
+                },
+                responses: responses
+                    .into_iter()
+                    .map(|(code, (_, payload))| (code, &payload.schema))
+                    .collect(),
+            }
+        };
+
+    let mut variants = vec![variant(
+        verb.to_string(),
+        default_request,
+        default_responses.clone(),
+    )];
+    if let (Some(content), Some((default_media_type, _))) = (&endpoint.request, default_request) {
+        for (media_type, payload) in content {
+            if *media_type != default_media_type {
+                variants.push(variant(
+                    format!("{}{}", verb, media_type_suffix(media_type)),
+                    Some((*media_type, payload)),
+                    default_responses.clone(),
+                ));
+            }
+        }
+    }
+    let mut other_response_media_types: Vec<&'a str> = endpoint
+        .responses
+        .iter()
+        .flat_map(|(code, content)| {
+            let default_media_type = default_responses.get(code).map(|(m, _)| *m);
+            content
+                .keys()
+                .copied()
+                .filter(move |m| Some(*m) != default_media_type)
+        })
+        .collect();
+    other_response_media_types.sort();
+    other_response_media_types.dedup();
+    for accepted in other_response_media_types {
+        // codes not offering the media type keep their default
+        let responses = endpoint
+            .responses
+            .iter()
+            .filter_map(|(code, content)| match content.get_key_value(accepted) {
+                Some((media_type, payload)) => Some((*code, (*media_type, payload))),
+                None => default_responses.get(code).map(|d| (*code, *d)),
+            })
+            .collect();
+        variants.push(variant(
+            format!("{}Accepting{}", verb, media_type_suffix(accepted)),
+            default_request,
+            responses,
+        ));
+    }
+    variants
+}
+
+fn iast_is_optional(iast: &intermediate::IAST) -> bool {
+    match iast {
+        intermediate::IAST::Object(o) => o.optional || o.nullable,
+        intermediate::IAST::Primitive(p) => p.optional || p.nullable,
+        intermediate::IAST::Reference(r) => r.optional || r.nullable,
+    }
+}
+
+fn iast_description<'a>(iast: &intermediate::IAST<'a>) -> Option<&'a str> {
+    match iast {
+        intermediate::IAST::Object(o) => o.description,
+        intermediate::IAST::Primitive(p) => p.description,
+        intermediate::IAST::Reference(_) => None,
+    }
+}
+
+/// `text/plain; charset=utf-8` -> `TextPlain`, used to name the dedicated
+/// method of a media type.
+fn media_type_suffix(media_type: &str) -> String {
+    media_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
 #[macro_use]
 mod macros;
 pub struct EndpointAdder<'a> {
     scheme_adder: &'a schemes::SchemeAdder<'a>,
+    /// Generates request bodies, see [`schemes::SchemeAdder::directed`].
+    request_scheme_adder: schemes::SchemeAdder<'a>,
+    /// Generates responses, see [`schemes::SchemeAdder::directed`].
+    response_scheme_adder: schemes::SchemeAdder<'a>,
     intermediate: &'a intermediate::IntermediateFormat<'a>,
+    /// Every server (the API's, then the overriding ones of endpoints,
+    /// each URL once) with the name of its `BEAMServer` constructor.
+    servers: Vec<(&'a intermediate::Server<'a>, String)>,
+}
+
+/// `s` with its first character in upper case.
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map(|c| format!("{}{}", c.to_uppercase(), chars.as_str()))
+        .unwrap_or_default()
+}
+
+/// Names the `BEAMServer` constructor of each server after its description,
+/// e.g. `Production server` -> `productionServer`, falling back to
+/// `server{index}`. Names taken already get a numeric suffix.
+fn server_constructor_names<'a>(
+    servers: impl Iterator<Item = &'a intermediate::Server<'a>>,
+) -> Vec<(&'a intermediate::Server<'a>, String)> {
+    // members of `BEAMServer` a constructor must not clash with
+    let mut taken: Vec<String> = ["url", "all", "resolve", "toString"]
+        .map(String::from)
+        .to_vec();
+    let mut named: Vec<(&intermediate::Server, String)> = Vec::new();
+    for server in servers {
+        if named.iter().any(|(s, _)| s.url == server.url) {
+            continue;
+        }
+        let words = server
+            .description
+            .unwrap_or("")
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .enumerate()
+            .map(|(i, w)| {
+                let mut chars = w.chars();
+                match i {
+                    0 => format!(
+                        "{}{}",
+                        chars.next().unwrap_or_default().to_lowercase(),
+                        chars.as_str()
+                    ),
+                    _ => capitalize(w),
+                }
+            })
+            .collect::<String>();
+        let wanted = match words.is_empty() {
+            true => format!("server{}", named.len()),
+            false => sanitize_identifier(&words),
+        };
+        // e.g. two servers of the same description
+        let mut name = wanted.clone();
+        for i in 2.. {
+            if !taken.contains(&name) {
+                break;
+            }
+            name = format!("{}{}", wanted, i);
+        }
+        taken.push(name.clone());
+        named.push((server, name));
+    }
+    named
 }
 
 impl<'a> EndpointAdder<'a> {
@@ -63,7 +398,18 @@ impl<'a> EndpointAdder<'a> {
     ) -> Self {
         Self {
             scheme_adder,
+            request_scheme_adder: scheme_adder.directed(Direction::Request),
+            response_scheme_adder: scheme_adder.directed(Direction::Response),
             intermediate,
+            servers: server_constructor_names(
+                intermediate.servers.iter().chain(
+                    intermediate
+                        .routes
+                        .iter()
+                        .flat_map(|r| &r.endpoints)
+                        .flat_map(|e| &e.servers),
+                ),
+            ),
         }
     }
     pub fn add_endpoints(&self, out: &mut Vec<File>) {
@@ -114,8 +460,12 @@ impl<'a> EndpointAdder<'a> {
         out_files.extend(frag.files);
 
         let mut imports_content = String::new();
-        imports_content.push_str(&include_str!("endpoints/imports.dart"));
+        imports_content.push_str(include_str!("endpoints/imports.dart"));
         cpf!(imports_content, "import '{}';", root_frag_file_name);
+        if !intermediate.inbound.is_empty() {
+            out_files.extend(self.generate_inbound());
+            cpf!(imports_content, "export 'inbound.dart';");
+        }
         let mut content = String::new();
         content.push_str(&imports_content);
         content.push_str(&format!(
@@ -123,7 +473,9 @@ impl<'a> EndpointAdder<'a> {
             paths_enum.class_name
         ));
         content.push_str(&paths_enum.content);
-        content.push_str(&interface_content);
+        content.push_str(interface_content);
+        content.push_str(&self.generate_credentials_provider());
+        content.push_str(&self.generate_servers());
         out_files.push(File {
             path: std::path::PathBuf::from("endpoints.dart"),
             content,
@@ -134,6 +486,558 @@ impl<'a> EndpointAdder<'a> {
         }));
     }
 
+    /// `inbound.dart`, with an event class per webhook and callback
+    /// operation and the `BEAMInbound` dispatchers, `decodeWebhook` by name
+    /// and `decodeCallback` by operation and name, followed by the files of
+    /// their payloads.
+    fn generate_inbound(&self) -> Vec<File> {
+        let mut files = Vec::new();
+        let mut imports = String::new();
+        let mut events = String::new();
+        let mut dispatch = String::new();
+        let mut webhook_dispatch = String::new();
+        cpf!(imports, "// ignore_for_file: unused_import");
+        cpf!(imports, "import 'endpoints.dart';");
+        cpf!(imports, "import '../utils/serde.dart';");
+        cpf!(imports, "import 'dart:convert';\n");
+        for inbound in &self.intermediate.inbound {
+            let endpoint = &inbound.endpoint;
+            let verb = endpoint.method.string();
+            // webhooks are told apart by their name, callbacks by that of
+            // theirs and their operation, as their URLs are only known at
+            // runtime
+            let (class_name, doc, operation) = match &inbound.origin {
+                intermediate::InboundOrigin::Webhook => (
+                    format!("BEAMWebhook_{}_{}", sanitize(inbound.name), verb),
+                    format!("The webhook `{}`.", inbound.name),
+                    None,
+                ),
+                intermediate::InboundOrigin::Callback {
+                    path,
+                    method,
+                    expression,
+                } => (
+                    format!(
+                        "BEAMCallback{}_{}_{}_{}",
+                        sanitize(path),
+                        method.string(),
+                        sanitize(inbound.name),
+                        verb
+                    ),
+                    format!(
+                        "The callback `{}` of `{} {}`, sent to `{}`.",
+                        inbound.name,
+                        method.string().to_uppercase(),
+                        path,
+                        expression
+                    ),
+                    Some((*path, method.string())),
+                ),
+            };
+            let payload = endpoint
+                .request
+                .as_ref()
+                .and_then(intermediate::preferred_media_type)
+                .map(|(_, payload)| {
+                    let payload_name = format!("{}Payload", &class_name[4..]);
+                    let parsed = self.response_scheme_adder.parse_named_iast(
+                        &payload_name,
+                        &payload.schema,
+                        1,
+                    );
+                    let file_name = format!("inbound/{}.schema.dart", payload_name);
+                    cpf!(imports, "import '{}';", file_name);
+                    cpf!(imports, "export '{}';", file_name);
+                    files.push(File {
+                        path: std::path::PathBuf::from(file_name),
+                        content: parsed.content,
+                    });
+                    files.extend(parsed.files.into_iter().map(|f| File {
+                        path: std::path::PathBuf::from(format!(
+                            "inbound/{}",
+                            f.path.to_str().unwrap()
+                        )),
+                        content: f.content,
+                    }));
+                    self.response_class(
+                        &payload_name,
+                        &payload.schema,
+                        parsed.special_case,
+                        parsed.is_binary,
+                    )
+                });
+
+            cpf!(events, "\n/// {}", doc);
+            for text in [endpoint.summary, endpoint.description]
+                .into_iter()
+                .flatten()
+            {
+                cpf!(events, "///\n/// {}", text.replace("\n", "\n/// "));
+            }
+            cpf!(events, "class {} extends BEAMInboundEvent {{", class_name);
+            match &payload {
+                Some(payload) => {
+                    cpf!(events, "  final {} payload;\n", payload.type_str);
+                    cpf!(events, "  {}(this.payload);", class_name);
+                }
+                None => {
+                    cpf!(events, "  {}();", class_name);
+                }
+            }
+            cpf!(events, "}}");
+
+            let (dispatch, condition) = match operation {
+                Some((path, operation)) => (
+                    &mut dispatch,
+                    format!(
+                        "path == {} && operation == BEAMRequestMethod.{} && name == {}",
+                        dart_string_literal(path),
+                        operation,
+                        dart_string_literal(inbound.name)
+                    ),
+                ),
+                None => (
+                    &mut webhook_dispatch,
+                    format!("name == {}", dart_string_literal(inbound.name)),
+                ),
+            };
+            cpf!(
+                dispatch,
+                "    if (method == BEAMRequestMethod.{} && {}) {{",
+                verb,
+                condition
+            );
+            cpf!(
+                dispatch,
+                "      return {}({});\n    }}",
+                class_name,
+                payload.map_or(String::new(), |payload| payload.decode("body"))
+            );
+        }
+        let mut content = imports;
+        content.push_str(
+            "\n/// A decoded request the API sent: of one of its webhooks or of a\n/// callback of one of its operations.\nsealed class BEAMInboundEvent {}\n",
+        );
+        content.push_str(&events);
+        cpf!(content, "\n/// Decodes the requests the API sends.");
+        cpf!(content, "abstract final class BEAMInbound {{");
+        cpf!(
+            content,
+            "  /// The event of the JSON [body] of a [method] request of the webhook\n  /// [name], null if there is no such webhook."
+        );
+        cpf!(
+            content,
+            "  static BEAMInboundEvent? decodeWebhook({{\n    required String name,\n    required BEAMRequestMethod method,\n    dynamic body,\n  }}) {{"
+        );
+        content.push_str(&webhook_dispatch);
+        cpf!(content, "    return null;\n  }}\n");
+        cpf!(
+            content,
+            "  /// The event of the JSON [body] of a [method] request of the callback\n  /// [name] of the [operation] on [path], null if there is no such\n  /// callback."
+        );
+        cpf!(
+            content,
+            "  static BEAMInboundEvent? decodeCallback({{\n    required String path,\n    required BEAMRequestMethod operation,\n    required String name,\n    required BEAMRequestMethod method,\n    dynamic body,\n  }}) {{"
+        );
+        content.push_str(&dispatch);
+        cpf!(content, "    return null;\n  }}\n}}");
+        files.push(File {
+            path: std::path::PathBuf::from("inbound.dart"),
+            content,
+        });
+        files
+    }
+
+    /// The `BEAMServer` class, with a constructor per server taking its
+    /// variables, followed by the enums of the variables with a fixed set
+    /// of values.
+    fn generate_servers(&self) -> String {
+        let mut c = String::new();
+        let mut enums = String::new();
+        cpf!(c, "\n/// A server of the API.");
+        cpf!(c, "class BEAMServer {{");
+        cpf!(
+            c,
+            "  /// The base URL, with the server variables substituted."
+        );
+        cpf!(c, "  final String url;\n");
+        cpf!(c, "  /// A server at [url], e.g. a local one.");
+        cpf!(c, "  const BEAMServer(this.url);\n");
+        for (server, ctor) in &self.servers {
+            let mut params = Vec::new();
+            // `{variable}` -> the Dart expression of its value
+            let mut values = HashMap::new();
+            let mut docs = Vec::new();
+            for variable in &server.variables {
+                let param = create_property_name(variable.name);
+                if variable.values.is_empty() {
+                    params.push(format!(
+                        "String {} = {}",
+                        param,
+                        dart_string_literal(variable.default)
+                    ));
+                    values.insert(variable.name, param.clone());
+                } else {
+                    let enum_code = self.scheme_adder.generate_primitive_sum_type(
+                        &format!(
+                            "Server{}{}",
+                            capitalize(ctor),
+                            capitalize(&sanitize(variable.name))
+                        ),
+                        &format!(
+                            "/// The values of `{}` of `{}`.\n",
+                            variable.name, server.url
+                        ),
+                        &variable
+                            .values
+                            .iter()
+                            .map(|value| schemes::AllowedValue {
+                                value,
+                                is_string: true,
+                                description: "",
+                            })
+                            .collect::<Vec<_>>(),
+                    );
+                    params.push(format!(
+                        "{} {} = {}.t_{}",
+                        enum_code.class_name,
+                        param,
+                        enum_code.class_name,
+                        sanitize(variable.default)
+                    ));
+                    values.insert(variable.name, format!("{}.toJson()", param));
+                    enums.push_str(&enum_code.content);
+                }
+                if let Some(description) = variable.description {
+                    docs.push(format!(
+                        "[{}]: {}",
+                        param,
+                        description.replace("\n", "\n  /// ")
+                    ));
+                }
+            }
+            // substitute the variables into the (escaped) URL template
+            let mut url = String::new();
+            let mut rest = server.url;
+            while let Some(start) = rest.find('{') {
+                let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+                    break;
+                };
+                url.push_str(&escape_dart_string(&rest[..start]));
+                match values.get(&rest[start + 1..end]) {
+                    Some(value) => url.push_str(&format!("${{{}}}", value)),
+                    None => url.push_str(&escape_dart_string(&rest[start..=end])),
+                }
+                rest = &rest[end + 1..];
+            }
+            url.push_str(&escape_dart_string(rest));
+
+            cpf!(c, "  /// `{}`", server.url);
+            if let Some(description) = server.description {
+                cpf!(c, "  ///\n  /// {}", description.replace("\n", "\n  /// "));
+            }
+            for doc in docs {
+                cpf!(c, "  ///\n  /// {}", doc);
+            }
+            cpf!(
+                c,
+                "  factory BEAMServer.{}({}) => BEAMServer('{}');\n",
+                ctor,
+                match params.is_empty() {
+                    true => String::new(),
+                    false => format!("{{{}}}", params.join(", ")),
+                },
+                url
+            );
+        }
+        cpf!(
+            c,
+            "  /// The API's own servers, in order of preference, with the default\n  /// variables."
+        );
+        cpf!(
+            c,
+            "  static List<BEAMServer> get all => [{}];\n",
+            self.servers
+                .iter()
+                .filter(|(server, _)| self
+                    .intermediate
+                    .servers
+                    .iter()
+                    .any(|s| s.url == server.url))
+                .map(|(_, ctor)| format!("BEAMServer.{}()", ctor))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        cpf!(c, "  /// [path] on this server.");
+        cpf!(c, "  Uri resolve(String path) => Uri.parse('$url$path');\n");
+        cpf!(c, "  @override\n  String toString() => url;");
+        cpf!(c, "}}");
+        c.push_str(&enums);
+        c
+    }
+
+    /// The `BEAMCredentialsProvider` interface, with a method per security
+    /// scheme, and the `_applyCredentials` function adding what it provides
+    /// for a scheme to a request as the scheme prescribes.
+    fn generate_credentials_provider(&self) -> String {
+        let mut provider = String::new();
+        let mut apply = String::new();
+        cpf!(
+            provider,
+            "\n/// Provides the credentials of the API's security schemes, each null if\n/// there are none (yet)."
+        );
+        cpf!(
+            provider,
+            "abstract interface class BEAMCredentialsProvider {{"
+        );
+        cpf!(
+            apply,
+            "/// Adds the credentials [provider] has for the security scheme [scheme] to\n/// [credentials], false if it has none."
+        );
+        cpf!(apply, "Future<bool> _applyCredentials(\n  BEAMCredentialsProvider provider,\n  String scheme,\n  List<String> scopes,\n  BEAMRequestCredentials credentials,\n) async {{\n  switch (scheme) {{");
+        for scheme in &self.intermediate.security_schemes {
+            let method = create_property_name(scheme.name);
+            let (doc, typ, takes_scopes, target, value) = match &scheme.kind {
+                intermediate::SecuritySchemeKind::ApiKey { name, location } => {
+                    let (place, target) = match location {
+                        intermediate::ParamLocation::Query => ("query parameter", "query"),
+                        intermediate::ParamLocation::Cookie => ("cookie", "cookies"),
+                        _ => ("header", "headers"),
+                    };
+                    (
+                        format!("an API key, sent as the {} `{}`.", place, name),
+                        "String",
+                        false,
+                        format!("{}[{}]", target, dart_string_literal(name)),
+                        "value".to_string(),
+                    )
+                }
+                intermediate::SecuritySchemeKind::Http { scheme, .. }
+                    if scheme.eq_ignore_ascii_case("basic") =>
+                {
+                    (
+                        "HTTP basic authentication.".to_string(),
+                        "BEAMBasicCredentials",
+                        false,
+                        "headers['Authorization']".to_string(),
+                        "value.header".to_string(),
+                    )
+                }
+                intermediate::SecuritySchemeKind::Http {
+                    scheme,
+                    bearer_format,
+                } => {
+                    let auth_scheme = capitalize(scheme);
+                    (
+                        match bearer_format {
+                            Some(format) => format!(
+                                "a token for HTTP `{}` authentication, formatted as {}.",
+                                scheme, format
+                            ),
+                            None => format!("a token for HTTP `{}` authentication.", scheme),
+                        },
+                        "String",
+                        false,
+                        "headers['Authorization']".to_string(),
+                        format!("'{} $value'", sanitize(&auth_scheme)),
+                    )
+                }
+                intermediate::SecuritySchemeKind::OAuth2 { scopes } => (
+                    match scopes.is_empty() {
+                        true => "an OAuth2 access token granting [scopes].".to_string(),
+                        false => format!(
+                            "an OAuth2 access token granting [scopes], out of:\n{}",
+                            scopes
+                                .iter()
+                                .map(|(scope, description)| format!(
+                                    "- `{}`: {}",
+                                    scope,
+                                    description.replace("\n", " ")
+                                ))
+                                .collect::<Vec<_>>()
+                                .join("\n")
+                        ),
+                    },
+                    "String",
+                    true,
+                    "headers['Authorization']".to_string(),
+                    "'Bearer $value'".to_string(),
+                ),
+                intermediate::SecuritySchemeKind::OpenIdConnect { url } => (
+                    format!(
+                        "an access token of the OpenID Connect provider at {}, granting\n[scopes].",
+                        url
+                    ),
+                    "String",
+                    true,
+                    "headers['Authorization']".to_string(),
+                    "'Bearer $value'".to_string(),
+                ),
+            };
+            cpf!(
+                provider,
+                "  /// `{}`: {}",
+                scheme.name,
+                doc.replace("\n", "\n  /// ")
+            );
+            if let Some(description) = scheme.description {
+                cpf!(
+                    provider,
+                    "  ///\n  /// {}",
+                    description.replace("\n", "\n  /// ")
+                );
+            }
+            let scopes = if takes_scopes { "scopes" } else { "" };
+            cpf!(
+                provider,
+                "  FutureOr<{}?> {}({});\n",
+                typ,
+                method,
+                if takes_scopes {
+                    "List<String> scopes"
+                } else {
+                    ""
+                }
+            );
+            cpf!(
+                apply,
+                "    case {}:\n      final value = await provider.{}({});\n      if (value == null) return false;\n      credentials.{} = {};\n      return true;",
+                dart_string_literal(scheme.name),
+                method,
+                scopes,
+                target,
+                value
+            );
+        }
+        provider.truncate(provider.trim_end().len());
+        cpf!(provider, "\n}}\n");
+        cpf!(apply, "  }}\n  return false;\n}}");
+        provider.push_str(&apply);
+        provider
+    }
+
+    /// Generates the `BEAMResponseHeaders` subclass `BEAM{name}` with a typed
+    /// getter per header the responses of `endpoint` declare, and returns
+    /// its class name, or `None` if they declare none.
+    fn mk_response_headers(
+        &self,
+        endpoint: &intermediate::Endpoint,
+        name: &str,
+        depth: usize,
+        out: &mut String,
+        imports: &mut String,
+    ) -> Option<String> {
+        // header names are case-insensitive, the first declaration wins
+        let mut headers = BTreeMap::new();
+        for header in endpoint.response_headers.values().flatten() {
+            headers
+                .entry(header.name.to_ascii_lowercase())
+                .or_insert(header);
+        }
+        if headers.is_empty() {
+            return None;
+        }
+        let class_name = format!("BEAM{}", name);
+        let mut enums = String::new();
+        cpf!(
+            out,
+            "\n/// The declared headers of the responses, null if absent."
+        );
+        cpf!(out, "class {} extends BEAMResponseHeaders {{", class_name);
+        cpf!(out, "  {}(super.headers);", class_name);
+        for (key, header) in &headers {
+            let getter = create_property_name(header.name);
+            let typ = self.param_type(
+                &header.schema,
+                &format!("{}_{}", name, sanitize(header.name)),
+                depth,
+                &mut enums,
+                imports,
+            );
+            cpf!(out, "\n  /// `{}`", header.name);
+            if let Some(description) = header.description {
+                cpf!(
+                    out,
+                    "  ///\n  /// {}",
+                    description.replace("\n", "\n  /// ")
+                );
+            }
+            cpf!(
+                out,
+                "  {}? get {} => switch (raw[{}]) {{\n    final v? => {},\n    null => null,\n  }};",
+                typ.typ,
+                getter,
+                dart_string_literal(key),
+                self.decode_header(&header.schema, &typ, "v")
+            );
+        }
+        cpf!(out, "}}");
+        out.push_str(&enums);
+        Some(class_name)
+    }
+
+    /// Dart expression decoding the header string `expr` into a value of
+    /// `typ`, the [`ParamType`] of `schema`.
+    fn decode_header(&self, schema: &intermediate::IAST, typ: &ParamType, expr: &str) -> String {
+        use intermediate::{Primitive, IAST};
+        match &typ.encoding {
+            ParamEncoding::Primitive => {
+                // follow references to the primitive they alias
+                let mut schema = schema;
+                for _ in 0..self.intermediate.schemes.len() {
+                    let IAST::Reference(reference) = schema else {
+                        break;
+                    };
+                    match self
+                        .intermediate
+                        .find_scheme(strip_ref_prefix(reference.path))
+                    {
+                        Some(scheme) => schema = &scheme.obj,
+                        None => break,
+                    }
+                }
+                match schema {
+                    IAST::Primitive(prim) => match prim.value {
+                        Primitive::Integer => format!("int.parse({})", expr),
+                        Primitive::Number => format!("num.parse({})", expr),
+                        Primitive::Boolean => format!("bool.parse({})", expr),
+                        Primitive::String => expr.to_string(),
+                        _ => format!("jsonDecode({})", expr),
+                    },
+                    _ => expr.to_string(),
+                }
+            }
+            ParamEncoding::Enum => format!("{}.fromJson({})", typ.typ, expr),
+            ParamEncoding::Class => format!("{}.fromJson(jsonDecode({}))", typ.typ, expr),
+            ParamEncoding::Json => format!("jsonDecode({})", expr),
+            ParamEncoding::Format(codec) => codec.decode(expr, false),
+            // the elements of a list are comma separated (`simple` style)
+            ParamEncoding::List(inner) => {
+                let inner_schema = match schema {
+                    IAST::Primitive(prim) => match &prim.value {
+                        Primitive::List(inner_schema) => inner_schema.as_ref(),
+                        _ => schema,
+                    },
+                    _ => schema,
+                };
+                format!(
+                    "{}.split(',').map((e) => {}).toList()",
+                    expr,
+                    self.decode_header(inner_schema, inner, "e.trim()")
+                )
+            }
+        }
+    }
+
+    /// The name of the `BEAMServer` constructor of `server`.
+    fn server_constructor(&self, server: &intermediate::Server) -> &str {
+        self.servers
+            .iter()
+            .find(|(s, _)| s.url == server.url)
+            .map(|(_, ctor)| ctor.as_str())
+            .expect("every server has a constructor")
+    }
+
     /// Parse a single response IAST into its own
     /// `{name}/{method}.resp.{code}.schema.dart` schema file (plus any
     /// nested sub-files), appending them to `deps`, and classify the result
@@ -143,6 +1047,7 @@ impl<'a> EndpointAdder<'a> {
     /// whether the route imports the per-code file directly (single
     /// response) or imports the union that re-exports it (multiple
     /// responses).
+    #[allow(clippy::too_many_arguments)]
     fn emit_response_schema(
         &self,
         response_name: &str,
@@ -153,9 +1058,9 @@ impl<'a> EndpointAdder<'a> {
         depth: usize,
         deps: &mut Vec<File>,
     ) -> ResponseClass {
-        let parsed = self
-            .scheme_adder
-            .parse_named_iast(response_name, response, depth + 1);
+        let parsed =
+            self.response_scheme_adder
+                .parse_named_iast(response_name, response, depth + 1);
         let dep_path_str = format!("{}/{}.resp.{}.schema.dart", name, method_str, code);
         deps.push(File {
             path: std::path::PathBuf::from(&dep_path_str),
@@ -165,9 +1070,33 @@ impl<'a> EndpointAdder<'a> {
             path: std::path::PathBuf::from(format!("{}/{}", name, f.path.to_str().unwrap())),
             content: f.content,
         }));
-        match parsed.special_case {
+        self.response_class(
+            response_name,
+            response,
+            parsed.special_case,
+            parsed.is_binary,
+        )
+    }
+
+    /// Classifies `response`, parsed as `response_name`, into a
+    /// [`ResponseClass`] describing how the value is decoded.
+    fn response_class(
+        &self,
+        response_name: &str,
+        response: &intermediate::IAST,
+        special_case: Option<schemes::GenerationSpecialCase>,
+        is_binary: bool,
+    ) -> ResponseClass {
+        match self
+            .response_scheme_adder
+            .map_link_special_case(response, special_case)
+        {
             Some(schemes::GenerationSpecialCase { reason, type_name }) => ResponseClass {
                 type_str: type_name,
+                codec: match reason.collection() {
+                    Some(_) => self.response_scheme_adder.element_format_codec(response),
+                    None => self.response_scheme_adder.format_codec(response),
+                },
                 // Is the response value a raw primitive (no `.fromJson`)
                 // rather than a generated class? Three sub-cases: the IAST
                 // itself was primitive, it was a list whose elements were
@@ -178,24 +1107,24 @@ impl<'a> EndpointAdder<'a> {
                 // classifies enums as non-primitive.
                 is_primitive: match &reason {
                     GenerationSpecialCaseType::Primitive => true,
-                    GenerationSpecialCaseType::List(_, is_primitive) => *is_primitive,
+                    GenerationSpecialCaseType::List(_, is_primitive)
+                    | GenerationSpecialCaseType::Map(_, is_primitive) => *is_primitive,
                     GenerationSpecialCaseType::Link(link) => {
                         self.intermediate.resolve_ref(link).is_primitive()
                     }
                 },
-                list_inner_type: if let GenerationSpecialCaseType::List(inner_type, _) = reason {
-                    Some(inner_type)
-                } else {
-                    None
-                },
-                is_binary: parsed.is_binary,
+                collection: reason
+                    .collection()
+                    .map(|(collection, inner_type, _)| (collection, inner_type.clone())),
+                is_binary,
                 is_status_code_union: false,
             },
             None => ResponseClass {
                 type_str: self.scheme_adder.class_name(response_name),
                 is_primitive: false,
-                list_inner_type: None,
-                is_binary: parsed.is_binary,
+                collection: None,
+                codec: None,
+                is_binary,
                 is_status_code_union: false,
             },
         }
@@ -223,6 +1152,7 @@ impl<'a> EndpointAdder<'a> {
             "import '{}utils/serde.dart';",
             "../".repeat(depth + 1)
         );
+        cpf!(imports_str, "import 'dart:convert';");
         cpf!(imports_str, "import 'dart:typed_data';\n");
         cpf!(c, "/// {}", route.path);
         cpf!(
@@ -242,244 +1172,439 @@ impl<'a> EndpointAdder<'a> {
             sanitize(route.path)
         );
         for method in &route.endpoints {
-            let method_str = method.method.string();
-            let param_name = format!("_P_{}", method_str);
-            let body_class = match &method.request {
-                Some(request) => {
-                    //do things
-                    let request_name = format!("{}{}Request", name, method_str);
-                    let parsed =
-                        self.scheme_adder
-                            .parse_named_iast(&request_name, request, depth + 1);
-                    deps.extend(parsed.files.into_iter().map(|f| File {
-                        path: std::path::PathBuf::from(format!(
-                            "{}/{}",
-                            name,
-                            f.path.to_str().unwrap()
-                        )),
-                        content: f.content,
-                    }));
-                    let dep_path_str = format!("{}/{}.req.body.schema.dart", name, method_str);
-                    let dep_path = std::path::PathBuf::from(&dep_path_str);
-                    deps.push(File {
-                        path: dep_path,
-                        content: parsed.content,
-                    });
-                    imports_str.push_str(&format!("import '{}';\n", &dep_path_str));
-                    imports_str.push_str(&format!("export '{}';\n", &dep_path_str));
-                    // Classify the request body so the impl emission below
-                    // can pick the right "serialize before sending" form.
-                    // Three buckets:
-                    //   * primitive (no .toJson)  -> send `body` raw
-                    //   * single object/enum     -> send `body?.toJson()`
-                    //   * list of object/enum    -> send `body?.map((e) => e.toJson()).toList()`
-                    // For lists, we rely on the same `is_primitive` flag the
-                    // response side uses (set by `parse_named_iast` and now
-                    // chain-following via the shared `resolve_ref`), so
-                    // `List<Primitive>` and `List<$ref to primitive typedef>`
-                    // both bucket as primitive.
-                    match parsed.special_case {
-                        Some(schemes::GenerationSpecialCase { reason, type_name }) => BodyClass {
-                            decl: Some(format!(" {{required {} body}}", type_name)),
-                            is_primitive: match &reason {
-                                GenerationSpecialCaseType::List(_, is_primitive) => *is_primitive,
-                                GenerationSpecialCaseType::Primitive => true,
-                                // A link can point at a primitive
-                                // typedef (e.g. `typedef Foo = String;`);
-                                // in that case the body has no
-                                // `.toJson()` and must be passed as raw
-                                // JSON. Defer to the shared resolver so
-                                // chains of refs are followed too.
-                                GenerationSpecialCaseType::Link(link) => {
-                                    self.intermediate.resolve_ref(link).is_primitive()
-                                }
-                            },
-                            list_inner_type: match reason {
-                                GenerationSpecialCaseType::List(inner_type, _) => Some(inner_type),
-                                _ => None,
-                            },
-                        },
-                        _ => BodyClass {
-                            decl: Some(format!(
-                                " {{required {} body}}",
-                                self.scheme_adder.class_name(&request_name)
-                            )),
-                            is_primitive: false,
-                            list_inner_type: None,
-                        },
-                    }
-                }
-                None => BodyClass {
-                    decl: None,
-                    is_primitive: true,
-                    list_inner_type: None,
-                },
-            };
+            let verb = method.method.string();
+            let param_name = format!("_P_{}", verb);
             let params_str = if method.params.is_empty() {
                 String::new()
             } else {
                 format!("{} params,", param_name)
             };
-            let params = mk_params(&method.params, &param_name);
+            let params = self.mk_params(
+                &method.params,
+                &param_name,
+                &format!("{}_{}", name, verb),
+                depth,
+            );
+            imports_str.push_str(&params.imports);
 
-            let response_class = {
-                let responses = &method.responses;
-                if responses.is_empty() {
-                    ResponseClass {
-                        type_str: "()".to_string(),
-                        is_primitive: true,
-                        list_inner_type: None,
-                        is_binary: false,
-                        is_status_code_union: false,
+            param_typedef_strs.push_str(&params.typedef);
+            let headers_class = self.mk_response_headers(
+                method,
+                &format!("{}_{}ResponseHeaders", name, verb),
+                depth,
+                &mut param_typedef_strs,
+                &mut imports_str,
+            );
+            let security_args = match method.security.is_empty() {
+                true => String::new(),
+                false => format!(
+                    ", security: const [{}]",
+                    method
+                        .security
+                        .iter()
+                        .map(|requirement| format!(
+                            "{{{}}}",
+                            requirement
+                                .iter()
+                                .map(|(scheme, scopes)| format!(
+                                    "{}: [{}]",
+                                    dart_string_literal(scheme),
+                                    scopes
+                                        .iter()
+                                        .map(|scope| dart_string_literal(scope))
+                                        .collect::<Vec<_>>()
+                                        .join(", ")
+                                ))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            };
+            let servers_args = match method.servers.is_empty() {
+                true => String::new(),
+                false => format!(
+                    ", servers: [{}]",
+                    method
+                        .servers
+                        .iter()
+                        .map(|server| format!("BEAMServer.{}()", self.server_constructor(server)))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            };
+            // one Dart method per media type the endpoint offers, all of them
+            // sharing the params record
+            for variant in media_variants(method) {
+                let method_str = variant.name.as_str();
+                let media_args =
+                    format!("{}{}{}", variant.handle_args(), security_args, servers_args);
+                let form = variant
+                    .request
+                    .filter(|payload| {
+                        matches!(
+                            payload.format,
+                            PayloadFormat::MultipartForm | PayloadFormat::UrlEncodedForm
+                        )
+                    })
+                    .and_then(|payload| {
+                        self.mk_form(
+                            payload,
+                            &format!("_F_{}", method_str),
+                            &format!("{}_{}Form", name, method_str),
+                            depth,
+                        )
+                    });
+                let body_class = match (form, variant.request) {
+                    (Some(form), _) => {
+                        imports_str.push_str(&form.imports);
+                        param_typedef_strs.push_str(&form.typedef);
+                        BodyClass {
+                            decl: Some(format!(" {{required {} body}}", form.record_name)),
+                            is_primitive: false,
+                            collection: None,
+                            codec: None,
+                            form: Some(form.as_form_body),
+                        }
                     }
-                } else if responses.len() == 1 {
-                    // Single response: parse it into its own
-                    // `{method}.resp.{code}.schema.dart` and return that type
-                    // directly — no union wrapper is needed.
-                    let (code, response) = responses.first_key_value().unwrap();
-                    let response_name = format!("{}_{}Response", name, method_str);
-                    let response_class = self.emit_response_schema(
-                        &response_name,
-                        code,
-                        response,
-                        name,
-                        &method_str,
-                        depth,
-                        &mut deps,
-                    );
-                    let dep_path_str = format!("{}/{}.resp.{}.schema.dart", name, method_str, code);
-                    imports_str.push_str(&format!("import '{}';\n", &dep_path_str));
-                    imports_str.push_str(&format!("export '{}';\n", &dep_path_str));
-                    response_class
-                } else {
-                    // Multiple responses: parse each status code into its own
-                    // `{method}.resp.{code}.schema.dart` + `{name}_{method}_
-                    // {code}Response` class, then generate one "super" union
-                    // over them. Keying the union arms by status code (rather
-                    // than by resolved schema name) means codes that share a
-                    // schema — e.g. several error codes pointing at one error
-                    // model — stay distinct instead of colliding.
-                    let super_name = format!("{}_{}Response", name, method_str);
-                    let mut variants = Vec::with_capacity(responses.len());
-                    for (code, response) in responses.iter() {
-                        let response_name = format!("{}_{}_{}Response", name, method_str, code);
-                        let rc = self.emit_response_schema(
+                    (
+                        None,
+                        Some(intermediate::Payload {
+                            schema: request, ..
+                        }),
+                    ) => {
+                        //do things
+                        let request_name = format!("{}{}Request", name, method_str);
+                        let parsed = self.request_scheme_adder.parse_named_iast(
+                            &request_name,
+                            request,
+                            depth + 1,
+                        );
+                        deps.extend(parsed.files.into_iter().map(|f| File {
+                            path: std::path::PathBuf::from(format!(
+                                "{}/{}",
+                                name,
+                                f.path.to_str().unwrap()
+                            )),
+                            content: f.content,
+                        }));
+                        let dep_path_str = format!("{}/{}.req.body.schema.dart", name, method_str);
+                        let dep_path = std::path::PathBuf::from(&dep_path_str);
+                        deps.push(File {
+                            path: dep_path,
+                            content: parsed.content,
+                        });
+                        imports_str.push_str(&format!("import '{}';\n", &dep_path_str));
+                        imports_str.push_str(&format!("export '{}';\n", &dep_path_str));
+                        // Classify the request body so the impl emission below
+                        // can pick the right "serialize before sending" form.
+                        // Three buckets:
+                        //   * primitive (no .toJson)  -> send `body` raw
+                        //   * single object/enum     -> send `body?.toJson()`
+                        //   * list of object/enum    -> send `body?.map((e) => e.toJson()).toList()`
+                        // For lists, we rely on the same `is_primitive` flag the
+                        // response side uses (set by `parse_named_iast` and now
+                        // chain-following via the shared `resolve_ref`), so
+                        // `List<Primitive>` and `List<$ref to primitive typedef>`
+                        // both bucket as primitive.
+                        match self
+                            .request_scheme_adder
+                            .map_link_special_case(request, parsed.special_case)
+                        {
+                            Some(schemes::GenerationSpecialCase { reason, type_name }) => {
+                                BodyClass {
+                                    decl: Some(format!(" {{required {} body}}", type_name)),
+                                    codec: match reason.collection() {
+                                        Some(_) => {
+                                            self.request_scheme_adder.element_format_codec(request)
+                                        }
+                                        None => self.request_scheme_adder.format_codec(request),
+                                    },
+                                    is_primitive: match &reason {
+                                        GenerationSpecialCaseType::List(_, is_primitive)
+                                        | GenerationSpecialCaseType::Map(_, is_primitive) => {
+                                            *is_primitive
+                                        }
+                                        GenerationSpecialCaseType::Primitive => true,
+                                        // A link can point at a primitive
+                                        // typedef (e.g. `typedef Foo = String;`);
+                                        // in that case the body has no
+                                        // `.toJson()` and must be passed as raw
+                                        // JSON. Defer to the shared resolver so
+                                        // chains of refs are followed too.
+                                        GenerationSpecialCaseType::Link(link) => {
+                                            self.intermediate.resolve_ref(link).is_primitive()
+                                        }
+                                    },
+                                    collection: reason.collection().map(
+                                        |(collection, inner_type, _)| {
+                                            (collection, inner_type.clone())
+                                        },
+                                    ),
+                                    form: None,
+                                }
+                            }
+                            _ => BodyClass {
+                                decl: Some(format!(
+                                    " {{required {} body}}",
+                                    self.scheme_adder.class_name(&request_name)
+                                )),
+                                is_primitive: false,
+                                collection: None,
+                                codec: None,
+                                form: None,
+                            },
+                        }
+                    }
+                    (None, None) => BodyClass {
+                        decl: None,
+                        is_primitive: true,
+                        collection: None,
+                        codec: None,
+                        form: None,
+                    },
+                };
+
+                let response_class = {
+                    let responses = &variant.responses;
+                    if responses.is_empty() {
+                        ResponseClass {
+                            type_str: "()".to_string(),
+                            is_primitive: true,
+                            collection: None,
+                            codec: None,
+                            is_binary: false,
+                            is_status_code_union: false,
+                        }
+                    } else if responses.len() == 1 {
+                        // Single response: parse it into its own
+                        // `{method}.resp.{code}.schema.dart` and return that type
+                        // directly — no union wrapper is needed.
+                        let (code, response) = responses.first_key_value().unwrap();
+                        let response_name = format!("{}_{}Response", name, method_str);
+                        let response_class = self.emit_response_schema(
                             &response_name,
                             code,
                             response,
                             name,
-                            &method_str,
+                            method_str,
                             depth,
                             &mut deps,
                         );
-                        variants.push(schemes::ResponseUnionVariant {
-                            code: code.to_string(),
-                            value_type: rc.type_str,
-                            is_primitive: rc.is_primitive,
-                            list_inner_type: rc.list_inner_type,
-                            // Sibling of the union file in the same directory.
-                            import_path: format!("{}.resp.{}.schema.dart", method_str, code),
+                        let dep_path_str =
+                            format!("{}/{}.resp.{}.schema.dart", name, method_str, code);
+                        imports_str.push_str(&format!("import '{}';\n", &dep_path_str));
+                        imports_str.push_str(&format!("export '{}';\n", &dep_path_str));
+                        response_class
+                    } else {
+                        // Multiple responses: parse each status code into its own
+                        // `{method}.resp.{code}.schema.dart` + `{name}_{method}_
+                        // {code}Response` class, then generate one "super" union
+                        // over them. Keying the union arms by status code (rather
+                        // than by resolved schema name) means codes that share a
+                        // schema — e.g. several error codes pointing at one error
+                        // model — stay distinct instead of colliding.
+                        let super_name = format!("{}_{}Response", name, method_str);
+                        let mut variants = Vec::with_capacity(responses.len());
+                        for (code, response) in responses.iter() {
+                            let response_name = format!("{}_{}_{}Response", name, method_str, code);
+                            let rc = self.emit_response_schema(
+                                &response_name,
+                                code,
+                                response,
+                                name,
+                                method_str,
+                                depth,
+                                &mut deps,
+                            );
+                            variants.push(schemes::ResponseUnionVariant {
+                                code: code.to_string(),
+                                value_type: rc.type_str,
+                                is_primitive: rc.is_primitive,
+                                collection: rc.collection,
+                                codec: rc.codec,
+                                // Sibling of the union file in the same directory.
+                                import_path: format!("{}.resp.{}.schema.dart", method_str, code),
+                            });
+                        }
+                        let union_content = self.scheme_adder.generate_response_union(
+                            &super_name,
+                            &variants,
+                            depth + 1,
+                        );
+                        let union_path = format!("{}/{}.resp.schema.dart", name, method_str);
+                        deps.push(File {
+                            path: std::path::PathBuf::from(&union_path),
+                            content: union_content,
                         });
+                        imports_str.push_str(&format!("import '{}';\n", &union_path));
+                        imports_str.push_str(&format!("export '{}';\n", &union_path));
+                        ResponseClass {
+                            type_str: self.scheme_adder.class_name(&super_name),
+                            is_primitive: false,
+                            collection: None,
+                            codec: None,
+                            is_binary: false,
+                            is_status_code_union: true,
+                        }
                     }
-                    let union_content = self.scheme_adder.generate_response_union(
-                        &super_name,
-                        &variants,
-                        depth + 1,
-                    );
-                    let union_path = format!("{}/{}.resp.schema.dart", name, method_str);
-                    deps.push(File {
-                        path: std::path::PathBuf::from(&union_path),
-                        content: union_content,
-                    });
-                    imports_str.push_str(&format!("import '{}';\n", &union_path));
-                    imports_str.push_str(&format!("export '{}';\n", &union_path));
-                    ResponseClass {
-                        type_str: self.scheme_adder.class_name(&super_name),
-                        is_primitive: false,
-                        list_inner_type: None,
-                        is_binary: false,
-                        is_status_code_union: true,
-                    }
-                }
-            };
-            let impl_str = {
-                let mut s = String::new();
-                s.push_str(&format!(
-                    "\n\t\t{}",
-                    params.as_json_body.replace("\n", "\n\t\t")
-                ));
-                let body_emission: String = match (
-                    &body_class.decl,
-                    body_class.is_primitive,
-                    &body_class.list_inner_type,
-                ) {
-                    // No body: send `null`.
-                    (None, _, _) => "null".to_string(),
-                    // Primitive body (single primitive OR `List<Primitive>`):
-                    // send raw, no serialization step needed.
-                    (Some(_), true, _) => "body".to_string(),
-                    // Non-primitive list body (`List<Object>` /
-                    // `List<Enum>`): Dart's built-in `List` has no
-                    // `.toJson()`, so we must serialize element-by-element.
-                    (Some(_), false, Some(_)) => {
-                        "body?.map((e) => e.toJson()).toList()".to_string()
-                    }
-                    // Single non-primitive body: call .toJson() directly.
-                    (Some(_), false, None) => "body?.toJson()".to_string(),
-                };
-                let expected_response_type = match response_class.is_binary {
-                    true => "BEAMExpectedResponseType.binary",
-                    false => "BEAMExpectedResponseType.json",
                 };
-                if response_class.is_status_code_union {
-                    // Multi-status union: thread the (optional) HTTP status
-                    // code through the handler via a `BeamStatusCodeRef`. When
-                    // the handler is status-code aware it fills the ref and we
-                    // decode the exact variant via `fromCode`; otherwise the
-                    // ref stays null and we fall back to the (discouraged)
-                    // `fromJson`.
-                    let t = &response_class.type_str;
+                let (impl_str, with_headers_impl) = {
+                    let mut s = String::new();
                     s.push_str(&format!(
-                        "final statusCodeRef = BeamStatusCodeRef();\n\t\treturn handleCached(method: BEAMRequestMethod.{method}, params: paramsJson, body: {body}, expectedResponseType: {expected}, statusCodeRef: statusCodeRef).then((json) {{\n\t\t\tfinal statusCode = statusCodeRef.statusCode;\n\t\t\treturn statusCode != null ? ({t}.fromCode(statusCode, json) ?? {t}.fromJson(json)) : {t}.fromJson(json);\n\t\t}});\n",
-                        method = method_str,
-                        body = body_emission,
-                        expected = expected_response_type,
-                        t = t,
+                        "\n\t\t{}",
+                        params.as_json_body.replace("\n", "\n\t\t")
                     ));
-                } else {
-                    cpf!(s, "return handleCached(method: BEAMRequestMethod.{}, params: paramsJson, body: {}, expectedResponseType: {}).then((json) => {});", method_str, body_emission, expected_response_type, match (response_class.is_primitive, &response_class.list_inner_type) {
-                        (true, None) => "json".to_string(),
-                        (true, Some(_)) => format!("json"),
-                        (false, Some(inner_type)) => format!("(json as List).map((e) => {}.fromJson(e)).toList()", inner_type),
-                        (false, None) => format!("{}.fromJson(json)", response_class.type_str),
+                    if let Some(form) = &body_class.form {
+                        s.push_str(&form.replace("\n", "\n\t\t"));
+                    }
+                    let body_emission: String = match (
+                        &body_class.decl,
+                        body_class.is_primitive,
+                        &body_class.collection,
+                        body_class.codec,
+                    ) {
+                        // No body: send `null`.
+                        (None, _, _, _) => "null".to_string(),
+                        // Form body: send what the form was turned into.
+                        _ if body_class.form.is_some() => "formBody".to_string(),
+                        // Formatted primitive body (or collection thereof):
+                        // send its JSON string(s).
+                        (Some(_), _, Some((collection, _)), Some(codec)) => {
+                            collection.encode("body", &codec.encode("e", false))
+                        }
+                        (Some(_), _, None, Some(codec)) => codec.encode("body", false),
+                        // Primitive body (single primitive OR `List<Primitive>`):
+                        // send raw, no serialization step needed.
+                        (Some(_), true, _, None) => "body".to_string(),
+                        // Non-primitive collection body (`List<Object>` /
+                        // `Map<String, Enum>`): Dart's built-in collections
+                        // have no `.toJson()`, so we must serialize
+                        // element-by-element.
+                        (Some(_), false, Some((collection, _)), None) => {
+                            collection.encode("body?", "e.toJson()")
+                        }
+                        // Single non-primitive body: call .toJson() directly.
+                        (Some(_), false, None, None) => "body?.toJson()".to_string(),
+                    };
+                    let expected_response_type = match response_class.is_binary {
+                        true => "BEAMExpectedResponseType.binary",
+                        false => "BEAMExpectedResponseType.json",
+                    };
+                    let t = &response_class.type_str;
+                    let decode = match response_class.is_status_code_union {
+                        true => format!("switch (statusCodeRef.statusCode) {{\n\t\t\tfinal statusCode? => {t}.fromCode(statusCode, json) ?? {t}.fromJson(json),\n\t\t\tnull => {t}.fromJson(json),\n\t\t}}", t = t),
+                        false => response_class.decode("json"),
+                    };
+                    // the same request, but decoding the headers as well
+                    let with_headers = headers_class.as_ref().map(|headers_class| {
+                        format!(
+                            "{}final statusCodeRef = BeamStatusCodeRef();\n\t\treturn handleCached(method: BEAMRequestMethod.{}, params: paramsJson, body: {}{}, expectedResponseType: {}, statusCodeRef: statusCodeRef).then((json) => BEAMWithHeaders({}, {}(statusCodeRef.headers ?? const {{}})));\n",
+                            s, verb, body_emission, media_args, expected_response_type, decode, headers_class
+                        )
                     });
-                }
-                s
-            };
+                    if response_class.is_status_code_union {
+                        // Multi-status union: thread the (optional) HTTP status
+                        // code through the handler via a `BeamStatusCodeRef`. When
+                        // the handler is status-code aware it fills the ref and we
+                        // decode the exact variant via `fromCode`; otherwise the
+                        // ref stays null and we fall back to the (discouraged)
+                        // `fromJson`.
+                        s.push_str(&format!(
+                            "final statusCodeRef = BeamStatusCodeRef();\n\t\treturn handleCached(method: BEAMRequestMethod.{method}, params: paramsJson, body: {body}{media}, expectedResponseType: {expected}, statusCodeRef: statusCodeRef).then((json) {{\n\t\t\tfinal statusCode = statusCodeRef.statusCode;\n\t\t\treturn statusCode != null ? ({t}.fromCode(statusCode, json) ?? {t}.fromJson(json)) : {t}.fromJson(json);\n\t\t}});\n",
+                            method = verb,
+                            body = body_emission,
+                            media = media_args,
+                            expected = expected_response_type,
+                            t = t,
+                        ));
+                    } else {
+                        cpf!(s, "return handleCached(method: BEAMRequestMethod.{}, params: paramsJson, body: {}{}, expectedResponseType: {}).then((json) => {});", verb, body_emission, media_args, expected_response_type, decode);
+                    }
+                    (s, with_headers)
+                };
 
-            param_typedef_strs.push_str(&params.typedef);
-            cpf!(
-                c,
-                "\n\t///{}\n\t///",
-                method.summary.unwrap_or("").replace("\n", "\n\t/// ")
-            );
-            cpf!(
-                c,
-                "\t///{}",
-                method.description.unwrap_or("").replace("\n", "\n\t/// ")
-            );
-            cpf!(
-                c,
-                "  BEAMCachedResponse<{}> {}({}{}){{{}\t}}",
-                response_class.type_str,
-                method_str,
-                params_str,
-                match &body_class.decl {
-                    Some(decl) => decl,
-                    None => "",
-                },
-                impl_str
-            );
+                cpf!(
+                    c,
+                    "\n\t///{}\n\t///",
+                    method.summary.unwrap_or("").replace("\n", "\n\t/// ")
+                );
+                cpf!(
+                    c,
+                    "\t///{}",
+                    method.description.unwrap_or("").replace("\n", "\n\t/// ")
+                );
+                if let Some(content_type) = variant.content_type {
+                    cpf!(c, "\t/// Sends the body as `{}`.", content_type);
+                }
+                if let Some(accept) = &variant.accept {
+                    cpf!(c, "\t/// Accepts `{}`.", accept);
+                }
+                if let Some(server) = method.servers.first() {
+                    cpf!(
+                        c,
+                        "\t/// Sent to `{}` rather than the API's servers.",
+                        server.url
+                    );
+                }
+                if !method.security.is_empty() {
+                    cpf!(
+                        c,
+                        "\t/// Requires {}.",
+                        method
+                            .security
+                            .iter()
+                            .map(|requirement| match requirement.is_empty() {
+                                true => "no credentials".to_string(),
+                                false => requirement
+                                    .iter()
+                                    .map(|(scheme, scopes)| match scopes.is_empty() {
+                                        true => format!("`{}`", scheme),
+                                        false => format!("`{}` ({})", scheme, scopes.join(", ")),
+                                    })
+                                    .collect::<Vec<_>>()
+                                    .join(" and "),
+                            })
+                            .collect::<Vec<_>>()
+                            .join(" or ")
+                    );
+                }
+                cpf!(
+                    c,
+                    "  BEAMCachedResponse<{}> {}({}{}){{{}\t}}",
+                    response_class.type_str,
+                    method_str,
+                    params_str,
+                    match &body_class.decl {
+                        Some(decl) => decl,
+                        None => "",
+                    },
+                    impl_str
+                );
+                if let (Some(headers_class), Some(with_headers_impl)) =
+                    (&headers_class, with_headers_impl)
+                {
+                    cpf!(
+                        c,
+                        "\n\t/// Like [{}], but with the response's headers as well.",
+                        method_str
+                    );
+                    cpf!(
+                        c,
+                        "  BEAMCachedResponse<BEAMWithHeaders<{}, {}>> {}WithHeaders({}{}){{{}\t}}",
+                        response_class.type_str,
+                        headers_class,
+                        method_str,
+                        params_str,
+                        match &body_class.decl {
+                            Some(decl) => decl,
+                            None => "",
+                        },
+                        with_headers_impl
+                    );
+                }
+            }
         }
         cpf!(c, "}}\n");
         c.push_str(&param_typedef_strs);
@@ -518,20 +1643,33 @@ impl<'a> EndpointAdder<'a> {
                 class_name = format!("BEAM{}Frag_{}", name, sanitized_frag_name);
 
                 let sub_dir_name = format!("{}_frags", sanitized_frag_name);
+                let mut param_enums = String::new();
+                let param =
+                    self.fragment_param(&name, node, depth, &mut param_enums, &mut imports_str);
+                if let Some((param_type, param)) = &param {
+                    cpf!(s, "/// {{{}}}: {}", param.name, param_type.typ);
+                    if let Some(description) = param.description {
+                        cpf!(s, "/// {}", description.replace("\n", "\n/// "));
+                    }
+                }
                 cpf!(s, "class {} extends BEAMWithParent {{", class_name);
                 cpf!(
                     s,
                     "\t{}({{required super.deps, required super.parent{}}}) : super(ownFragment: {});\n",
                     class_name,
-                    if node.is_param {
-                        ", required String param"
-                    } else {
-                        ""
+                    match (&param, node.is_param) {
+                        (Some((param_type, _)), _) => format!(", required {} param", param_type.typ),
+                        (None, true) => ", required String param".to_string(),
+                        (None, false) => String::new(),
                     },
-                    if node.is_param {
-                        "param".to_string()
-                    } else {
-                        format!("'{}'", node.path_fragment_name)
+                    // path params are percent-encoded, as they end up verbatim
+                    // in the interpolated path
+                    match (&param, node.is_param) {
+                        (Some((param_type, _)), _) => {
+                            format!("Uri.encodeComponent({})", param_type.encode("param"))
+                        }
+                        (None, true) => "Uri.encodeComponent(param)".to_string(),
+                        (None, false) => format!("'{}'", node.path_fragment_name),
                     }
                 );
                 if is_root {
@@ -552,12 +1690,40 @@ impl<'a> EndpointAdder<'a> {
                                 // valid identifier (no leading digit).
                                 let child_getter_name = sanitize_identifier(child_frag_name);
                                 if child_node.is_param {
+                                    // the enum classes of inline enum params
+                                    // live in the child's own file
+                                    let child_param = self.fragment_param(
+                                        &sanitize(&child_name),
+                                        child_node,
+                                        depth,
+                                        &mut String::new(),
+                                        &mut imports_str,
+                                    );
+                                    let (param_typ, param_ident) = match &child_param {
+                                        Some((param_type, param)) => {
+                                            if let Some(description) = param.description {
+                                                cpf!(
+                                                    s,
+                                                    "\t/// {}",
+                                                    description.replace("\n", "\n\t/// ")
+                                                );
+                                            }
+                                            (
+                                                param_type.typ.clone(),
+                                                create_property_name(param.name),
+                                            )
+                                        }
+                                        None => ("String".to_string(), "param".to_string()),
+                                    };
                                     cpf!(
                                         s,
-                                        "\t{} {}(String param) => {}(parent: this, param: param, deps: this.deps);",
+                                        "\t{} {}({} {}) => {}(parent: this, param: {}, deps: this.deps);",
                                         child_frag.class_name,
                                         child_getter_name,
-                                        child_frag.class_name
+                                        param_typ,
+                                        param_ident,
+                                        child_frag.class_name,
+                                        param_ident
                                     );
                                 } else {
                                     cpf!(
@@ -598,6 +1764,7 @@ impl<'a> EndpointAdder<'a> {
                     imports_str.push_str(&format!("export '{}';\n", child_file_name));
                 }
                 cpf!(s, "}}\n");
+                s.push_str(&param_enums);
             }
             RouteFragment::Leaf(RouteFragmentLeafData { route_idx }) => {
                 let route = &(routes[*route_idx]);
@@ -617,48 +1784,419 @@ impl<'a> EndpointAdder<'a> {
             files: deps,
         }
     }
-}
 
-fn mk_params(params: &[intermediate::Param], name: &str) -> ParamsCode {
-    let mut s_typedef = String::new();
-    let mut s_as_json_body = String::new();
-    cpf!(s_typedef, "typedef {} = (", name);
-    cpf!(s_as_json_body, "final Map<String, String> paramsJson = {{");
-    if !params.is_empty() {
+    /// Builds the `_P_<method>` params record typedef and the `paramsJson`
+    /// map for an endpoint. Every param is typed after its schema (see
+    /// [`Self::param_type`]); `enum_prefix` names the enum classes generated
+    /// for inline enum params.
+    fn mk_params(
+        &self,
+        params: &[intermediate::Param],
+        name: &str,
+        enum_prefix: &str,
+        depth: usize,
+    ) -> ParamsCode {
+        let mut s_typedef = String::new();
+        let mut s_as_json_body = String::new();
+        let mut s_enums = String::new();
+        let mut s_imports = String::new();
+        cpf!(s_typedef, "typedef {} = (", name);
+        cpf!(s_as_json_body, "final Map<String, String> paramsJson = {{");
+        if !params.is_empty() {
+            cpf!(s_typedef, "{{");
+            for p in params {
+                let p_ident = create_property_name(p.name);
+                let param_type = self.param_type(
+                    &p.schema,
+                    &format!("{}_{}", enum_prefix, sanitize(p.name)),
+                    depth,
+                    &mut s_enums,
+                    &mut s_imports,
+                );
+                // records can't have default values, so a missing optional
+                // param falls back to its default when sent
+                let default = match p.required {
+                    true => None,
+                    false => self
+                        .scheme_adder
+                        .default_literal(&p.schema, &param_type.typ),
+                };
+                cpf!(
+                    s_typedef,
+                    "  /// {}",
+                    p.description.unwrap_or("").replace("\n", "\n  /// ")
+                );
+                if let Some(default) = &default {
+                    cpf!(s_typedef, "  ///\n  /// Defaults to `{}`.", default);
+                }
+                cpf!(
+                    s_typedef,
+                    "  {}{} {},",
+                    param_type.typ,
+                    if p.required { "" } else { "?" },
+                    p_ident
+                );
+                let value = match (&default, p.required) {
+                    (Some(default), _) => format!("(params.{} ?? {})", p_ident, default),
+                    (None, true) => format!("params.{}", p_ident),
+                    (None, false) => format!("params.{}!", p_ident),
+                };
+                cpf!(
+                    s_as_json_body,
+                    "  {}'{}': {},",
+                    if p.required || default.is_some() {
+                        String::new()
+                    } else {
+                        format!("if (params.{} != null) ", p_ident)
+                    },
+                    p.name.replace("$", "\\$"),
+                    param_type.encode_param(&value, p.style)
+                );
+            }
+            cpf!(s_typedef, "}}");
+        }
+        cpf!(s_typedef, ");\n");
+        cpf!(s_as_json_body, "}};");
+        s_typedef.push_str(&s_enums);
+        ParamsCode {
+            typedef: s_typedef,
+            as_json_body: s_as_json_body,
+            imports: s_imports,
+        }
+    }
+
+    /// Builds the `_F_<method>` record typedef for a form body and the
+    /// statements turning it into the body sent to the handler: a
+    /// `BEAMMultipartBody` for `multipart/form-data`, a `BEAMUrlEncodedBody`
+    /// for `application/x-www-form-urlencoded`. Properties are typed like
+    /// params. In multipart forms binary properties become
+    /// `BEAMMultipartFile`s and every element of an array is a part of its
+    /// own; urlencoded forms serialize arrays and objects after the
+    /// property's `encoding`. `readOnly` properties are left out. `None` if
+    /// the body is no object with properties, which is then sent like any
+    /// other body.
+    fn mk_form(
+        &self,
+        payload: &intermediate::Payload,
+        record_name: &str,
+        enum_prefix: &str,
+        depth: usize,
+    ) -> Option<FormCode> {
+        let is_multipart = payload.format == PayloadFormat::MultipartForm;
+        let properties = self.form_properties(&payload.schema)?;
+        // forms are only ever sent, so `readOnly` properties never belong in them
+        let mut names: Vec<&&str> = properties
+            .iter()
+            .filter(|(_, iast)| !iast.is_read_only())
+            .map(|(name, _)| name)
+            .collect();
+        names.sort();
+        let mut s_typedef = String::new();
+        let mut s_enums = String::new();
+        let mut s_imports = String::new();
+        let mut s_fields = String::new();
+        let mut s_files = String::new();
+        cpf!(s_typedef, "typedef {} = (", record_name);
         cpf!(s_typedef, "{{");
-        for p in params {
-            let p_ident = create_property_name(p.name);
-            cpf!(
-                s_typedef,
-                "  /// {}",
-                p.description.unwrap_or("").replace("\n", "\n  /// ")
+        for name in names {
+            let iast = &properties[*name];
+            let ident = create_property_name(name);
+            let part_name = name.replace("$", "\\$");
+            let optional = iast_is_optional(iast);
+            let value = format!("body.{}{}", ident, if optional { "!" } else { "" });
+            let condition = if optional {
+                format!("if (body.{} != null) ", ident)
+            } else {
+                String::new()
+            };
+            let default_encoding = intermediate::PropertyEncoding::default();
+            let encoding = payload.encoding.get(*name).unwrap_or(&default_encoding);
+            let file = |expr: &str| match encoding.content_type {
+                Some(content_type) => {
+                    format!("{}.withFallbackContentType('{}')", expr, content_type)
+                }
+                None => expr.to_string(),
+            };
+            let style_args = format!(
+                "style: BEAMFormStyle.{}, explode: {}",
+                match encoding.style {
+                    FormStyle::Form => "form",
+                    FormStyle::SpaceDelimited => "spaceDelimited",
+                    FormStyle::PipeDelimited => "pipeDelimited",
+                    FormStyle::DeepObject => "deepObject",
+                },
+                encoding.explode
             );
+            let typ = match iast {
+                _ if is_multipart && self.resolves_to_binary(iast) => {
+                    cpf!(
+                        s_files,
+                        "\t{}MapEntry('{}', {}),",
+                        condition,
+                        part_name,
+                        file(&value)
+                    );
+                    "BEAMMultipartFile".to_string()
+                }
+                intermediate::IAST::Primitive(intermediate::AnnotatedObj {
+                    value: intermediate::Primitive::List(inner),
+                    ..
+                }) if is_multipart && self.resolves_to_binary(inner) => {
+                    cpf!(
+                        s_files,
+                        "\t{}for (final e in {}) MapEntry('{}', {}),",
+                        condition,
+                        value,
+                        part_name,
+                        file("e")
+                    );
+                    "List<BEAMMultipartFile>".to_string()
+                }
+                _ => {
+                    let param_type = self.param_type(
+                        iast,
+                        &format!("{}_{}", enum_prefix, sanitize(name)),
+                        depth,
+                        &mut s_enums,
+                        &mut s_imports,
+                    );
+                    match (&param_type.encoding, is_multipart) {
+                        // every element of an array is a part of its own
+                        (ParamEncoding::List(inner), true) => {
+                            cpf!(
+                                s_fields,
+                                "\t{}for (final e in {}) MapEntry('{}', {}),",
+                                condition,
+                                value,
+                                part_name,
+                                inner.encode("e")
+                            );
+                        }
+                        (ParamEncoding::List(inner), false) => {
+                            cpf!(
+                                s_fields,
+                                "\t{}...BEAMUrlEncodedBody.array('{}', {}.map((e) => {}), {}),",
+                                condition,
+                                part_name,
+                                value,
+                                inner.encode("e"),
+                                style_args
+                            );
+                        }
+                        (ParamEncoding::Class, false) => {
+                            cpf!(
+                                s_fields,
+                                "\t{}...BEAMUrlEncodedBody.object('{}', {}.toJson(), {}),",
+                                condition,
+                                part_name,
+                                value,
+                                style_args
+                            );
+                        }
+                        _ => {
+                            cpf!(
+                                s_fields,
+                                "\t{}MapEntry('{}', {}),",
+                                condition,
+                                part_name,
+                                param_type.encode(&value)
+                            );
+                        }
+                    }
+                    param_type.typ
+                }
+            };
+            if let Some(description) = iast_description(iast) {
+                cpf!(s_typedef, "  /// {}", description.replace("\n", "\n  /// "));
+            }
             cpf!(
                 s_typedef,
-                "  String{} {},",
-                if p.required { "" } else { "?" },
-                p_ident
-            );
-            cpf!(
-                s_as_json_body,
-                "  {}'{}': params.{}{},",
-                if p.required {
-                    String::new()
-                } else {
-                    format!("if (params.{} != null) ", p_ident)
-                },
-                p.name.replace("$", "\\$"),
-                p_ident,
-                if p.required { "" } else { "!" }
+                "  {}{} {},",
+                typ,
+                if optional { "?" } else { "" },
+                ident
             );
         }
         cpf!(s_typedef, "}}");
+        cpf!(s_typedef, ");\n");
+        s_typedef.push_str(&s_enums);
+        let mut s_form_body = String::new();
+        if is_multipart {
+            cpf!(s_form_body, "final formBody = BEAMMultipartBody(fields: [");
+            s_form_body.push_str(&s_fields);
+            cpf!(s_form_body, "], files: [");
+            s_form_body.push_str(&s_files);
+            cpf!(s_form_body, "]);");
+        } else {
+            cpf!(s_form_body, "final formBody = BEAMUrlEncodedBody([");
+            s_form_body.push_str(&s_fields);
+            cpf!(s_form_body, "]);");
+        }
+        Some(FormCode {
+            record_name: record_name.to_string(),
+            typedef: s_typedef,
+            as_form_body: s_form_body,
+            imports: s_imports,
+        })
+    }
+
+    /// The properties of an object schema, following references.
+    fn form_properties<'i>(
+        &'i self,
+        iast: &'i intermediate::IAST<'a>,
+    ) -> Option<&'i HashMap<&'a str, intermediate::IAST<'a>>> {
+        use intermediate::{AlgType, AnnotatedObj, IAST};
+        let mut current = iast;
+        // bounded, as references may be cyclic
+        for _ in 0..=self.intermediate.schemes.len() {
+            match current {
+                IAST::Object(AnnotatedObj {
+                    value: AlgType::Product(properties, _),
+                    ..
+                }) => return Some(properties),
+                IAST::Reference(reference) => {
+                    current = &self.intermediate.find_scheme(reference.path)?.obj;
+                }
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    /// Whether `iast` is (a reference to) a `format: binary` string.
+    fn resolves_to_binary(&self, iast: &intermediate::IAST) -> bool {
+        use intermediate::{Primitive, IAST};
+        let mut current = iast;
+        for _ in 0..=self.intermediate.schemes.len() {
+            match current {
+                IAST::Primitive(prim) => return matches!(prim.value, Primitive::Binary),
+                IAST::Reference(reference) => match self.intermediate.find_scheme(reference.path) {
+                    Some(scheme) => current = &scheme.obj,
+                    None => return false,
+                },
+                IAST::Object(_) => return false,
+            }
+        }
+        false
+    }
+
+    /// The declared path param of a fragment `node` (if any) together with
+    /// its Dart type. `owner_name` is the (sanitized) name the fragment's
+    /// own class is generated under, so that the parent's accessor and the
+    /// fragment's file agree on the name of inline enum classes.
+    fn fragment_param(
+        &self,
+        owner_name: &str,
+        node: &intermediate::RouteFragmentNodeData,
+        depth: usize,
+        enums: &mut String,
+        imports: &mut String,
+    ) -> Option<(ParamType, &'a intermediate::Param<'a>)> {
+        let param = self.intermediate.path_param(node.param.as_ref()?);
+        let param_type = self.param_type(
+            &param.schema,
+            &format!(
+                "{}Frag_{}Param",
+                owner_name,
+                sanitize(&node.path_fragment_name)
+            ),
+            depth,
+            enums,
+            imports,
+        );
+        Some((param_type, param))
     }
-    cpf!(s_typedef, ");\n");
-    cpf!(s_as_json_body, "}};");
-    ParamsCode {
-        typedef: s_typedef,
-        as_json_body: s_as_json_body,
+
+    /// Maps a param schema to its Dart type. Inline enums get their own enum
+    /// class (appended to `enums`), referenced schemes are imported (into
+    /// `imports`) and used by their generated class name.
+    fn param_type(
+        &self,
+        iast: &intermediate::IAST,
+        name: &str,
+        depth: usize,
+        enums: &mut String,
+        imports: &mut String,
+    ) -> ParamType {
+        use intermediate::{Primitive, ResolvedRef, IAST};
+        match iast {
+            IAST::Primitive(prim) => match &prim.value {
+                Primitive::String | Primitive::Number | Primitive::Integer | Primitive::Boolean => {
+                    ParamType {
+                        typ: schemes::to_dart_prim(&prim.value),
+                        encoding: ParamEncoding::Primitive,
+                    }
+                }
+                Primitive::Enum(allowed_values) => {
+                    let enum_code = self.scheme_adder.generate_primitive_sum_type(
+                        name,
+                        "",
+                        &allowed_values
+                            .iter()
+                            .map(|v| schemes::AllowedValue {
+                                value: v.value.as_str(),
+                                is_string: v.is_string,
+                                description: "",
+                            })
+                            .collect::<Vec<_>>(),
+                    );
+                    enums.push_str(&enum_code.content);
+                    ParamType {
+                        typ: enum_code.class_name,
+                        encoding: ParamEncoding::Enum,
+                    }
+                }
+                Primitive::List(inner) => {
+                    let inner =
+                        self.param_type(inner, &format!("{}_", name), depth, enums, imports);
+                    ParamType {
+                        typ: format!("List<{}>", inner.typ),
+                        encoding: ParamEncoding::List(Box::new(inner)),
+                    }
+                }
+                Primitive::Never => ParamType {
+                    typ: schemes::to_dart_prim(&prim.value),
+                    encoding: ParamEncoding::Class,
+                },
+                Primitive::Map(_) | Primitive::Dynamic | Primitive::Binary => ParamType {
+                    typ: "dynamic".to_string(),
+                    encoding: ParamEncoding::Json,
+                },
+                Primitive::DateTime | Primitive::Date | Primitive::Uri | Primitive::Base64 => {
+                    ParamType {
+                        typ: schemes::to_dart_prim(&prim.value),
+                        encoding: FormatCodec::of(&prim.value)
+                            .map_or(ParamEncoding::Primitive, ParamEncoding::Format),
+                    }
+                }
+            },
+            IAST::Reference(reference) => {
+                let trimmed_link = sanitize(strip_ref_prefix(reference.path));
+                cpf!(
+                    imports,
+                    "import '{}schemes/{}.dart';",
+                    "../".repeat(depth + 1),
+                    trimmed_link
+                );
+                ParamType {
+                    typ: self.scheme_adder.class_name(&trimmed_link),
+                    encoding: match self.intermediate.resolve_ref(reference.path) {
+                        ResolvedRef::Primitive => match self.scheme_adder.format_codec(iast) {
+                            Some(codec) => ParamEncoding::Format(codec),
+                            None => ParamEncoding::Primitive,
+                        },
+                        ResolvedRef::Enum => ParamEncoding::Enum,
+                        _ => ParamEncoding::Class,
+                    },
+                }
+            }
+            // inline objects / unions have no generated class of their own
+            // here, so they are passed as raw JSON
+            IAST::Object(_) => ParamType {
+                typ: "dynamic".to_string(),
+                encoding: ParamEncoding::Json,
+            },
+        }
     }
 }
 
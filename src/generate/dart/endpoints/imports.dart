@@ -1,2 +1,4 @@
 import '../utils/serde.dart';
 import 'dart:async';
+import 'dart:typed_data';
+import 'dart:convert';
@@ -20,6 +20,9 @@ abstract interface class JsonRequestHandler {
 /// `fromCode`, otherwise decoding falls back to the (discouraged) `fromJson`.
 class BeamStatusCodeRef {
   int? statusCode;
+
+  /// The response's headers, filled by [BeamResponseHeadersAwareHandler]s.
+  Map<String, String>? headers;
 }
 
 /// Optional, additive capability a [JsonRequestHandler] MAY also implement to
@@ -44,6 +47,295 @@ abstract interface class BeamStatusCodeAwareHandler
   });
 }
 
+/// Optional, additive capability a [BeamStatusCodeAwareHandler] MAY also
+/// implement to report the headers of a response.
+///
+/// Such a handler assigns them to [BeamStatusCodeRef.headers] along with the
+/// status code, whichever of its methods is given the ref. The `WithHeaders`
+/// methods of endpoints declaring response headers decode them into typed
+/// [BEAMResponseHeaders]; with other handlers those are all null.
+abstract interface class BeamResponseHeadersAwareHandler
+    implements BeamStatusCodeAwareHandler {}
+
+/// The headers of a response, by lower-case name. Endpoints declaring
+/// response headers get a subclass with a typed getter for each.
+class BEAMResponseHeaders {
+  final Map<String, String> raw;
+
+  BEAMResponseHeaders(Map<String, String> headers)
+    : raw = {
+        for (final MapEntry(:key, :value) in headers.entries)
+          key.toLowerCase(): value,
+      };
+}
+
+/// A decoded response [value] together with its [headers].
+class BEAMWithHeaders<T, H extends BEAMResponseHeaders> {
+  final T value;
+  final H headers;
+
+  const BEAMWithHeaders(this.value, this.headers);
+}
+
+/// Optional, additive capability a [JsonRequestHandler] MAY also implement to
+/// send bodies and accept responses in media types other than JSON.
+///
+/// Generated endpoints offering several media types get a dedicated method
+/// per media type (e.g. `postTextPlain`, `getAcceptingTextCsv`); only those
+/// pass a [contentType] or [accept] value. Handlers that do not implement
+/// this keep receiving them through [JsonRequestHandler.handle], without the
+/// media type information.
+abstract interface class BeamMediaTypeAwareHandler
+    implements JsonRequestHandler {
+  /// Like [JsonRequestHandler.handle], but sends [body] as [contentType]
+  /// (JSON when null) and asks for [accept] (JSON when null). When
+  /// [statusCodeRef] is given, the response's HTTP status code is assigned
+  /// to it like [BeamStatusCodeAwareHandler.handleWithStatusCode] does.
+  Future<dynamic> handleWithMediaType({
+    required BEAMRequestMethod method,
+    required String path,
+    String? contentType,
+    String? accept,
+    BeamStatusCodeRef? statusCodeRef,
+    Map<String, String> params = const {},
+    dynamic body,
+    BEAMExpectedResponseType expectedResponseType =
+        BEAMExpectedResponseType.json,
+  });
+}
+
+/// A file sent as one part of a `multipart/form-data` body.
+class BEAMMultipartFile {
+  final Uint8List bytes;
+  final String? filename;
+  final String? contentType;
+
+  const BEAMMultipartFile({
+    required this.bytes,
+    this.filename,
+    this.contentType,
+  });
+
+  /// This file, with [contentType] set to [fallback] unless already given.
+  BEAMMultipartFile withFallbackContentType(String fallback) =>
+      contentType != null
+      ? this
+      : BEAMMultipartFile(
+          bytes: bytes,
+          filename: filename,
+          contentType: fallback,
+        );
+}
+
+/// A `multipart/form-data` body: text parts and file parts, in order. A name
+/// repeats for every element of an array property.
+class BEAMMultipartBody {
+  final List<MapEntry<String, String>> fields;
+  final List<MapEntry<String, BEAMMultipartFile>> files;
+
+  const BEAMMultipartBody({this.fields = const [], this.files = const []});
+}
+
+/// Optional, additive capability a [JsonRequestHandler] MAY also implement to
+/// send `multipart/form-data` bodies (e.g. file uploads).
+///
+/// Only endpoints taking a form body need it; calling one of those with a
+/// handler that does not implement this fails with an [UnsupportedError].
+abstract interface class BeamMultipartHandler implements JsonRequestHandler {
+  /// Like [JsonRequestHandler.handle], but sends [body] as
+  /// `multipart/form-data`. When [statusCodeRef] is given, the response's
+  /// HTTP status code is assigned to it.
+  Future<dynamic> handleMultipart({
+    required BEAMRequestMethod method,
+    required String path,
+    required BEAMMultipartBody body,
+    BeamStatusCodeRef? statusCodeRef,
+    Map<String, String> params = const {},
+    BEAMExpectedResponseType expectedResponseType =
+        BEAMExpectedResponseType.json,
+  });
+}
+
+/// How an array or object property of a [BEAMUrlEncodedBody] is serialized,
+/// as the `style` of its OpenAPI `encoding`.
+enum BEAMFormStyle { form, spaceDelimited, pipeDelimited, deepObject }
+
+/// An `application/x-www-form-urlencoded` body: its fields, in order. A name
+/// repeats for every element of an exploded array.
+class BEAMUrlEncodedBody {
+  static const contentType = 'application/x-www-form-urlencoded';
+
+  final List<MapEntry<String, String>> fields;
+
+  const BEAMUrlEncodedBody(this.fields);
+
+  /// The body as sent over the wire, e.g. `a=1&b=x+y`.
+  String encode() => fields
+      .map(
+        (f) =>
+            '${Uri.encodeQueryComponent(f.key)}=${Uri.encodeQueryComponent(f.value)}',
+      )
+      .join('&');
+
+  /// The fields of the array property [name].
+  static Iterable<MapEntry<String, String>> array(
+    String name,
+    Iterable<String> values, {
+    BEAMFormStyle style = BEAMFormStyle.form,
+    bool explode = true,
+  }) {
+    if (explode && style == BEAMFormStyle.form) {
+      return values.map((v) => MapEntry(name, v));
+    }
+    return [MapEntry(name, values.join(_delimiter(style)))];
+  }
+
+  /// The fields of the object property [name], given its JSON value.
+  static Iterable<MapEntry<String, String>> object(
+    String name,
+    dynamic json, {
+    BEAMFormStyle style = BEAMFormStyle.form,
+    bool explode = true,
+  }) {
+    if (json is! Map) return [MapEntry(name, _scalar(json))];
+    final entries = json.entries
+        .where((e) => e.value != null)
+        .map((e) => MapEntry(e.key.toString(), _scalar(e.value)));
+    if (style == BEAMFormStyle.deepObject) {
+      return entries.map((e) => MapEntry('$name[${e.key}]', e.value));
+    }
+    if (explode && style == BEAMFormStyle.form) return entries;
+    return [
+      MapEntry(
+        name,
+        entries.expand((e) => [e.key, e.value]).join(_delimiter(style)),
+      ),
+    ];
+  }
+
+  static String _scalar(dynamic value) =>
+      value is String || value is num || value is bool
+      ? value.toString()
+      : jsonEncode(value);
+
+  static String _delimiter(BEAMFormStyle style) => switch (style) {
+    BEAMFormStyle.spaceDelimited => ' ',
+    BEAMFormStyle.pipeDelimited => '|',
+    _ => ',',
+  };
+}
+
+/// Optional, additive capability a [JsonRequestHandler] MAY also implement to
+/// send `application/x-www-form-urlencoded` bodies.
+///
+/// Handlers implementing [BeamMediaTypeAwareHandler] instead receive the
+/// already encoded form as a string body; calling an endpoint taking such a
+/// body with a handler implementing neither fails with an
+/// [UnsupportedError].
+abstract interface class BeamFormUrlEncodedHandler
+    implements JsonRequestHandler {
+  /// Like [JsonRequestHandler.handle], but sends [body] (see
+  /// [BEAMUrlEncodedBody.encode]) with the `Content-Type` [contentType].
+  /// When [statusCodeRef] is given, the response's HTTP status code is
+  /// assigned to it.
+  Future<dynamic> handleFormUrlEncoded({
+    required BEAMRequestMethod method,
+    required String path,
+    required BEAMUrlEncodedBody body,
+    required String contentType,
+    BeamStatusCodeRef? statusCodeRef,
+    Map<String, String> params = const {},
+    BEAMExpectedResponseType expectedResponseType =
+        BEAMExpectedResponseType.json,
+  });
+}
+
+/// One way of authenticating a request: the names of the security schemes
+/// whose credentials are sent together, each with the OAuth2 scopes it needs.
+typedef BEAMSecurityRequirement = Map<String, List<String>>;
+
+/// Username and password of an HTTP basic security scheme.
+class BEAMBasicCredentials {
+  final String username;
+  final String password;
+
+  const BEAMBasicCredentials({required this.username, required this.password});
+
+  /// The `Authorization` header value.
+  String get header =>
+      'Basic ${base64Encode(utf8.encode('$username:$password'))}';
+}
+
+/// The headers, query parameters and cookies authenticating a request.
+class BEAMRequestCredentials {
+  final Map<String, String> headers = {};
+  final Map<String, String> query = {};
+  final Map<String, String> cookies = {};
+
+  /// The credentials for the first alternative of [security] that [provider]
+  /// has every credential of, null if there is none. An empty alternative
+  /// needs no credentials at all.
+  static Future<BEAMRequestCredentials?> of(
+    BEAMCredentialsProvider provider,
+    List<BEAMSecurityRequirement> security,
+  ) async {
+    alternatives:
+    for (final requirement in security) {
+      final credentials = BEAMRequestCredentials();
+      for (final MapEntry(key: scheme, value: scopes) in requirement.entries) {
+        if (!await _applyCredentials(provider, scheme, scopes, credentials)) {
+          continue alternatives;
+        }
+      }
+      return credentials;
+    }
+    return null;
+  }
+}
+
+/// Optional, additive capability a [JsonRequestHandler] MAY also implement to
+/// authenticate requests.
+///
+/// Requests to endpoints declaring security requirements go through
+/// [handleAuthenticated] with the credentials [credentialsProvider] has for
+/// them; requests to public endpoints never do, so they carry no tokens.
+/// Handlers not implementing this receive every request unauthenticated.
+abstract interface class BeamAuthenticatingHandler
+    implements JsonRequestHandler {
+  /// Provides the credentials of the API's security schemes.
+  BEAMCredentialsProvider get credentialsProvider;
+
+  /// Like [JsonRequestHandler.handle], but also sends the headers and cookies
+  /// of [credentials]; its query parameters are already part of [params].
+  /// [body] may be a [BEAMMultipartBody] or a [BEAMUrlEncodedBody], to be
+  /// sent like [BeamMultipartHandler] and [BeamFormUrlEncodedHandler] do.
+  /// [contentType], [accept] and [statusCodeRef] are used like in
+  /// [BeamMediaTypeAwareHandler.handleWithMediaType].
+  Future<dynamic> handleAuthenticated({
+    required BEAMRequestMethod method,
+    required String path,
+    required BEAMRequestCredentials credentials,
+    String? contentType,
+    String? accept,
+    BeamStatusCodeRef? statusCodeRef,
+    Map<String, String> params = const {},
+    dynamic body,
+    BEAMExpectedResponseType expectedResponseType =
+        BEAMExpectedResponseType.json,
+  });
+}
+
+/// Optional, additive capability a [JsonRequestHandler] MAY also implement to
+/// send the requests of endpoints with servers of their own there.
+///
+/// Handlers not implementing this send every request to the server they
+/// were set up for.
+abstract interface class BeamServerAwareHandler implements JsonRequestHandler {
+  /// A handler like this one, with all of its capabilities, but sending
+  /// requests to [server].
+  JsonRequestHandler onServer(BEAMServer server);
+}
+
 enum BEAMExpectedResponseType { json, binary }
 
 typedef BEAMRequestLeafDeps = JsonRequestHandler;
@@ -71,27 +363,22 @@ abstract class BEAMPath {
     BEAMExpectedResponseType expectedResponseType =
         BEAMExpectedResponseType.json,
     BeamStatusCodeRef? statusCodeRef,
+    String? contentType,
+    String? accept,
+    List<BEAMSecurityRequirement> security = const [],
+    List<BEAMServer> servers = const [],
   }) {
-    final h = handler;
-    // Use the status-code-aware path only when a ref was requested AND the
-    // handler supports it; otherwise fall back to the plain `handle`.
-    final Future<dynamic> upstream =
-        (statusCodeRef != null && h is BeamStatusCodeAwareHandler)
-        ? h.handleWithStatusCode(
-            method: method,
-            path: interpolatedPath,
-            statusCodeRef: statusCodeRef,
-            params: params,
-            body: body,
-            expectedResponseType: expectedResponseType,
-          )
-        : h.handle(
-            method: method,
-            path: interpolatedPath,
-            params: params,
-            body: body,
-            expectedResponseType: expectedResponseType,
-          );
+    final upstream = _send(
+      method: method,
+      params: params,
+      body: body,
+      expectedResponseType: expectedResponseType,
+      statusCodeRef: statusCodeRef,
+      contentType: contentType,
+      accept: accept,
+      security: security,
+      servers: servers,
+    );
     return upstream.then((response) {
       handler.cache?.storeInCache(
         response: response,
@@ -106,6 +393,137 @@ abstract class BEAMPath {
     });
   }
 
+  /// Hands the request to the most specific capability of [handler] the
+  /// request needs.
+  Future<dynamic> _send({
+    required BEAMRequestMethod method,
+    required Map<String, String> params,
+    required dynamic body,
+    required BEAMExpectedResponseType expectedResponseType,
+    BeamStatusCodeRef? statusCodeRef,
+    String? contentType,
+    String? accept,
+    List<BEAMSecurityRequirement> security = const [],
+    List<BEAMServer> servers = const [],
+  }) {
+    final h = switch (handler) {
+      // endpoints with servers of their own are sent to the first of them
+      BeamServerAwareHandler h when servers.isNotEmpty => h.onServer(
+        servers.first,
+      ),
+      final h => h,
+    };
+    // Only endpoints requiring credentials get them.
+    if (security.isNotEmpty && h is BeamAuthenticatingHandler) {
+      return BEAMRequestCredentials.of(h.credentialsProvider, security).then((
+        credentials,
+      ) {
+        if (credentials == null) {
+          throw StateError(
+            '$interpolatedPath requires credentials the provider does not have',
+          );
+        }
+        return h.handleAuthenticated(
+          method: method,
+          path: interpolatedPath,
+          credentials: credentials,
+          contentType: body is BEAMUrlEncodedBody
+              ? contentType ?? BEAMUrlEncodedBody.contentType
+              : contentType,
+          accept: accept,
+          statusCodeRef: statusCodeRef,
+          params: {...params, ...credentials.query},
+          body: body,
+          expectedResponseType: expectedResponseType,
+        );
+      });
+    }
+    // Form bodies need a handler that can send them.
+    if (body is BEAMMultipartBody) {
+      if (h is BeamMultipartHandler) {
+        return h.handleMultipart(
+          method: method,
+          path: interpolatedPath,
+          body: body,
+          statusCodeRef: statusCodeRef,
+          params: params,
+          expectedResponseType: expectedResponseType,
+        );
+      }
+      return Future.error(
+        UnsupportedError(
+          '$interpolatedPath takes a multipart body, but the handler is no BeamMultipartHandler',
+        ),
+      );
+    }
+    if (body is BEAMUrlEncodedBody) {
+      final formContentType = contentType ?? BEAMUrlEncodedBody.contentType;
+      if (h is BeamFormUrlEncodedHandler) {
+        return h.handleFormUrlEncoded(
+          method: method,
+          path: interpolatedPath,
+          body: body,
+          contentType: formContentType,
+          statusCodeRef: statusCodeRef,
+          params: params,
+          expectedResponseType: expectedResponseType,
+        );
+      }
+      // the encoded form is a plain string body of its media type
+      if (h is BeamMediaTypeAwareHandler) {
+        return h.handleWithMediaType(
+          method: method,
+          path: interpolatedPath,
+          contentType: formContentType,
+          accept: accept,
+          statusCodeRef: statusCodeRef,
+          params: params,
+          body: body.encode(),
+          expectedResponseType: expectedResponseType,
+        );
+      }
+      return Future.error(
+        UnsupportedError(
+          '$interpolatedPath takes a urlencoded form body, but the handler is no BeamFormUrlEncodedHandler',
+        ),
+      );
+    }
+    // Use the media-type-aware path only when a non-JSON media type is
+    // involved, and the status-code-aware path only when a ref was
+    // requested, each only if the handler supports it; otherwise fall back
+    // to the plain `handle`.
+    if ((contentType != null || accept != null) &&
+        h is BeamMediaTypeAwareHandler) {
+      return h.handleWithMediaType(
+        method: method,
+        path: interpolatedPath,
+        contentType: contentType,
+        accept: accept,
+        statusCodeRef: statusCodeRef,
+        params: params,
+        body: body,
+        expectedResponseType: expectedResponseType,
+      );
+    }
+    if (statusCodeRef != null && h is BeamStatusCodeAwareHandler) {
+      return h.handleWithStatusCode(
+        method: method,
+        path: interpolatedPath,
+        statusCodeRef: statusCodeRef,
+        params: params,
+        body: body,
+        expectedResponseType: expectedResponseType,
+      );
+    }
+    return h.handle(
+      method: method,
+      path: interpolatedPath,
+      params: params,
+      body: body,
+      expectedResponseType: expectedResponseType,
+    );
+  }
+
   BEAMCachedResponse<dynamic> handleCached({
     required BEAMRequestMethod method,
     Map<String, String> params = const {},
@@ -113,6 +531,10 @@ abstract class BEAMPath {
     BEAMExpectedResponseType expectedResponseType =
         BEAMExpectedResponseType.json,
     BeamStatusCodeRef? statusCodeRef,
+    String? contentType,
+    String? accept,
+    List<BEAMSecurityRequirement> security = const [],
+    List<BEAMServer> servers = const [],
   }) {
     return BEAMCachedResponse<dynamic>(
       upstreamFuture: handle(
@@ -121,6 +543,10 @@ abstract class BEAMPath {
         body: body,
         expectedResponseType: expectedResponseType,
         statusCodeRef: statusCodeRef,
+        contentType: contentType,
+        accept: accept,
+        security: security,
+        servers: servers,
       ),
       cachedFuture: handler.cache?.fetchFromCache(
         method: method,
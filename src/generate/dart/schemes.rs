@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use super::super::interface::*;
 
@@ -8,23 +8,61 @@ use crate::{cpf, parse::intermediate};
 #[allow(non_upper_case_globals)]
 static empty_str: String = String::new();
 
+#[derive(Clone)]
 pub(super) struct SchemeAdder<'a> {
     class_prefix: &'a str,
     class_suffix: &'a str,
     vars_should_be_final: bool,
+    generate_validators: bool,
+    read_write_models: ReadWriteModels,
+    /// Set for the adders generating the request/response variants of
+    /// [`ReadWriteModels::Separate`].
+    direction: Option<Direction>,
+    /// The schemes getting request/response variants.
+    directed_schemes: HashSet<&'a str>,
     complete_iast: Option<&'a intermediate::IntermediateFormat<'a>>,
 }
 
+/// Whether a model is sent in requests or returned in responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Direction {
+    Request,
+    Response,
+}
+
+impl Direction {
+    fn suffix(self) -> &'static str {
+        match self {
+            Direction::Request => "Request",
+            Direction::Response => "Response",
+        }
+    }
+
+    /// Whether a property of the given schema is left out in this direction.
+    fn omits(self, iast: &intermediate::IAST) -> bool {
+        match self {
+            Direction::Request => iast.is_read_only(),
+            Direction::Response => iast.is_write_only(),
+        }
+    }
+}
+
 impl<'a> SchemeAdder<'a> {
     pub(super) fn new(
         class_prefix: &'a str,
         class_suffix: &'a str,
         vars_should_be_final: bool,
+        generate_validators: bool,
+        read_write_models: ReadWriteModels,
     ) -> Self {
         Self {
             class_prefix,
             class_suffix,
             vars_should_be_final,
+            generate_validators,
+            read_write_models,
+            direction: None,
+            directed_schemes: HashSet::new(),
             complete_iast: None,
         }
     }
@@ -34,6 +72,33 @@ impl<'a> SchemeAdder<'a> {
         intermediate: &'a intermediate::IntermediateFormat<'a>,
     ) {
         self.complete_iast = Some(intermediate);
+        if self.read_write_models == ReadWriteModels::Separate {
+            self.directed_schemes = intermediate.schemes_with_read_write_only();
+        }
+    }
+
+    /// The adder for models sent or returned in the given direction, which
+    /// only differ from the plain ones with [`ReadWriteModels::Separate`].
+    pub(super) fn directed(&self, direction: Direction) -> Self {
+        Self {
+            direction: match self.read_write_models {
+                ReadWriteModels::Ignore | ReadWriteModels::OptionalFields => None,
+                ReadWriteModels::Separate => Some(direction),
+            },
+            ..self.clone()
+        }
+    }
+
+    /// The sanitized name of the scheme `ref_path` points at, suffixed with
+    /// the direction if it has a request/response variant.
+    fn link_name(&self, ref_path: &str) -> String {
+        let scheme_name = strip_ref_prefix(ref_path);
+        match self.direction {
+            Some(direction) if self.directed_schemes.contains(scheme_name) => {
+                format!("{}{}", sanitize(scheme_name), direction.suffix())
+            }
+            _ => sanitize(scheme_name),
+        }
     }
 
     /// Thin wrapper that asks the shared
@@ -55,59 +120,280 @@ impl<'a> SchemeAdder<'a> {
         }
     }
 
-    pub(super) fn add_schemes(&self, out: &mut Vec<File>) {
-        let mut scheme_files = Vec::new();
-        for scheme in self.complete_iast.unwrap().schemes.iter() {
-            let sanitized_scheme_name = sanitize(scheme.name);
-            let mut parsed = self.parse_named_iast(
-                format!(
-                    "{}{}",
-                    sanitized_scheme_name,
-                    if scheme.is_inherently_nullable {
-                        "NonNull"
-                    } else {
-                        ""
+    /// The Dart type all JSON values of `iast` have, if they share one: a
+    /// union variant of it is recognized without trying to parse it.
+    fn json_type(&self, iast: &intermediate::IAST<'a>) -> Option<&'static str> {
+        let complete_iast = self.complete_iast?;
+        let mut current = iast;
+        let mut visited = HashSet::new();
+        loop {
+            match current {
+                intermediate::IAST::Reference(r) => {
+                    if !visited.insert(strip_ref_prefix(r.path)) {
+                        return None;
                     }
-                )
-                .as_str(),
-                &scheme.obj,
-                0,
-            );
+                    current = &complete_iast.find_scheme(r.path)?.obj;
+                }
+                intermediate::IAST::Object(obj) => {
+                    return match obj.value {
+                        intermediate::AlgType::Sum(_) => None,
+                        _ => Some("Map"),
+                    }
+                }
+                intermediate::IAST::Primitive(prim) => {
+                    return match prim.value {
+                        Primitive::String
+                        | Primitive::DateTime
+                        | Primitive::Date
+                        | Primitive::Uri
+                        | Primitive::Base64 => Some("String"),
+                        Primitive::Integer => Some("int"),
+                        Primitive::Number => Some("num"),
+                        Primitive::Boolean => Some("bool"),
+                        Primitive::List(_) => Some("List"),
+                        Primitive::Map(_) => Some("Map"),
+                        _ => None,
+                    }
+                }
+            }
+        }
+    }
 
-            if scheme.is_inherently_nullable {
-                // lol irgendwann sollte man mal auf ne templating engine umsteigen
-                cpf!(
-                    parsed.content,
-                    "
-class BEAM{}Model implements BEAMSerde {{
+    /// The [`FormatCodec`] of the formatted primitive the given IAST
+    /// resolves to (following `Reference`s), if any.
+    pub(super) fn format_codec(&self, iast: &intermediate::IAST<'a>) -> Option<FormatCodec> {
+        FormatCodec::of(&self.complete_iast?.resolve_primitive(iast)?.value)
+    }
 
-    BEAM{}Model(this.value);
-    final BEAM{}NonNullModel? value;
-    factory BEAM{}Model.fromJson(Map<String, dynamic>? json) {{
-        if (json == null) {{
-            return BEAM{}Model(null);
-        }}
-        return BEAM{}Model(BEAM{}NonNullModel.fromJson(json));
-    }}
+    /// Like [`Self::format_codec`], but for the elements of the list or map
+    /// the given IAST resolves to.
+    pub(super) fn element_format_codec(
+        &self,
+        iast: &intermediate::IAST<'a>,
+    ) -> Option<FormatCodec> {
+        match &self.complete_iast?.resolve_primitive(iast)?.value {
+            Primitive::List(inner) | Primitive::Map(inner) => self.format_codec(inner),
+            _ => None,
+        }
+    }
 
-    toJson() => value?.toJson();
-}}
-                        ",
-                    sanitized_scheme_name,
-                    sanitized_scheme_name,
-                    sanitized_scheme_name,
-                    sanitized_scheme_name,
-                    sanitized_scheme_name,
-                    sanitized_scheme_name,
-                    sanitized_scheme_name,
+    /// The value type of the `Map<String, _>` typedef generated for the
+    /// scheme the given reference (transitively) points at, if it is a map,
+    /// and whether the values are primitive.
+    fn referenced_map_values(&self, iast: &intermediate::IAST<'a>) -> Option<(String, bool)> {
+        let iformat = self.complete_iast?;
+        let mut current = iast;
+        let mut visited = HashSet::new();
+        while let intermediate::IAST::Reference(reference) = current {
+            if !visited.insert(reference.path) {
+                return None;
+            }
+            let scheme = iformat.find_scheme(reference.path)?;
+            if let intermediate::IAST::Primitive(AnnotatedObj {
+                value: Primitive::Map(values),
+                ..
+            }) = &scheme.obj
+            {
+                // mirrors the naming of `parse_named_iast`
+                let value_type = match &**values {
+                    intermediate::IAST::Reference(values) => {
+                        self.class_name(&self.link_name(values.path))
+                    }
+                    _ => self.class_name(&format!(
+                        "{}{}_",
+                        self.link_name(scheme.name),
+                        if scheme.is_inherently_nullable {
+                            "NonNull"
+                        } else {
+                            ""
+                        }
+                    )),
+                };
+                return Some((value_type, self.iast_resolves_to_primitive(values)));
+            }
+            current = &scheme.obj;
+        }
+        None
+    }
+
+    /// Turns the `Link` special case of a reference to a map scheme into a
+    /// `Map` one, so that its values are (de)serialized one by one.
+    pub(super) fn map_link_special_case(
+        &self,
+        iast: &intermediate::IAST<'a>,
+        special_case: Option<GenerationSpecialCase>,
+    ) -> Option<GenerationSpecialCase> {
+        match special_case {
+            Some(GenerationSpecialCase {
+                reason: reason @ GenerationSpecialCaseType::Link(_),
+                type_name,
+            }) => Some(GenerationSpecialCase {
+                reason: match self.referenced_map_values(iast) {
+                    Some((values, is_primitive)) => {
+                        GenerationSpecialCaseType::Map(values, is_primitive)
+                    }
+                    None => reason,
+                },
+                type_name,
+            }),
+            special_case => special_case,
+        }
+    }
+
+    /// The `default` of the primitive or enum `iast` resolves to, as a const
+    /// Dart expression of `dart_type`. `None` if there is none or it does
+    /// not fit the type.
+    pub(super) fn default_literal(
+        &self,
+        iast: &intermediate::IAST<'a>,
+        dart_type: &str,
+    ) -> Option<String> {
+        let prim = self.complete_iast?.resolve_primitive(iast)?;
+        let default = prim.default?;
+        match &prim.value {
+            Primitive::String => default.as_str().map(dart_string_literal),
+            Primitive::Integer => default
+                .as_i64()
+                .map(|n| n.to_string())
+                .or_else(|| default.as_u64().map(|n| n.to_string())),
+            Primitive::Number => default.as_number().map(|n| n.to_string()),
+            Primitive::Boolean => default.as_bool().map(|b| b.to_string()),
+            Primitive::Enum(allowed_values) => allowed_values
+                .iter()
+                .find(|v| match default {
+                    serde_json::Value::String(s) => v.is_string && &v.value == s,
+                    other => {
+                        !v.is_string
+                            && serde_json::from_str::<serde_json::Value>(&v.value)
+                                .is_ok_and(|value| &value == other)
+                    }
+                })
+                .map(|v| format!("{}.t_{}", dart_type, sanitize(&v.value))),
+            _ => None,
+        }
+    }
+
+    /// The interfaces generated classes implement next to `BEAMSerde`.
+    fn serde_interfaces(&self) -> &'static str {
+        if self.generate_validators {
+            "BEAMSerde, BEAMValidatable"
+        } else {
+            "BEAMSerde"
+        }
+    }
+
+    /// Dart statements appending the violations of `value`, a value of
+    /// `iast` found at the JSON `path` (the content of a Dart string
+    /// literal), to `violations`. `level` keeps the loop variables of nested
+    /// lists apart.
+    fn validation_checks(
+        &self,
+        iast: &intermediate::IAST<'a>,
+        value: &str,
+        path: &str,
+        level: usize,
+    ) -> String {
+        let nested = format!("BEAMValidate.nested(violations, '{}', {});\n", path, value);
+        let Some(prim) = self.complete_iast.and_then(|i| i.resolve_primitive(iast)) else {
+            return nested;
+        };
+        let c = &prim.constraints;
+        let number = |n: &Option<serde_json::Number>| n.as_ref().map(|n| n.to_string());
+        let int = |n: &Option<u64>| n.map(|n| n.to_string());
+        let call = |check: &str, args: &[(&str, Option<String>)]| {
+            let args = args
+                .iter()
+                .filter_map(|(name, arg)| arg.as_ref().map(|arg| format!("{}: {}", name, arg)))
+                .collect::<Vec<_>>();
+            if args.is_empty() {
+                return String::new();
+            }
+            format!(
+                "BEAMValidate.{}(violations, '{}', {}, {});\n",
+                check,
+                path,
+                value,
+                args.join(", ")
+            )
+        };
+        match &prim.value {
+            Primitive::Number | Primitive::Integer => call(
+                "number",
+                &[
+                    ("minimum", number(&c.minimum)),
+                    ("exclusiveMinimum", number(&c.exclusive_minimum)),
+                    ("maximum", number(&c.maximum)),
+                    ("exclusiveMaximum", number(&c.exclusive_maximum)),
+                    ("multipleOf", number(&c.multiple_of)),
+                ],
+            ),
+            Primitive::String => call(
+                "string",
+                &[
+                    ("minLength", int(&c.min_length)),
+                    ("maxLength", int(&c.max_length)),
+                    ("pattern", c.pattern.map(dart_string_literal)),
+                ],
+            ),
+            Primitive::List(inner) => {
+                let mut checks = call(
+                    "list",
+                    &[
+                        ("minItems", int(&c.min_items)),
+                        ("maxItems", int(&c.max_items)),
+                        ("uniqueItems", c.unique_items.then(|| "true".to_string())),
+                    ],
                 );
+                let (index, element) = (format!("i{}", level), format!("e{}", level));
+                let element_checks = self.validation_checks(
+                    inner,
+                    &element,
+                    &format!("{}[${}]", path, index),
+                    level + 1,
+                );
+                if !element_checks.is_empty() {
+                    checks.push_str(&format!(
+                        "for (final ({}, {}) in BEAMValidate.items({})) {{\n",
+                        index, element, value
+                    ));
+                    for line in element_checks.lines() {
+                        checks.push_str(&format!("  {}\n", line));
+                    }
+                    checks.push_str("}\n");
+                }
+                checks
+            }
+            Primitive::Map(_) => nested,
+            _ => String::new(),
+        }
+    }
+
+    /// The `validate()` method running `checks` (see
+    /// [`Self::validation_checks`]).
+    fn validate_method(checks: &str) -> String {
+        let mut method = String::from(
+            "\n  @override\n  List<BEAMViolation> validate([String path = r'$']) {\n    final violations = <BEAMViolation>[];\n",
+        );
+        for line in checks.lines() {
+            method.push_str(&format!("    {}\n", line));
+        }
+        method.push_str("    return violations;\n  }\n");
+        method
+    }
+
+    pub(super) fn add_schemes(&self, out: &mut Vec<File>) {
+        let mut scheme_files = Vec::new();
+        let directed_adders = [
+            self.directed(Direction::Request),
+            self.directed(Direction::Response),
+        ];
+        for scheme in self.complete_iast.unwrap().schemes.iter() {
+            self.add_scheme(scheme, &mut scheme_files);
+            if self.directed_schemes.contains(scheme.name) {
+                for adder in directed_adders.iter() {
+                    adder.add_scheme(scheme, &mut scheme_files);
+                }
             }
-            let file = File {
-                path: std::path::PathBuf::from(format!("{}.dart", sanitized_scheme_name)),
-                content: parsed.content,
-            };
-            scheme_files.push(file);
-            scheme_files.extend(parsed.files);
         }
         // add barrel file
         scheme_files.push(File {
@@ -127,6 +413,92 @@ class BEAM{}Model implements BEAMSerde {{
         }));
     }
 
+    /// Adds the file of `scheme` (and those it depends on) to `out`.
+    fn add_scheme(&self, scheme: &intermediate::Scheme, out: &mut Vec<File>) {
+        let sanitized_scheme_name = self.link_name(scheme.name);
+        // generated along with the base class
+        if let Some(base) = self.extended_base(scheme) {
+            out.push(File {
+                path: std::path::PathBuf::from(format!("{}.dart", sanitized_scheme_name)),
+                content: format!("export '{}.dart';\n", self.link_name(base.name)),
+            });
+            return;
+        }
+        let mut parsed = self.parse_named_iast(
+            format!(
+                "{}{}",
+                sanitized_scheme_name,
+                if scheme.is_inherently_nullable {
+                    "NonNull"
+                } else {
+                    ""
+                }
+            )
+            .as_str(),
+            &scheme.obj,
+            0,
+        );
+
+        if scheme.is_inherently_nullable {
+            // lol irgendwann sollte man mal auf ne templating engine umsteigen
+            cpf!(
+                parsed.content,
+                "
+class BEAM{}Model implements {} {{
+
+    BEAM{}Model(this.value);
+    final BEAM{}NonNullModel? value;
+    factory BEAM{}Model.fromJson(Map<String, dynamic>? json) {{
+        if (json == null) {{
+            return BEAM{}Model(null);
+        }}
+        return BEAM{}Model(BEAM{}NonNullModel.fromJson(json));
+    }}
+
+    toJson() => value?.toJson();
+{}}}
+                        ",
+                sanitized_scheme_name,
+                self.serde_interfaces(),
+                sanitized_scheme_name,
+                sanitized_scheme_name,
+                sanitized_scheme_name,
+                sanitized_scheme_name,
+                sanitized_scheme_name,
+                sanitized_scheme_name,
+                if self.generate_validators {
+                    Self::validate_method("BEAMValidate.nested(violations, '$path', value);")
+                } else {
+                    String::new()
+                },
+            );
+        }
+        let file = File {
+            path: std::path::PathBuf::from(format!("{}.dart", sanitized_scheme_name)),
+            content: parsed.content,
+        };
+        out.push(file);
+        out.extend(parsed.files);
+    }
+
+    /// The base of the `allOf` hierarchy `scheme` is a subclass in.
+    fn extended_base(&self, scheme: &intermediate::Scheme) -> Option<&'a intermediate::Scheme<'a>> {
+        let base = self.complete_iast?.find_scheme(scheme.parent?)?;
+        match &base.obj {
+            intermediate::IAST::Object(AnnotatedObj {
+                value: intermediate::AlgType::Polymorphic(_, discrimination),
+                ..
+            }) if discrimination
+                .map
+                .values()
+                .any(|r| strip_ref_prefix(r.path) == scheme.name) =>
+            {
+                Some(base)
+            }
+            _ => None,
+        }
+    }
+
     pub(super) fn class_name(&self, name: &str) -> String {
         format!(
             "{}{}{}",
@@ -175,8 +547,31 @@ class BEAM{}Model implements BEAMSerde {{
                             is_binary: false,
                         }
                     }
-                    AlgType::Product(product) => {
-                        let generated = self.generate_product_type(name, &doc_str, product, depth);
+                    AlgType::Polymorphic(product, discrimination) => {
+                        let generated = self.generate_polymorphic_type(
+                            name,
+                            &doc_str,
+                            product,
+                            discrimination,
+                            depth,
+                        );
+                        ParsedIast {
+                            content: generated.content,
+                            files: generated.files,
+                            special_case: None,
+                            nullable: annotated_obj.nullable,
+                            optional: annotated_obj.optional,
+                            is_binary: false,
+                        }
+                    }
+                    AlgType::Product(product, additional) => {
+                        let generated = self.generate_product_type(
+                            name,
+                            &doc_str,
+                            product,
+                            additional.as_deref(),
+                            depth,
+                        );
                         ParsedIast {
                             content: generated.content,
                             files: generated.files,
@@ -190,7 +585,7 @@ class BEAM{}Model implements BEAMSerde {{
             }
             intermediate::IAST::Reference(annotated_ref) => {
                 let link = annotated_ref.path;
-                let trimmed_link = sanitize(strip_ref_prefix(link));
+                let trimmed_link = self.link_name(link);
                 ParsedIast {
                     // some references are nullable also (this should not be, but leons vibes introduce them nontheless), so we need to add the serde import anyway
                     content: format!(
@@ -255,9 +650,11 @@ class BEAM{}Model implements BEAMSerde {{
                             is_binary: false,
                         }
                     }
-                    intermediate::types::Primitive::List(inner_iast) => {
+                    intermediate::types::Primitive::List(inner_iast)
+                    | intermediate::types::Primitive::Map(inner_iast) => {
+                        let collection = Collection::of(&annotated_obj.value).unwrap();
                         let mut inner_name = &format!("{}_", name);
-                        let mut inner = self.parse_named_iast(&inner_name, inner_iast, depth);
+                        let mut inner = self.parse_named_iast(inner_name, inner_iast, depth);
                         let mut file_dependencies = Vec::new();
 
                         for f in inner.files.into_iter() {
@@ -273,35 +670,38 @@ class BEAM{}Model implements BEAMSerde {{
                             inner_name = internal_type_name;
                         }
 
-                        let outer_name = format!("List<{}>", self.class_name(&inner_name));
+                        let outer_name = collection.dart_type(&self.class_name(inner_name));
 
                         inner
                             .content
                             .push_str(&mk_type_def(name, &outer_name, true));
 
+                        // the type of elements in the collection, and whether
+                        // they are a primitive Dart value (no `.toJson` /
+                        // `.fromJson`). True for inline primitives, AND for
+                        // `$ref`s whose target scheme resolves to a primitive
+                        // typedef (`typedef Foo = String;` etc). Enums are NOT
+                        // primitive here because the generator emits a real
+                        // Dart enum class with `.fromJson` for them. The
+                        // chain-following is handled by the shared resolver,
+                        // so multi-hop refs (`Alias -> Id -> string`) behave
+                        // the same as a direct ref.
+                        let element_type = self.class_name(inner_name);
+                        let element_is_primitive = self.iast_resolves_to_primitive(inner_iast);
                         ParsedIast {
                             content: inner.content,
                             files: file_dependencies,
                             special_case: Some(GenerationSpecialCase {
-                                reason: GenerationSpecialCaseType::List(
-                                    // the type of elements in the list
-                                    self.class_name(&inner_name),
-                                    // Whether the elements in the list are
-                                    // a primitive Dart value (no
-                                    // `.toJson` / `.fromJson`). True for
-                                    // inline primitives, AND for `$ref`s
-                                    // whose target scheme resolves to a
-                                    // primitive typedef (`typedef Foo =
-                                    // String;` etc). Enums are NOT
-                                    // primitive here because the generator
-                                    // emits a real Dart enum class with
-                                    // `.fromJson` for them. The chain-
-                                    // following is handled by the shared
-                                    // resolver, so multi-hop refs
-                                    // (`Alias -> Id -> string`) behave the
-                                    // same as a direct ref.
-                                    self.iast_resolves_to_primitive(inner_iast),
-                                ),
+                                reason: match collection {
+                                    Collection::List => GenerationSpecialCaseType::List(
+                                        element_type,
+                                        element_is_primitive,
+                                    ),
+                                    Collection::Map => GenerationSpecialCaseType::Map(
+                                        element_type,
+                                        element_is_primitive,
+                                    ),
+                                },
                                 //XXX: use `self.class_name(name)` if we want the left part of the typedef
                                 // e.g. BEAM_v2_billing_subscriptions_subscribeMethods_postResponseModel
                                 // or user `outer_name` for the right part
@@ -423,18 +823,27 @@ class BEAM{}Model implements BEAMSerde {{
                 reason: GenerationSpecialCaseType::Link(internal_type_name),
             }) = &parsed.special_case
             {
-                variant_name = index_to_name(&internal_type_name);
+                variant_name = index_to_name(internal_type_name);
             }
             file_dependencies.push(File {
                 path: std::path::PathBuf::from(format!("{}/{}.dart", name, sanitized_inner_name)),
                 content: parsed.content,
             });
+            let codec = match &parsed.special_case {
+                Some(special_case) if special_case.reason.collection().is_some() => {
+                    self.element_format_codec(iast)
+                }
+                _ => self.format_codec(iast),
+            };
             variants.push(SumVariantClass {
                 class_name: self.class_name(&variant_name),
                 special_case: parsed.special_case,
+                codec,
+                validation_checks: self.validation_checks(iast, "value", "$path", 0),
                 // Prefer the `$ref` schema name; inline arms fall back to
                 // `variant{N}`.
                 short_name: short_variant_name(union_inner_name, index),
+                json_type: self.json_type(iast),
             });
             for f in parsed.files.into_iter() {
                 sub_file_dependencies.push(f);
@@ -454,9 +863,10 @@ class BEAM{}Model implements BEAMSerde {{
         }
 
         content.push_str(&format!(
-            "\n{}sealed class {} implements BEAMSerde {{\n\t{}{}();",
+            "\n{}sealed class {} implements {} {{\n\t{}{}();",
             doc_str,
             class_name,
+            self.serde_interfaces(),
             if self.vars_should_be_final {
                 "const "
             } else {
@@ -464,20 +874,42 @@ class BEAM{}Model implements BEAMSerde {{
             },
             class_name
         ));
-        content.push_str("\n\n\t@Deprecated(\"not deprecated, but usage is highly discouraged, as its not deterministic\")");
-        content.push_str(&format!(
-            "\n\tfactory {}.fromJson(dynamic json) {{\n\t\tfinal errors = <String,Object>{{}};",
-            class_name
-        ));
-        for variant in variants.iter() {
+        // variants of distinct JSON types are told apart by the value at hand
+        let json_types: Option<HashSet<&str>> =
+            variants.iter().map(|variant| variant.json_type).collect();
+        if json_types.is_some_and(|json_types| json_types.len() == variants.len()) {
             content.push_str(&format!(
-                "\n\t\ttry{{\n\t\t\treturn {}_.fromJson(json);\n\t\t}} catch(e) {{errors['{}']=e;}}",
-                variant.class_name, variant.class_name
+                "\n\n\tfactory {}.fromJson(dynamic json) => switch (json) {{",
+                class_name
             ));
+            // integers are numbers as well
+            let mut by_json_type: Vec<_> = variants.iter().collect();
+            by_json_type.sort_by_key(|variant| variant.json_type == Some("num"));
+            for variant in by_json_type {
+                content.push_str(&format!(
+                    "\n\t\t{} _ => {}_.fromJson(json),",
+                    variant.json_type.unwrap_or_default(),
+                    variant.class_name
+                ));
+            }
+            content.push_str(&format!(
+                "\n\t\t_ => throw BEAMWrongTypeError('$json is not a valid {}'),\n\t}};",
+                class_name
+            ));
+        } else {
+            content.push_str("\n\n\t@Deprecated(\"not deprecated, but usage is highly discouraged, as its not deterministic\")");
+            content.push_str(&format!(
+                "\n\tfactory {}.fromJson(dynamic json) {{\n\t\tfinal errors = <String,Object>{{}};",
+                class_name
+            ));
+            for variant in variants.iter() {
+                content.push_str(&format!(
+                    "\n\t\ttry{{\n\t\t\treturn {}_.fromJson(json);\n\t\t}} catch(e) {{errors['{}']=e;}}",
+                    variant.class_name, variant.class_name
+                ));
+            }
+            content.push_str("\n\t\tthrow BEAMUnionParseMultiError(errors);\n\t}");
         }
-        content.push_str(&format!(
-            "\n\t\tthrow BEAMUnionParseMultiError(errors);\n\t}}",
-        ));
 
         // Arms are named by their `$ref` schema name where available, falling
         // back to a positional `variant{N}` for inline arms.
@@ -534,49 +966,55 @@ class BEAM{}Model implements BEAMSerde {{
                 },
                 variant.class_name
             ));
+            let collection = not_built
+                .as_ref()
+                .and_then(|special_case| special_case.reason.collection());
+            let is_primitive = matches!(
+                not_built,
+                Some(GenerationSpecialCase {
+                    reason: GenerationSpecialCaseType::Primitive,
+                    type_name: _,
+                })
+            );
             content.push_str(&format!(
                 "\n  @override\n  dynamic toJson() => {};\n",
-                match not_built {
-                    Some(GenerationSpecialCase {
-                        reason: GenerationSpecialCaseType::Primitive,
-                        type_name: _,
-                    }) => "value".to_string(),
-                    Some(GenerationSpecialCase {
-                        reason: GenerationSpecialCaseType::List(inner_type, is_primitive),
-                        type_name: _,
-                    }) =>
-                        if *is_primitive {
-                            format!("value.map((e) => e as {}).toList()", inner_type)
-                        } else {
-                            format!("value.map((e) => e.toJson()).toList()")
-                        },
-                    _ => "value.toJson()".to_string(),
+                match (collection, variant.codec) {
+                    (Some((collection, _, _)), Some(codec)) => {
+                        collection.encode("value", &codec.encode("e", false))
+                    }
+                    (None, Some(codec)) => codec.encode("value", false),
+                    (Some((collection, inner_type, true)), None) => {
+                        collection.encode("value", &format!("e as {}", inner_type))
+                    }
+                    (Some((collection, _, false)), None) => {
+                        collection.encode("value", "e.toJson()")
+                    }
+                    (None, None) if is_primitive => "value".to_string(),
+                    (None, None) => "value.toJson()".to_string(),
                 }
             ));
             content.push_str(&format!(
                 "  factory {}_.fromJson(dynamic json) => \n\t\t{}_({});\n",
                 variant.class_name,
                 variant.class_name,
-                match not_built {
-                    Some(GenerationSpecialCase {
-                        reason: GenerationSpecialCaseType::Primitive,
-                        type_name: _,
-                    }) => "json".to_string(),
-                    Some(GenerationSpecialCase {
-                        reason: GenerationSpecialCaseType::List(inner_type, is_primitive),
-                        type_name: _,
-                    }) =>
-                        if *is_primitive {
-                            format!("(json as List).map((e) => e as {}).toList()", inner_type)
-                        } else {
-                            format!(
-                                "(json as List).map((e) => {}.fromJson(e)).toList()",
-                                inner_type
-                            )
-                        },
-                    _ => format!("{}.fromJson(json)", value_type_name),
+                match (collection, variant.codec) {
+                    (Some((collection, _, _)), Some(codec)) => {
+                        collection.decode("json", &codec.decode("e", false))
+                    }
+                    (None, Some(codec)) => codec.decode("json", false),
+                    (Some((collection, inner_type, true)), None) => {
+                        collection.decode("json", &format!("e as {}", inner_type))
+                    }
+                    (Some((collection, inner_type, false)), None) => {
+                        collection.decode("json", &format!("{}.fromJson(e)", inner_type))
+                    }
+                    (None, None) if is_primitive => "json".to_string(),
+                    (None, None) => format!("{}.fromJson(json)", value_type_name),
                 }
             ));
+            if self.generate_validators {
+                content.push_str(&Self::validate_method(&variant.validation_checks));
+            }
             content.push_str("}\n\n");
         }
 
@@ -702,21 +1140,31 @@ class BEAM{}Model implements BEAMSerde {{
 
         for v in variants {
             let vc = variant_class(v);
-            let to_json = match (&v.list_inner_type, v.is_primitive) {
-                (Some(inner), true) => format!("value.map((e) => e as {}).toList()", inner),
-                (Some(_), false) => "value.map((e) => e.toJson()).toList()".to_string(),
-                (None, true) => "value".to_string(),
-                (None, false) => "value.toJson()".to_string(),
+            let to_json = match (&v.collection, v.is_primitive, v.codec) {
+                (Some((collection, _)), _, Some(codec)) => {
+                    collection.encode("value", &codec.encode("e", false))
+                }
+                (None, _, Some(codec)) => codec.encode("value", false),
+                (Some((collection, inner)), true, None) => {
+                    collection.encode("value", &format!("e as {}", inner))
+                }
+                (Some((collection, _)), false, None) => collection.encode("value", "e.toJson()"),
+                (None, true, None) => "value".to_string(),
+                (None, false, None) => "value.toJson()".to_string(),
             };
-            let from_json = match (&v.list_inner_type, v.is_primitive) {
-                (Some(inner), true) => {
-                    format!("(json as List).map((e) => e as {}).toList()", inner)
+            let from_json = match (&v.collection, v.is_primitive, v.codec) {
+                (Some((collection, _)), _, Some(codec)) => {
+                    collection.decode("json", &codec.decode("e", false))
                 }
-                (Some(inner), false) => {
-                    format!("(json as List).map((e) => {}.fromJson(e)).toList()", inner)
+                (None, _, Some(codec)) => codec.decode("json", false),
+                (Some((collection, inner)), true, None) => {
+                    collection.decode("json", &format!("e as {}", inner))
                 }
-                (None, true) => "json".to_string(),
-                (None, false) => format!("{}.fromJson(json)", v.value_type),
+                (Some((collection, inner)), false, None) => {
+                    collection.decode("json", &format!("{}.fromJson(e)", inner))
+                }
+                (None, true, None) => "json".to_string(),
+                (None, false, None) => format!("{}.fromJson(json)", v.value_type),
             };
             content.push_str(&format!("class {}_ extends {} {{\n", vc, class_name));
             content.push_str(&format!("  {}{} value;\n", final_kw, v.value_type));
@@ -752,7 +1200,7 @@ class BEAM{}Model implements BEAMSerde {{
         let mut variants: Vec<DiscriminatedVariant> = Vec::new();
 
         for (index, (discriminator_value, annotated_ref)) in discrimination.map.iter().enumerate() {
-            let trimmed_link = sanitize(strip_ref_prefix(annotated_ref.path));
+            let trimmed_link = self.link_name(annotated_ref.path);
             variants.push(DiscriminatedVariant {
                 class_name: self.class_name(&format!("{}{}", name, trimmed_link)),
                 discriminator_value,
@@ -787,9 +1235,10 @@ class BEAM{}Model implements BEAMSerde {{
 
         // Sealed class definition
         content.push_str(&format!(
-            "\n{}sealed class {} implements BEAMSerde {{\n\t{}{}();",
+            "\n{}sealed class {} implements {} {{\n\t{}{}();",
             doc_str,
             class_name,
+            self.serde_interfaces(),
             if self.vars_should_be_final {
                 "const "
             } else {
@@ -864,6 +1313,11 @@ class BEAM{}Model implements BEAMSerde {{
                 variant.referenced_type_name
             ));
             content.push_str("\n  @override\n  dynamic toJson() => value.toJson();\n");
+            if self.generate_validators {
+                content.push_str(&Self::validate_method(
+                    "BEAMValidate.nested(violations, '$path', value);",
+                ));
+            }
             content.push_str("}\n\n");
         }
 
@@ -897,7 +1351,7 @@ class BEAM{}Model implements BEAMSerde {{
             content.push_str(&format!("  ///{}\n", desc.replace("\n", "\n  ///")));
             content.push_str(&format!("  t_{},\n", enum_value));
         }
-        content.push_str(&"\t;\n\n\t@override\n\tdynamic toJson() => switch(this) {\n");
+        content.push_str("\t;\n\n\t@override\n\tdynamic toJson() => switch(this) {\n");
         for (orig_value, enum_value, is_string, _) in allowed_values_str.iter() {
             content.push_str(&format!(
                 "\t\tt_{} => {},\n",
@@ -951,9 +1405,126 @@ class BEAM{}Model implements BEAMSerde {{
         name: &str,
         doc_str: &str,
         product: &HashMap<&str, intermediate::IAST>,
+        additional: Option<&intermediate::IAST>,
         depth: usize,
     ) -> GeneratedCode {
-        let class_name = self.class_name(name);
+        let properties =
+            self.product_properties(name, doc_str, product, additional, &HashSet::new(), depth);
+        let mut content = Self::product_imports(properties.files.iter(), depth);
+        content.push_str(&self.product_class(name, doc_str, &properties, &ClassRole::Standalone));
+        GeneratedCode {
+            content,
+            files: properties.into_files(),
+        }
+    }
+
+    /// The sealed base class of an `allOf` hierarchy together with the classes
+    /// of the schemes extending it, as Dart only allows subclassing a sealed
+    /// class within its library. The files of the subclasses just export this
+    /// one (see [`Self::extended_base`]).
+    fn generate_polymorphic_type(
+        &self,
+        name: &str,
+        doc_str: &str,
+        product: &HashMap<&str, intermediate::IAST>,
+        discrimination: &intermediate::types::Discrimination,
+        depth: usize,
+    ) -> GeneratedCode {
+        let key = discrimination.key;
+        let base =
+            self.product_properties(name, doc_str, product, None, &HashSet::from([key]), depth);
+        // the subclasses leave the base's properties to it
+        let mut inherited: HashSet<&str> = product.keys().copied().collect();
+        inherited.insert(key);
+        let empty = HashMap::new();
+        let subclasses: Vec<_> = discrimination
+            .map
+            .iter()
+            .filter_map(|(value, reference)| {
+                let scheme = self.complete_iast?.find_scheme(reference.path)?;
+                let sub_name = self.link_name(scheme.name);
+                let (sub_doc_str, sub_product, sub_additional) = match &scheme.obj {
+                    intermediate::IAST::Object(
+                        annotated_obj @ AnnotatedObj {
+                            value: intermediate::AlgType::Product(sub_product, sub_additional),
+                            ..
+                        },
+                    ) => (
+                        mk_doc_str(&sub_name, annotated_obj, 0),
+                        sub_product,
+                        sub_additional.as_deref(),
+                    ),
+                    // extending without adding anything
+                    _ => (format!("/// {}\n", sub_name), &empty, None),
+                };
+                let properties = self.product_properties(
+                    &sub_name,
+                    &sub_doc_str,
+                    sub_product,
+                    sub_additional,
+                    &inherited,
+                    depth,
+                );
+                Some((*value, sub_name, sub_doc_str, properties))
+            })
+            .collect();
+
+        let mut content = Self::product_imports(
+            base.files.iter().chain(
+                subclasses
+                    .iter()
+                    .flat_map(|(_, _, _, properties)| properties.files.iter()),
+            ),
+            depth,
+        );
+        content.push_str(
+            &self.product_class(
+                name,
+                doc_str,
+                &base,
+                &ClassRole::Base {
+                    key,
+                    subclasses: subclasses
+                        .iter()
+                        .map(|(value, sub_name, _, _)| (*value, self.class_name(sub_name)))
+                        .collect(),
+                },
+            ),
+        );
+        let base_class = self.class_name(name);
+        for (value, sub_name, sub_doc_str, properties) in subclasses.iter() {
+            content.push('\n');
+            content.push_str(&self.product_class(
+                sub_name,
+                sub_doc_str,
+                properties,
+                &ClassRole::Subclass {
+                    base: &base_class,
+                    inherited: &base,
+                    key,
+                    value,
+                },
+            ));
+        }
+        let mut files = Vec::new();
+        for (_, _, _, properties) in subclasses.into_iter() {
+            files.extend(properties.into_files());
+        }
+        files.extend(base.into_files());
+        GeneratedCode { content, files }
+    }
+
+    /// The properties of `product` apart from the `skipped` ones, with their
+    /// `additional` ones, for [`Self::product_class`].
+    fn product_properties<'p>(
+        &self,
+        name: &str,
+        doc_str: &str,
+        product: &'p HashMap<&'p str, intermediate::IAST>,
+        additional: Option<&intermediate::IAST>,
+        skipped: &HashSet<&str>,
+        depth: usize,
+    ) -> ProductProperties<'p> {
         let mut file_dependencies = Vec::new();
         let mut file_sub_dependencies = Vec::new();
         let mut properties: Vec<Property> = Vec::new();
@@ -965,8 +1536,28 @@ class BEAM{}Model implements BEAMSerde {{
             props.sort_by_key(|(p_name, _)| p_name.to_string());
             props
         };
+        let mut validation_checks = String::new();
         for (p_name, iast) in sorted_props.iter() {
+            if skipped.contains(*p_name)
+                || self
+                    .direction
+                    .is_some_and(|direction| direction.omits(iast))
+            {
+                continue;
+            }
+            // a model shared by requests and responses lacks these in one of them
+            let access_optional = self.read_write_models != ReadWriteModels::Ignore
+                && self.direction.is_none()
+                && (iast.is_read_only() || iast.is_write_only());
             let sanitized_p_name = sanitize(p_name);
+            if self.generate_validators {
+                validation_checks.push_str(&self.validation_checks(
+                    iast,
+                    &create_property_name(p_name),
+                    &format!("$path{}", json_path_member(p_name)),
+                    0,
+                ));
+            }
             if let intermediate::IAST::Primitive(prim) = &iast {
                 let (prim_type, prim_data) = match &prim.value {
                     intermediate::types::Primitive::Enum(allowed_values) => {
@@ -986,9 +1577,10 @@ class BEAM{}Model implements BEAMSerde {{
                         extra_content.push_str(&enum_code.content);
                         (enum_code.class_name, PropertyType::Normal)
                     }
-                    intermediate::types::Primitive::List(inner_iast) => {
+                    intermediate::types::Primitive::List(inner_iast)
+                    | intermediate::types::Primitive::Map(inner_iast) => {
                         let mut full_name = &format!("{}_{}", name, sanitized_p_name);
-                        let parsed = self.parse_named_iast(&full_name, inner_iast, depth + 1);
+                        let parsed = self.parse_named_iast(full_name, inner_iast, depth + 1);
 
                         if let Some(GenerationSpecialCase {
                             type_name: _,
@@ -1009,63 +1601,18 @@ class BEAM{}Model implements BEAMSerde {{
                         for f in parsed.files.into_iter() {
                             file_sub_dependencies.push(f);
                         }
-                        let inner_class_name = self.class_name(&full_name);
+                        let collection = Collection::of(&prim.value).unwrap();
+                        let inner_class_name = self.class_name(full_name);
                         (
-                            format!("{}<{}>", to_dart_prim(&prim.value), inner_class_name),
-                            PropertyType::Primitive(PrimitivePropertyType::List {
+                            collection.dart_type(&inner_class_name),
+                            PropertyType::Primitive(PrimitivePropertyType::Collection {
+                                collection,
                                 inner_type: inner_class_name,
-                                inner_is_primitive: match &**inner_iast {
-                                    // enums are not considered primitive in dart because e.g. they need to be parsed with .fromJson
-                                    intermediate::IAST::Primitive(AnnotatedObj {
-                                        value: Primitive::Enum(_),
-                                        ..
-                                    }) => false,
-                                    // `Never` -> `UnknownBEAMObject`, which
-                                    // implements `BEAMSerde`, so its list
-                                    // elements are (de)serialized via
-                                    // `.fromJson` / `.toJson()` like a class
-                                    // rather than cast as raw primitives.
-                                    intermediate::IAST::Primitive(AnnotatedObj {
-                                        value: Primitive::Never,
-                                        ..
-                                    }) => false,
-                                    intermediate::IAST::Primitive(_) => true,
-                                    // A reference may point at a scheme that is
-                                    // itself just a primitive typedef (e.g.
-                                    // `typedef BEAMCmsObjectIdParamModel = String;`).
-                                    // In that case the list elements have no
-                                    // `.toJson()` / `.fromJson()` and must be
-                                    // treated as primitives.
-                                    intermediate::IAST::Reference(_) => {
-                                        self.iast_resolves_to_primitive(inner_iast)
-                                    }
-                                    _ => false,
-                                },
+                                inner_codec: self.format_codec(inner_iast),
+                                inner_is_primitive: self.iast_resolves_to_primitive(inner_iast),
                             }),
                         )
                     }
-                    intermediate::types::Primitive::Map(inner_iast) => {
-                        let full_name = format!("{}_{}", name, sanitized_p_name);
-                        let parsed = self.parse_named_iast(&full_name, inner_iast, depth + 1);
-                        file_dependencies.push(File {
-                            path: std::path::PathBuf::from(format!(
-                                "{}/{}.dart",
-                                name, sanitized_p_name
-                            )),
-                            content: parsed.content,
-                        });
-                        for f in parsed.files.into_iter() {
-                            file_sub_dependencies.push(f);
-                        }
-                        (
-                            format!(
-                                "{}<String,{}>",
-                                to_dart_prim(&prim.value),
-                                self.class_name(&full_name)
-                            ),
-                            PropertyType::Primitive(PrimitivePropertyType::Default),
-                        )
-                    }
                     // `Never` -> `UnknownBEAMObject`, which implements
                     // `BEAMSerde` (`.fromJson` / `.toJson`). Treat it like a
                     // class property so it is (de)serialized rather than
@@ -1075,16 +1622,23 @@ class BEAM{}Model implements BEAMSerde {{
                     }
                     _ => (
                         to_dart_prim(&prim.value),
-                        PropertyType::Primitive(PrimitivePropertyType::Default),
+                        PropertyType::Primitive(match FormatCodec::of(&prim.value) {
+                            Some(codec) => PrimitivePropertyType::Format(codec),
+                            None => PrimitivePropertyType::Default,
+                        }),
                     ),
                 };
+                // a property with a default always has a value
+                let default = self.default_literal(iast, &prim_type);
                 properties.push(Property {
                     name: p_name,
+                    nullable: prim.nullable
+                        || ((prim.optional || access_optional) && default.is_none()),
+                    optional: (prim.optional || access_optional) && default.is_none(),
                     typ: prim_type,
-                    nullable: prim.nullable || prim.optional,
-                    optional: prim.optional,
-                    doc_str: mk_doc_str(p_name, &prim, 1),
+                    doc_str: mk_doc_str(p_name, prim, 1),
                     prop_type: prim_data,
+                    default,
                 });
                 continue;
             }
@@ -1108,20 +1662,33 @@ class BEAM{}Model implements BEAMSerde {{
             // have `.toJson()` / `.fromJson()` called on it, because Dart
             // built-in types like `String` don't expose those methods.
             // Detect that case and fall through to the primitive code path.
-            let prop_type = if matches!(iast, intermediate::IAST::Reference(_))
-                && self.iast_resolves_to_primitive(iast)
-            {
+            // Formatted primitives behind a reference still need their codec.
+            let prop_type = if !matches!(iast, intermediate::IAST::Reference(_)) {
+                PropertyType::Normal
+            } else if let Some((values, values_are_primitive)) = self.referenced_map_values(iast) {
+                PropertyType::Primitive(PrimitivePropertyType::Collection {
+                    collection: Collection::Map,
+                    inner_type: values,
+                    inner_codec: self.element_format_codec(iast),
+                    inner_is_primitive: values_are_primitive,
+                })
+            } else if let Some(codec) = self.format_codec(iast) {
+                PropertyType::Primitive(PrimitivePropertyType::Format(codec))
+            } else if self.iast_resolves_to_primitive(iast) {
                 PropertyType::Primitive(PrimitivePropertyType::Default)
             } else {
                 PropertyType::Normal
             };
+            let default = self.default_literal(iast, &type_name);
             properties.push(Property {
                 name: p_name,
                 typ: type_name,
-                nullable: parsed.nullable || parsed.optional,
-                optional: parsed.optional,
+                nullable: parsed.nullable
+                    || ((parsed.optional || access_optional) && default.is_none()),
+                optional: (parsed.optional || access_optional) && default.is_none(),
                 doc_str: "".to_string(),
                 prop_type,
+                default,
             });
             file_dependencies.push(File {
                 path: std::path::PathBuf::from(format!("{}/{}.dart", name, sanitized_p_name)),
@@ -1132,21 +1699,114 @@ class BEAM{}Model implements BEAMSerde {{
             }
         }
 
+        // properties beyond the fixed ones are kept in a map of their own
+        let additional = match additional {
+            Some(values) => {
+                let field = if properties
+                    .iter()
+                    .any(|prop| create_property_name(prop.name) == "additional")
+                {
+                    "$additional"
+                } else {
+                    "additional"
+                };
+                let file_name = sanitize(field);
+                let mut full_name = format!("{}_{}", name, file_name);
+                let parsed = self.parse_named_iast(&full_name, values, depth + 1);
+                if let Some(GenerationSpecialCase {
+                    type_name: _,
+                    reason: GenerationSpecialCaseType::Link(internal_type_name),
+                }) = parsed.special_case
+                {
+                    full_name = internal_type_name;
+                }
+                file_dependencies.push(File {
+                    path: std::path::PathBuf::from(format!("{}/{}.dart", name, file_name)),
+                    content: parsed.content,
+                });
+                file_sub_dependencies.extend(parsed.files);
+                if self.generate_validators {
+                    validation_checks.push_str(&format!(
+                        "BEAMValidate.nested(violations, '$path', {});\n",
+                        field
+                    ));
+                }
+                Some(AdditionalProperties {
+                    field,
+                    value_type: self.class_name(&full_name),
+                    is_primitive: self.iast_resolves_to_primitive(values),
+                    codec: self.format_codec(values),
+                })
+            }
+            None => None,
+        };
+
+        ProductProperties {
+            properties,
+            additional,
+            known_keys: sorted_props.iter().map(|(p_name, _)| **p_name).collect(),
+            validation_checks,
+            extra_content,
+            files: file_dependencies,
+            sub_files: file_sub_dependencies
+                .into_iter()
+                .map(|f| File {
+                    path: std::path::PathBuf::from(format!("{}/{}", name, f.path.display())),
+                    content: f.content,
+                })
+                .collect(),
+        }
+    }
+
+    /// The serde import and the imports (and exports) of `files`, which head
+    /// the library of a product class.
+    fn product_imports<'f>(files: impl Iterator<Item = &'f File>, depth: usize) -> String {
         let mut content = String::new();
         content.push_str(&format!(
             "import '../{}utils/serde.dart';\n",
             "../".repeat(depth)
         ));
-        for f in file_dependencies.iter() {
+        for f in files {
             content.push_str(&format!("import '{}';\n", f.path.display()));
             content.push_str(&format!("export '{}';\n", f.path.display()));
         }
         content.push_str("\n\n");
+        content
+    }
 
-        content.push_str(&format!(
-            "{}class {} implements BEAMSerde {{\n",
-            doc_str, class_name
-        ));
+    /// The class of a product type with the given `properties`, playing `role`.
+    fn product_class(
+        &self,
+        name: &str,
+        doc_str: &str,
+        props: &ProductProperties,
+        role: &ClassRole,
+    ) -> String {
+        let class_name = self.class_name(name);
+        let properties = &props.properties;
+        let additional = &props.additional;
+        let inherited: &[Property] = match role {
+            ClassRole::Subclass { inherited, .. } => &inherited.properties,
+            _ => &[],
+        };
+        let mut content = String::new();
+        content.push_str(&match role {
+            ClassRole::Standalone => format!(
+                "{}class {} implements {} {{\n",
+                doc_str,
+                class_name,
+                self.serde_interfaces()
+            ),
+            ClassRole::Base { .. } => format!(
+                "{}sealed class {} implements {} {{\n",
+                doc_str,
+                class_name,
+                self.serde_interfaces()
+            ),
+            ClassRole::Subclass { base, .. } => {
+                format!("{}class {} extends {} {{\n", doc_str, class_name, base)
+            }
+        });
         for prop in properties.iter() {
             content.push_str(&format!(
                 "\n{}  {}{}{} {};\n",
@@ -1162,6 +1822,33 @@ class BEAM{}Model implements BEAMSerde {{
             ));
         }
 
+        match role {
+            ClassRole::Base { key, .. } => content.push_str(&format!(
+                "\n  /// The `{}` telling the subclasses apart.\n  String get {};\n",
+                key,
+                create_property_name(key)
+            )),
+            ClassRole::Subclass { key, value, .. } => content.push_str(&format!(
+                "\n  @override\n  String get {} => {};\n",
+                create_property_name(key),
+                dart_string_literal(value)
+            )),
+            ClassRole::Standalone => {}
+        }
+
+        if let Some(additional) = &additional {
+            content.push_str(&format!(
+                "\n  /// The properties not listed above (`additionalProperties`).\n  {}{} {};\n",
+                if self.vars_should_be_final {
+                    "final "
+                } else {
+                    ""
+                },
+                Collection::Map.dart_type(&additional.value_type),
+                additional.field
+            ));
+        }
+
         // constructor
         content.push_str(&format!(
             "\n\n  {}{}({{\n",
@@ -1172,46 +1859,103 @@ class BEAM{}Model implements BEAMSerde {{
             },
             class_name
         ));
-        for prop in properties.iter() {
+        let parameters = inherited
+            .iter()
+            .map(|prop| (prop, "super"))
+            .chain(properties.iter().map(|prop| (prop, "this")));
+        for (prop, receiver) in parameters {
+            content.push_str(&match &prop.default {
+                Some(default) => {
+                    format!(
+                        "    {}.{} = {},\n",
+                        receiver,
+                        create_property_name(prop.name),
+                        default
+                    )
+                }
+                None => format!(
+                    "    {}{}.{},\n",
+                    if !prop.nullable { "required " } else { "" },
+                    receiver,
+                    create_property_name(prop.name)
+                ),
+            });
+        }
+        if let Some(additional) = &additional {
+            content.push_str(&format!("    this.{} = const {{}},\n", additional.field));
+        }
+        content.push_str("  });\n");
+
+        if let ClassRole::Base { key, subclasses } = role {
             content.push_str(&format!(
-                "    {}this.{},\n",
-                if !prop.nullable { "required " } else { "" },
-                create_property_name(prop.name)
+                "\n  factory {}.fromJson(Map<String,dynamic> json) => switch (json['{}']) {{\n",
+                class_name, key
             ));
+            for (value, subclass) in subclasses.iter() {
+                content.push_str(&format!(
+                    "    {} => {}.fromJson(json),\n",
+                    dart_string_literal(value),
+                    subclass
+                ));
+            }
+            content.push_str(&format!(
+                "    final discriminator => throw BEAMUnknownValueError('{}: unknown discriminator value $discriminator'),\n  }};\n}}\n",
+                class_name
+            ));
+            content.push_str(&props.extra_content);
+            return content;
         }
-        content.push_str("  });\n");
 
         //to json
         content.push_str("\n\n  @override\n  Map<String,dynamic> toJson() => {\n");
-        for prop in properties.iter() {
+        // first, so that they never override a fixed property
+        if let Some(additional) = &additional {
+            content.push_str(&format!(
+                "    ...{},\n",
+                if additional.is_primitive && additional.codec.is_none() {
+                    additional.field.to_string()
+                } else {
+                    Collection::Map.encode(
+                        additional.field,
+                        &element_encode(additional.is_primitive, additional.codec),
+                    )
+                }
+            ));
+        }
+        if let ClassRole::Subclass { key, .. } = role {
+            content.push_str(&format!("    '{}': {},\n", key, create_property_name(key)));
+        }
+        for prop in inherited.iter().chain(properties.iter()) {
+            let prop_name = create_property_name(prop.name);
             content.push_str(&format!(
-                "    {}'{}': {}{},\n",
+                "    {}'{}': {},\n",
                 if prop.optional {
-                    format!("if({} != null) ", create_property_name(prop.name))
+                    format!("if({} != null) ", prop_name)
                 } else {
                     "".to_string()
                 },
                 prop.name,
-                create_property_name(prop.name),
-                if let PropertyType::Normal = prop.prop_type {
-                    String::from("?.toJson()")
-                } else {
-                    match &prop.prop_type {
-                        PropertyType::Primitive(PrimitivePropertyType::List {
-                            inner_is_primitive,
-                            ..
-                        }) => {
-                            format!(
-                                "?.map((e) => {}).toList()",
-                                if *inner_is_primitive {
-                                    "e"
-                                } else {
-                                    "e.toJson()"
-                                }
-                            )
-                        }
-                        _ => "".to_string(),
+                match &prop.prop_type {
+                    PropertyType::Normal => format!("{}?.toJson()", prop_name),
+                    PropertyType::Primitive(PrimitivePropertyType::Collection {
+                        collection,
+                        inner_is_primitive,
+                        inner_codec,
+                        ..
+                    }) => collection.encode(
+                        &format!("{}?", prop_name),
+                        &element_encode(*inner_is_primitive, *inner_codec),
+                    ),
+                    // optional properties are only written when non-null
+                    PropertyType::Primitive(PrimitivePropertyType::Format(codec))
+                        if prop.optional =>
+                    {
+                        codec.encode(&format!("{}!", prop_name), false)
                     }
+                    PropertyType::Primitive(PrimitivePropertyType::Format(codec)) => {
+                        codec.encode(&prop_name, prop.nullable)
+                    }
+                    PropertyType::Primitive(PrimitivePropertyType::Default) => prop_name.clone(),
                 }
             ));
         }
@@ -1222,42 +1966,51 @@ class BEAM{}Model implements BEAMSerde {{
             "\n  factory {}.fromJson(Map<String,dynamic> json) => {}(\n",
             class_name, class_name
         ));
-        for prop in properties.iter() {
+        for prop in inherited.iter().chain(properties.iter()) {
             content.push_str(&format!(
                 "    {}: {},\n",
                 create_property_name(prop.name),
                 if let PropertyType::Primitive(prim) = &prop.prop_type {
                     match prim {
-                        PrimitivePropertyType::List {
+                        PrimitivePropertyType::Collection {
+                            collection,
                             inner_type,
                             inner_is_primitive,
+                            inner_codec,
                         } => {
                             format!(
-                                "{} (json['{}'] as List).map((e) => {}).toList()",
+                                "{} {}",
                                 if prop.nullable {
                                     format!("json['{}'] == null ? null : ", prop.name)
                                 } else {
                                     "".to_string()
                                 },
-                                prop.name,
-                                if *inner_is_primitive {
-                                    format!("e as {}", inner_type)
-                                } else {
-                                    format!("{}.fromJson(e)", inner_type)
-                                },
+                                collection.decode(
+                                    &format!("json['{}']", prop.name),
+                                    &element_decode(inner_type, *inner_is_primitive, *inner_codec),
+                                ),
                             )
                         }
+                        PrimitivePropertyType::Format(codec) => {
+                            codec.decode(&format!("json['{}']", prop.name), prop.nullable)
+                        }
                         PrimitivePropertyType::Default => {
-                            format!("((val){{ if (val is {}{}) return val; throw BEAMWrongTypeError('$val is not of type {} for property {}'); }})(json['{}'])", prop.typ, if prop.nullable { "?" } else { "" }, prop.typ, prop.name, prop.name)
+                            let json_value = match &prop.default {
+                                Some(default) => format!("json['{}'] ?? {}", prop.name, default),
+                                None => format!("json['{}']", prop.name),
+                            };
+                            format!("((val){{ if (val is {}{}) return val; throw BEAMWrongTypeError('$val is not of type {} for property {}'); }})({})", prop.typ, if prop.nullable { "?" } else { "" }, prop.typ, prop.name, json_value)
                         }
                     }
                 } else {
                     format!(
                         "{}{}.fromJson(json['{}'])",
-                        if prop.nullable {
-                            format!("json['{}'] == null ? null : ", prop.name)
-                        } else {
-                            "".to_string()
+                        match (&prop.default, prop.nullable) {
+                            (Some(default), _) => {
+                                format!("json['{}'] == null ? {} : ", prop.name, default)
+                            }
+                            (None, true) => format!("json['{}'] == null ? null : ", prop.name),
+                            (None, false) => "".to_string(),
                         },
                         prop.typ,
                         prop.name,
@@ -1265,17 +2018,37 @@ class BEAM{}Model implements BEAMSerde {{
                 }
             ));
         }
+        if let Some(additional) = &additional {
+            content.push_str(&format!(
+                "    {}: {{\n      for (final MapEntry(:key, value: e) in json.entries)\n        if (!const <String>{{{}}}.contains(key)) key: {},\n    }},\n",
+                additional.field,
+                props
+                    .known_keys
+                    .iter()
+                    .map(|p_name| dart_string_literal(p_name))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                element_decode(
+                    &additional.value_type,
+                    additional.is_primitive,
+                    additional.codec
+                ),
+            ));
+        }
         content.push_str("  );\n");
-        content.push_str("}\n");
-        content.push_str(&extra_content);
-        file_dependencies.extend(file_sub_dependencies.into_iter().map(|f| File {
-            path: std::path::PathBuf::from(format!("{}/{}", name, f.path.display())),
-            content: f.content,
-        }));
-        GeneratedCode {
-            content,
-            files: file_dependencies,
+        if self.generate_validators {
+            let inherited_checks = match role {
+                ClassRole::Subclass { inherited, .. } => inherited.validation_checks.as_str(),
+                _ => "",
+            };
+            content.push_str(&Self::validate_method(&format!(
+                "{}{}",
+                inherited_checks, props.validation_checks
+            )));
         }
+        content.push_str("}\n");
+        content.push_str(&props.extra_content);
+        content
     }
 }
 
@@ -1292,6 +2065,15 @@ fn mk_doc_str<T>(name: &str, annotated_obj: &intermediate::AnnotatedObj<T>, tabs
             description.replace("\n", format!("\n{}///", "\t".repeat(tabs)).as_str())
         ));
     }
+    if let Some(format) = annotated_obj.format {
+        doc_str.push_str(&format!("{}/// FORMAT: {}\n", "\t".repeat(tabs), format));
+    }
+    if annotated_obj.read_only {
+        doc_str.push_str(&format!("{}/// READ ONLY\n", "\t".repeat(tabs)));
+    }
+    if annotated_obj.write_only {
+        doc_str.push_str(&format!("{}/// WRITE ONLY\n", "\t".repeat(tabs)));
+    }
     if annotated_obj.is_deprecated {
         doc_str.push_str(&format!("{}/// DEPRECATED\n", "\t".repeat(tabs)));
         doc_str.push_str(&format!("{}@deprecated\n", "\t".repeat(tabs)));
@@ -1299,6 +2081,40 @@ fn mk_doc_str<T>(name: &str, annotated_obj: &intermediate::AnnotatedObj<T>, tabs
     doc_str
 }
 
+/// The JSON path member selecting the property `name`, as the content of a
+/// Dart string literal: `.name` for plain identifiers, `["name"]` otherwise.
+fn json_path_member(name: &str) -> String {
+    let is_identifier = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if is_identifier {
+        format!(".{}", name)
+    } else {
+        format!("[\"{}\"]", escape_dart_string(&name.replace('"', "\\\"")))
+    }
+}
+
+/// Escapes `s` for use inside a single-quoted Dart string literal.
+pub(super) fn escape_dart_string(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\'', "\\'")
+        .replace('$', "\\$")
+        .replace('\n', "\\n")
+}
+
+/// `s` as a Dart string literal, raw where possible so regular expressions
+/// stay readable.
+pub(super) fn dart_string_literal(s: &str) -> String {
+    if s.contains('\n') {
+        format!("'{}'", escape_dart_string(s))
+    } else if !s.contains('\'') {
+        format!("r'{}'", s)
+    } else if !s.contains('"') {
+        format!("r\"{}\"", s)
+    } else {
+        format!("'{}'", escape_dart_string(s))
+    }
+}
+
 pub fn sanitize(name: &str) -> String {
     name.chars()
         .map(|c| if c.is_alphanumeric() { c } else { '_' })
@@ -1373,7 +2189,7 @@ pub fn create_property_name(name: &str) -> String {
     }
 }
 
-fn to_dart_prim(primitive: &intermediate::types::Primitive) -> String {
+pub(super) fn to_dart_prim(primitive: &intermediate::types::Primitive) -> String {
     use intermediate::types::Primitive;
     match primitive {
         Primitive::String => "String".to_string(),
@@ -1386,6 +2202,51 @@ fn to_dart_prim(primitive: &intermediate::types::Primitive) -> String {
         Primitive::Enum(_) => "Enum".to_string(),
         Primitive::Dynamic => "dynamic".to_string(),
         Primitive::Binary => "Uint8List".to_string(),
+        Primitive::DateTime | Primitive::Date => "DateTime".to_string(),
+        Primitive::Uri => "Uri".to_string(),
+        Primitive::Base64 => "Uint8List".to_string(),
+    }
+}
+
+/// How a formatted primitive (whose Dart type is not a JSON value) is
+/// converted from and to JSON, via the `BEAMFormats` helpers of
+/// `utils/serde.dart`.
+#[derive(Clone, Copy)]
+pub(super) struct FormatCodec(&'static str);
+
+impl FormatCodec {
+    pub(super) fn of(primitive: &Primitive) -> Option<Self> {
+        match primitive {
+            Primitive::DateTime => Some(Self("dateTime")),
+            Primitive::Date => Some(Self("date")),
+            Primitive::Uri => Some(Self("uri")),
+            Primitive::Base64 => Some(Self("bytes")),
+            _ => None,
+        }
+    }
+
+    /// Dart expression decoding the JSON value `json`, keeping `null` as is
+    /// when `nullable`.
+    pub(super) fn decode(&self, json: &str, nullable: bool) -> String {
+        let decoded = format!("BEAMFormats.{}FromJson({})", self.0, json);
+        if nullable {
+            format!("{} == null ? null : {}", json, decoded)
+        } else {
+            decoded
+        }
+    }
+
+    /// Dart expression encoding `value` into its JSON string, keeping `null`
+    /// as is when `nullable`.
+    pub(super) fn encode(&self, value: &str, nullable: bool) -> String {
+        if nullable {
+            format!(
+                "{} == null ? null : BEAMFormats.{}ToJson({}!)",
+                value, self.0, value
+            )
+        } else {
+            format!("BEAMFormats.{}ToJson({})", self.0, value)
+        }
     }
 }
 
@@ -1396,6 +2257,9 @@ struct Property<'a> {
     optional: bool,
     doc_str: String,
     prop_type: PropertyType,
+    /// Dart expression of the schema's `default`, used when the property is
+    /// not given.
+    default: Option<String>,
 }
 
 enum PropertyType {
@@ -1404,13 +2268,89 @@ enum PropertyType {
 }
 
 enum PrimitivePropertyType {
-    List {
+    /// A `List` or `Map`, (de)serialized element by element.
+    Collection {
+        collection: Collection,
         inner_type: String,
         inner_is_primitive: bool,
+        inner_codec: Option<FormatCodec>,
     },
+    /// A formatted primitive, converted through its codec.
+    Format(FormatCodec),
     Default,
 }
 
+/// The members [`SchemeAdder::product_class`] declares, and the files their
+/// types live in.
+struct ProductProperties<'p> {
+    properties: Vec<Property<'p>>,
+    additional: Option<AdditionalProperties>,
+    /// All properties of the schema, including those declared elsewhere.
+    known_keys: Vec<&'p str>,
+    validation_checks: String,
+    /// The enums of the properties, placed after the class.
+    extra_content: String,
+    /// The files of the property types, relative to the class.
+    files: Vec<File>,
+    /// The files those depend on.
+    sub_files: Vec<File>,
+}
+
+impl ProductProperties<'_> {
+    fn into_files(self) -> Vec<File> {
+        let mut files = self.files;
+        files.extend(self.sub_files);
+        files
+    }
+}
+
+/// The part a class generated by [`SchemeAdder::product_class`] plays in an
+/// `allOf` hierarchy.
+enum ClassRole<'r> {
+    Standalone,
+    /// The sealed base, constructing the subclass (given by discriminator
+    /// value and class name) the `key` property names.
+    Base {
+        key: &'r str,
+        subclasses: Vec<(&'r str, String)>,
+    },
+    /// A subclass of `base`, which declares the `inherited` properties and
+    /// the `key` this class answers with `value`.
+    Subclass {
+        base: &'r str,
+        inherited: &'r ProductProperties<'r>,
+        key: &'r str,
+        value: &'r str,
+    },
+}
+
+/// The map field of a product type keeping the properties its schema allows
+/// besides the fixed ones.
+struct AdditionalProperties {
+    field: &'static str,
+    value_type: String,
+    is_primitive: bool,
+    codec: Option<FormatCodec>,
+}
+
+/// Dart expression decoding the collection element `e` into `inner_type`.
+fn element_decode(inner_type: &str, is_primitive: bool, codec: Option<FormatCodec>) -> String {
+    match (codec, is_primitive) {
+        (Some(codec), _) => codec.decode("e", false),
+        (None, true) => format!("e as {}", inner_type),
+        (None, false) => format!("{}.fromJson(e)", inner_type),
+    }
+}
+
+/// Dart expression encoding the collection element `e`.
+fn element_encode(is_primitive: bool, codec: Option<FormatCodec>) -> String {
+    match (codec, is_primitive) {
+        (Some(codec), _) => codec.encode("e", false),
+        (None, true) => "e".to_string(),
+        (None, false) => "e.toJson()".to_string(),
+    }
+}
+
 /// Result of [`SchemeAdder::parse_named_iast`].
 ///
 /// `content` is the generated Dart source for this node, `files` are the
@@ -1464,16 +2404,25 @@ struct DiscriminatedVariant<'a> {
 struct SumVariantClass {
     class_name: String,
     special_case: Option<GenerationSpecialCase>,
+    /// Codec of the wrapped value (or of its elements, for lists) if it is a
+    /// formatted primitive.
+    codec: Option<FormatCodec>,
+    /// Statements validating the wrapped value, see
+    /// [`SchemeAdder::validation_checks`].
+    validation_checks: String,
     /// Short, lowerCamelCase arm name (the `$ref` schema name when available,
     /// else a positional `variant{N}`) used for the redirecting constructor
     /// and `match` callback.
     short_name: String,
+    /// The Dart type of the JSON values of this variant, see
+    /// [`SchemeAdder::json_type`].
+    json_type: Option<&'static str>,
 }
 
 /// One arm of a response union built by
 /// [`SchemeAdder::generate_response_union`]. Each arm corresponds to a
 /// single HTTP status `code` and wraps a value of `value_type`, decoded
-/// according to `is_primitive` / `list_inner_type` (mirroring the
+/// according to `is_primitive` / `collection` (mirroring the
 /// classification carried by the endpoint generator's `ResponseClass`).
 /// `import_path` is the sibling per-code schema file the union imports and
 /// re-exports.
@@ -1481,7 +2430,12 @@ pub(super) struct ResponseUnionVariant {
     pub code: String,
     pub value_type: String,
     pub is_primitive: bool,
-    pub list_inner_type: Option<String>,
+    /// The collection the value is and its element type, for lists and
+    /// maps.
+    pub collection: Option<(Collection, String)>,
+    /// Codec of the value (or of its elements, for collections) if it is a
+    /// formatted primitive.
+    pub codec: Option<FormatCodec>,
     pub import_path: String,
 }
 
@@ -1498,4 +2452,69 @@ pub(super) enum GenerationSpecialCaseType {
     Link(String),
     //inner type, is primitive
     List(String, bool),
+    //value type, is primitive
+    Map(String, bool),
+}
+
+impl GenerationSpecialCaseType {
+    /// The collection, its element type and whether the elements are
+    /// primitive, for `List`s and `Map`s.
+    pub(super) fn collection(&self) -> Option<(Collection, &String, bool)> {
+        match self {
+            Self::List(inner_type, is_primitive) => {
+                Some((Collection::List, inner_type, *is_primitive))
+            }
+            Self::Map(inner_type, is_primitive) => {
+                Some((Collection::Map, inner_type, *is_primitive))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A Dart collection whose elements are (de)serialized one by one: `List`
+/// for arrays, `Map<String, _>` for objects with `additionalProperties`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Collection {
+    List,
+    Map,
+}
+
+impl Collection {
+    pub(super) fn of(primitive: &Primitive) -> Option<Self> {
+        match primitive {
+            Primitive::List(_) => Some(Self::List),
+            Primitive::Map(_) => Some(Self::Map),
+            _ => None,
+        }
+    }
+
+    /// The Dart type of this collection of `element`s.
+    pub(super) fn dart_type(self, element: &str) -> String {
+        match self {
+            Self::List => format!("List<{}>", element),
+            Self::Map => format!("Map<String, {}>", element),
+        }
+    }
+
+    /// Dart expression decoding the JSON value `json` into this collection,
+    /// each element `e` with the expression `element`.
+    pub(super) fn decode(self, json: &str, element: &str) -> String {
+        match self {
+            Self::List => format!("({} as List).map((e) => {}).toList()", json, element),
+            Self::Map => format!(
+                "({} as Map).map((k, e) => MapEntry(k as String, {}))",
+                json, element
+            ),
+        }
+    }
+
+    /// Dart expression encoding the collection `value` (which may end in a
+    /// null-aware `?`), each element `e` with the expression `element`.
+    pub(super) fn encode(self, value: &str, element: &str) -> String {
+        match self {
+            Self::List => format!("{}.map((e) => {}).toList()", value, element),
+            Self::Map => format!("{}.map((k, e) => MapEntry(k, {}))", value, element),
+        }
+    }
 }
@@ -1,5 +1,8 @@
 // in dart we cannot enforce our classes to have a fromJson method
 import 'dart:convert';
+import 'dart:typed_data';
+
+export 'dart:typed_data' show Uint8List;
 
 abstract interface class BEAMSerde {
   /// either a Map<String, dynamic> or a List<dynamic> or a String or a int or a double or a bool or null
@@ -69,6 +72,180 @@ class BEAMUnionParseMultiError extends Error {
   }
 }
 
+/// (De)serialization of string formats that map onto richer Dart types.
+abstract final class BEAMFormats {
+  static T _expectString<T>(dynamic json, String format, T Function(String) parse) {
+    if (json is! String) {
+      throw BEAMWrongTypeError('$json is not a $format string');
+    }
+    try {
+      return parse(json);
+    } on FormatException catch (e) {
+      throw BEAMWrongTypeError('$json is not a valid $format: ${e.message}');
+    }
+  }
+
+  /// `format: date-time`, an RFC 3339 timestamp. Sent in UTC, as local
+  /// `DateTime`s have no offset in their ISO-8601 form.
+  static DateTime dateTimeFromJson(dynamic json) =>
+      _expectString(json, 'date-time', DateTime.parse);
+  static String dateTimeToJson(DateTime value) =>
+      value.toUtc().toIso8601String();
+
+  /// `format: date`, an ISO-8601 calendar date (`yyyy-mm-dd`).
+  static DateTime dateFromJson(dynamic json) =>
+      _expectString(json, 'date', DateTime.parse);
+  static String dateToJson(DateTime value) =>
+      value.toIso8601String().substring(0, 10);
+
+  /// `format: uri`.
+  static Uri uriFromJson(dynamic json) => _expectString(json, 'uri', Uri.parse);
+  static String uriToJson(Uri value) => value.toString();
+
+  /// `format: byte`, base64 encoded bytes.
+  static Uint8List bytesFromJson(dynamic json) =>
+      _expectString(json, 'byte', base64Decode);
+  static String bytesToJson(Uint8List value) => base64Encode(value);
+}
+
+/// A value breaking a validation keyword of its schema.
+class BEAMViolation {
+  /// JSON path of the value, e.g. `$.items[2].name`.
+  final String path;
+
+  /// The violated schema keyword, e.g. `maxLength`.
+  final String keyword;
+  final String message;
+
+  const BEAMViolation({
+    required this.path,
+    required this.keyword,
+    required this.message,
+  });
+
+  @override
+  String toString() => '$path: $message ($keyword)';
+}
+
+/// Implemented by generated classes when validators are generated.
+abstract interface class BEAMValidatable {
+  /// The violations of this value and of the values it contains, their
+  /// paths starting at [path].
+  List<BEAMViolation> validate([String path = r'$']);
+}
+
+/// Checks used by the generated `validate()` methods. Each appends the
+/// violations of a (possibly null) value to `out`.
+abstract final class BEAMValidate {
+  static void number(
+    List<BEAMViolation> out,
+    String path,
+    num? value, {
+    num? minimum,
+    num? exclusiveMinimum,
+    num? maximum,
+    num? exclusiveMaximum,
+    num? multipleOf,
+  }) {
+    if (value == null) return;
+    void fail(String keyword, String message) =>
+        out.add(BEAMViolation(path: path, keyword: keyword, message: message));
+    if (minimum != null && value < minimum) {
+      fail('minimum', '$value is less than $minimum');
+    }
+    if (exclusiveMinimum != null && value <= exclusiveMinimum) {
+      fail('exclusiveMinimum', '$value is not greater than $exclusiveMinimum');
+    }
+    if (maximum != null && value > maximum) {
+      fail('maximum', '$value is greater than $maximum');
+    }
+    if (exclusiveMaximum != null && value >= exclusiveMaximum) {
+      fail('exclusiveMaximum', '$value is not less than $exclusiveMaximum');
+    }
+    // tolerate the rounding errors of floating point divisions
+    final quotient = multipleOf == null ? 0 : value / multipleOf;
+    if (multipleOf != null && (quotient - quotient.round()).abs() > 1e-9) {
+      fail('multipleOf', '$value is not a multiple of $multipleOf');
+    }
+  }
+
+  static void string(
+    List<BEAMViolation> out,
+    String path,
+    String? value, {
+    int? minLength,
+    int? maxLength,
+    String? pattern,
+  }) {
+    if (value == null) return;
+    void fail(String keyword, String message) =>
+        out.add(BEAMViolation(path: path, keyword: keyword, message: message));
+    // lengths count unicode code points, not UTF-16 code units
+    final length = value.runes.length;
+    if (minLength != null && length < minLength) {
+      fail('minLength', 'is shorter than $minLength characters');
+    }
+    if (maxLength != null && length > maxLength) {
+      fail('maxLength', 'is longer than $maxLength characters');
+    }
+    if (pattern != null && !RegExp(pattern, unicode: true).hasMatch(value)) {
+      fail('pattern', 'does not match $pattern');
+    }
+  }
+
+  static void list(
+    List<BEAMViolation> out,
+    String path,
+    List? value, {
+    int? minItems,
+    int? maxItems,
+    bool uniqueItems = false,
+  }) {
+    if (value == null) return;
+    void fail(String keyword, String message) =>
+        out.add(BEAMViolation(path: path, keyword: keyword, message: message));
+    if (minItems != null && value.length < minItems) {
+      fail('minItems', 'has fewer than $minItems items');
+    }
+    if (maxItems != null && value.length > maxItems) {
+      fail('maxItems', 'has more than $maxItems items');
+    }
+    if (uniqueItems) {
+      final seen = <String>{};
+      for (final item in value) {
+        final json = jsonEncode(
+          item,
+          toEncodable: (o) => o is BEAMSerde ? o.toJson() : o.toString(),
+        );
+        if (!seen.add(json)) {
+          fail('uniqueItems', 'contains $json more than once');
+          break;
+        }
+      }
+    }
+  }
+
+  /// The items of a (possibly null) list, with their indices.
+  static Iterable<(int, T)> items<T>(List<T>? value) =>
+      value?.indexed ?? const [];
+
+  /// The violations of a value that may contain [BEAMValidatable]s, e.g. a
+  /// generated class or a list of them.
+  static void nested(List<BEAMViolation> out, String path, dynamic value) {
+    if (value is BEAMValidatable) {
+      out.addAll(value.validate(path));
+    } else if (value is List) {
+      for (final (i, item) in value.indexed) {
+        nested(out, '$path[$i]', item);
+      }
+    } else if (value is Map) {
+      for (final entry in value.entries) {
+        nested(out, '$path["${entry.key}"]', entry.value);
+      }
+    }
+  }
+}
+
 class UnknownBEAMObject implements BEAMSerde {
   const UnknownBEAMObject({this.rawValue});
 
@@ -1,15 +1,48 @@
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct GenerationArgs {
     pub ignore_deprecated_fields: bool,
+    /// Give generated classes a `validate()` method checking the validation
+    /// keywords (`minimum`, `maxLength`, `pattern`, ...) of their schema.
+    pub generate_validators: bool,
+    pub read_write_models: ReadWriteModels,
+    /// Fail instead of generating what the spec describes less precisely,
+    /// see [`IntermediateArgs::strict`](crate::parse::intermediate::IntermediateArgs::strict).
+    pub strict: bool,
+}
+
+/// How schemes with `readOnly` / `writeOnly` properties are generated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReadWriteModels {
+    /// One model per scheme, in which `readOnly` and `writeOnly` are only
+    /// documented: such properties are as required as the schema says.
+    #[default]
+    Ignore,
+    /// One model per scheme, in which `readOnly` and `writeOnly` properties
+    /// are optional, as they are missing from requests or responses.
+    OptionalFields,
+    /// Additionally a `...Request` model without the `readOnly` properties
+    /// and a `...Response` model without the `writeOnly` ones, which the
+    /// endpoints send and return.
+    Separate,
 }
 
 pub trait Generator {
     // generate a list of files (name, content)
+    /// `raw` is the JSON document `spec` was read from, holding what `oas3`
+    /// doesn't model.
     fn generate(
         &self,
         spec: &oas3::Spec,
+        raw: &serde_json::Value,
         args: GenerationArgs,
-    ) -> impl std::future::Future<Output = Result<Vec<File>, String>> + Send;
+    ) -> impl std::future::Future<Output = Result<Generated, String>> + Send;
+}
+
+/// What a [`Generator`] produced: the files to write, and the problems
+/// found in the spec while generating them.
+pub struct Generated {
+    pub files: Vec<File>,
+    pub diagnostics: Vec<crate::parse::intermediate::Diagnostic>,
 }
 
 pub struct File {
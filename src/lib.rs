@@ -11,10 +11,10 @@
 pub mod generate;
 pub mod parse;
 
-use generate::{DartGenerator, File, GenerationArgs, Generator};
+use generate::{DartGenerator, File, Generated, GenerationArgs, Generator};
 
-/// Run the full pipeline (`spec JSON` -> `IntermediateFormat` -> generated
-/// Dart files) and return the list of files that would be written to disk.
+/// Run the full pipeline (`spec JSON/YAML` -> `IntermediateFormat` ->
+/// generated Dart files) and return the list of files that would be written to disk.
 ///
 /// Intended for use from tests and tooling; the CLI in `main.rs` performs
 /// the same steps inline so that it can also stream progress logs.
@@ -27,13 +27,33 @@ pub async fn generate_dart_files(
     spec_json: &str,
     ignore_deprecated_fields: bool,
 ) -> Result<Vec<File>, String> {
-    let spec = oas3::from_json(spec_json).map_err(|e| format!("parse spec: {:?}", e))?;
-    DartGenerator
-        .generate(
-            &spec,
-            GenerationArgs {
-                ignore_deprecated_fields,
-            },
-        )
+    generate_dart_files_with_args(
+        spec_json,
+        GenerationArgs {
+            ignore_deprecated_fields,
+            ..Default::default()
+        },
+    )
+    .await
+}
+
+/// Like [`generate_dart_files`], but with full control over the
+/// [`GenerationArgs`].
+///
+/// Whether `spec_json` is JSON or YAML is sniffed from its content, see
+/// [`parse::SpecFormat::sniff`].
+pub async fn generate_dart_files_with_args(
+    spec_json: &str,
+    args: GenerationArgs,
+) -> Result<Vec<File>, String> {
+    generate_dart(spec_json, args)
         .await
+        .map(|generated| generated.files)
+}
+
+/// Like [`generate_dart_files_with_args`], but also returns the
+/// diagnostics collected while parsing the spec.
+pub async fn generate_dart(spec_json: &str, args: GenerationArgs) -> Result<Generated, String> {
+    let (spec, raw) = parse::read_spec(spec_json)?;
+    DartGenerator.generate(&spec, &raw, args).await
 }
@@ -2,17 +2,26 @@
 // can also be exercised from integration tests; pull what the CLI
 // needs back into scope from the library crate.
 use openapi_parser::generate::*;
+use openapi_parser::parse::bundle::bundle;
+use openapi_parser::parse::intermediate::{Diagnostic, Severity};
+use openapi_parser::parse::read_spec_value;
+use openapi_parser::parse::source::{SpecLoader, SpecSource};
 
 enum DestinationLanguage {
     Dart,
 }
 
 impl Generator for DestinationLanguage {
-    async fn generate(&self, spec: &oas3::Spec, args: GenerationArgs) -> Result<Vec<File>, String> {
+    async fn generate(
+        &self,
+        spec: &oas3::Spec,
+        raw: &serde_json::Value,
+        args: GenerationArgs,
+    ) -> Result<Generated, String> {
         let generator = match self {
             DestinationLanguage::Dart => DartGenerator,
         };
-        generator.generate(spec, args).await
+        generator.generate(spec, raw, args).await
     }
 }
 
@@ -25,37 +34,39 @@ impl std::fmt::Display for DestinationLanguage {
     }
 }
 
-async fn fetch_spec_json(url: &str) -> Result<String, reqwest::Error> {
-    let fake = false;
-    if fake {
-        return Ok(include_str!("../test_scheme.json").to_string());
+/// Prints `diagnostics` grouped by severity and code, most severe first.
+fn print_diagnostics(diagnostics: &[Diagnostic]) {
+    if diagnostics.is_empty() {
+        return;
     }
-    let username = std::env::var("SWAGGER_BASIC_USER");
-    let password = std::env::var("SWAGGER_BASIC_PASS");
-
-    let client = reqwest::Client::builder()
-        .user_agent("curl/8.7.1")
-        .build()
-        .unwrap();
-
-    let response = match username {
-        Ok(username) => client.get(url).basic_auth(username, password.ok()).send(),
-        Err(_) => client.get(url).send(),
+    let mut groups = std::collections::BTreeMap::<_, Vec<&Diagnostic>>::new();
+    for diagnostic in diagnostics {
+        groups
+            .entry((std::cmp::Reverse(diagnostic.severity), diagnostic.code))
+            .or_default()
+            .push(diagnostic);
+    }
+    println!("{} diagnostics:", diagnostics.len());
+    for ((std::cmp::Reverse(severity), code), group) in groups {
+        println!("{}[{}] ({}):", severity, code, group.len());
+        for diagnostic in group {
+            println!("  {}: {}", diagnostic.pointer, diagnostic.message);
+        }
     }
-    .await?;
-    let body = response.text().await?;
-    Ok(body)
 }
 
 #[tokio::main]
 async fn main() {
     // parse args from the following format
-    // --spec-url/-u <spec-url> --out-dir/-o <out-dir> --destination-language/-d <destination-language>
+    // --spec-url/-u <spec-url (http(s)://, file:// or a path, - for stdin)> --out-dir/-o <out-dir> --destination-language/-d <destination-language>
     let mut args = std::env::args().skip(1);
     let mut spec_url = None;
     let mut out_dir = None;
     let mut destination_language = None;
     let mut skip_deprecated_removal = false;
+    let mut generate_validators = false;
+    let mut read_write_models = ReadWriteModels::Ignore;
+    let mut strict = false;
 
     while let Some(arg) = args.next() {
         match arg.as_str() {
@@ -74,6 +85,18 @@ async fn main() {
             "--skip-deprecated-removal" => {
                 skip_deprecated_removal = true;
             }
+            "--generate-validators" => {
+                generate_validators = true;
+            }
+            "--optional-read-write-fields" => {
+                read_write_models = ReadWriteModels::OptionalFields;
+            }
+            "--separate-read-write-models" => {
+                read_write_models = ReadWriteModels::Separate;
+            }
+            "--strict" => {
+                strict = true;
+            }
             _ => panic!("Unknown argument: {}", arg),
         }
     }
@@ -84,38 +107,71 @@ async fn main() {
 
     let destination_language = destination_language.unwrap_or(DestinationLanguage::Dart);
 
-    println!("getting spec");
-    let json = match fetch_spec_json(&spec_url).await {
-        Ok(json) => json,
+    let source = match SpecSource::parse(&spec_url) {
+        Ok(source) => source,
+        Err(e) => {
+            println!("spec source error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let loader = SpecLoader::new(
+        std::env::var("SWAGGER_BASIC_USER")
+            .ok()
+            .map(|username| (username, std::env::var("SWAGGER_BASIC_PASS").ok())),
+    );
+
+    println!("getting spec from {}", source);
+    let bundle = match bundle(&loader, &source).await {
+        Ok(bundle) => bundle,
         Err(e) => {
-            println!("fetching spec error: {:?}", e);
-            return;
+            println!("fetching spec error: {}", e);
+            std::process::exit(1);
         }
     };
+    for collision in &bundle.collisions {
+        println!("name collision: {}", collision);
+    }
     println!("parsing spec");
-    let spec = match oas3::from_json(json) {
-        Ok(spec) => spec,
+    let (spec, raw) = match read_spec_value(bundle.document) {
+        Ok(read) => read,
         Err(e) => {
-            println!("parsing spec error: {:?}", e);
-            return;
+            println!("parsing spec error: {}", e);
+            std::process::exit(1);
         }
     };
     println!("generating {:} code", destination_language);
-    let files = match destination_language
+    let generated = match destination_language
         .generate(
             &spec,
+            &raw,
             GenerationArgs {
                 ignore_deprecated_fields: !skip_deprecated_removal,
+                generate_validators,
+                read_write_models,
+                strict,
             },
         )
         .await
     {
-        Ok(files) => files,
+        Ok(generated) => generated,
         Err(e) => {
-            println!("generating code error: {:?}", e);
-            return;
+            println!("generating code error: {}", e);
+            // e.g. a spec rejected by `--strict`, which CI should notice
+            std::process::exit(1);
         }
     };
+    print_diagnostics(&generated.diagnostics);
+    // problems found past the parsing, which `--strict` rejects as well
+    if strict
+        && generated
+            .diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity >= Severity::Warning)
+    {
+        println!("strict mode, no files written");
+        std::process::exit(1);
+    }
+    let files = generated.files;
     if files.is_empty() {
         println!("no files to write");
         return;
@@ -133,7 +189,7 @@ async fn main() {
         std::fs::write(path, file.content).unwrap();
         print!(".");
     }
-    println!("");
+    println!();
     println!("done");
 
     // exit with 0
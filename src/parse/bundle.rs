@@ -0,0 +1,451 @@
+//! Bundling of specs split across several documents.
+//!
+//! The documents a spec references through `$ref`s like
+//! `./schemas/user.yaml#/User` are loaded, and their targets are hoisted
+//! into the components of the root document, so that the rest of the
+//! pipeline only ever sees local `#/components/...` references.
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::{Map, Value};
+
+use super::source::{SpecLoader, SpecSource};
+
+/// A spec with every external reference resolved into its root document.
+#[derive(Debug, Clone)]
+pub struct Bundle {
+    pub document: Value,
+    /// The hoisted components whose name was taken already.
+    pub collisions: Vec<Collision>,
+}
+
+/// A component hoisted from another document under a name that was taken
+/// already, by a component of the root or one hoisted before.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision {
+    /// The components section, e.g. `schemas`.
+    pub section: String,
+    pub name: String,
+    /// The reference the component was hoisted from.
+    pub origin: String,
+    /// Where the component that has the name was taken from.
+    pub taken_by: String,
+    /// The name the component got instead, `None` if it is identical to
+    /// the one with the name and was merged into it.
+    pub renamed_to: Option<String>,
+}
+
+impl std::fmt::Display for Collision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.renamed_to {
+            Some(renamed_to) => write!(
+                f,
+                "{} `{}` of {} differs from the one of {}, renamed to `{}`",
+                self.section, self.name, self.origin, self.taken_by, renamed_to
+            ),
+            None => write!(
+                f,
+                "{} `{}` of {} is identical to the one of {}, merged",
+                self.section, self.name, self.origin, self.taken_by
+            ),
+        }
+    }
+}
+
+/// Loads the spec at `source` along with every document it references,
+/// transitively, and bundles them into one document.
+pub async fn bundle(loader: &SpecLoader, source: &SpecSource) -> Result<Bundle, String> {
+    let source = match source {
+        SpecSource::File(path) => {
+            SpecSource::File(std::fs::canonicalize(path).unwrap_or_else(|_| path.clone()))
+        }
+        source => source.clone(),
+    };
+    let root = loader.load(&source).await?.document()?;
+    let root_key = source.to_string();
+    let root_source = source.clone();
+
+    let mut documents = HashMap::new();
+    let mut seen = HashSet::from([root_key.clone()]);
+    let mut pending = vec![(source, root)];
+    while let Some((source, document)) = pending.pop() {
+        let mut references = Vec::new();
+        collect_references(&document, &mut references);
+        for reference in references {
+            let (location, _) = split_reference(reference);
+            if location.is_empty() {
+                continue;
+            }
+            let referenced = source.join(location)?;
+            if seen.insert(referenced.to_string()) {
+                let loaded = loader
+                    .scoped_to(&root_source, &referenced)
+                    .load(&referenced)
+                    .await
+                    .map_err(|e| format!("load {} (referenced by {}): {}", reference, source, e))?;
+                pending.push((referenced, loaded.document()?));
+            }
+        }
+        documents.insert(source.to_string(), (source, document));
+    }
+
+    // even without other documents, references of the root to itself by
+    // its path become local ones
+    let (root_source, mut root) = documents.remove(&root_key).expect("the root was loaded");
+    let mut bundler = Bundler::new(&root, root_source, documents);
+    let root_base = bundler.root_key.clone();
+    bundler.rewrite(&mut root, &root_base, &mut Vec::new())?;
+    for (section, name, component) in bundler.hoisted_components {
+        let mut parent = &mut root;
+        for key in section.iter() {
+            parent = parent
+                .as_object_mut()
+                .ok_or_else(|| format!("`{}` of the root is not an object", key))?
+                .entry(*key)
+                .or_insert_with(|| Value::Object(Map::new()));
+        }
+        if let Some(parent) = parent.as_object_mut() {
+            parent.insert(name, component);
+        }
+    }
+    Ok(Bundle {
+        document: root,
+        collisions: bundler.collisions,
+    })
+}
+
+/// Where the target of a reference goes in the bundle.
+#[derive(Debug, Clone, Copy)]
+enum Placement {
+    /// Into the components at this path of the root.
+    Section(&'static [&'static str]),
+    /// In place of the reference, for path items, which cannot be
+    /// referenced from the components of OpenAPI 3.0.
+    Inline,
+}
+
+struct Bundler {
+    root_key: String,
+    root_source: SpecSource,
+    swagger2: bool,
+    /// The referenced documents, by the source they were loaded from.
+    documents: HashMap<String, (SpecSource, Value)>,
+    /// The local references of the targets hoisted already, by their
+    /// document and JSON pointer.
+    hoisted: HashMap<(String, String), String>,
+    /// The taken names of each section, with the origin and the original
+    /// JSON of the component.
+    taken: HashMap<&'static [&'static str], HashMap<String, (String, Value)>>,
+    hoisted_components: Vec<(&'static [&'static str], String, Value)>,
+    collisions: Vec<Collision>,
+}
+
+impl Bundler {
+    fn new(
+        root: &Value,
+        root_source: SpecSource,
+        documents: HashMap<String, (SpecSource, Value)>,
+    ) -> Self {
+        let root_key = root_source.to_string();
+        let swagger2 = super::swagger2::is_swagger2(root);
+        let mut taken: HashMap<&'static [&'static str], HashMap<String, (String, Value)>> =
+            HashMap::new();
+        for section in SECTIONS.iter().chain(SWAGGER2_SECTIONS.iter()) {
+            let mut components = Some(root);
+            for key in section.iter() {
+                components = components.and_then(|components| components.get(key));
+            }
+            let components = components.and_then(Value::as_object).into_iter().flatten();
+            taken.insert(
+                section,
+                components
+                    .map(|(name, component)| {
+                        let origin = format!("{}#/{}/{}", root_key, section.join("/"), name);
+                        (name.clone(), (origin, component.clone()))
+                    })
+                    .collect(),
+            );
+        }
+        Self {
+            root_key,
+            root_source,
+            swagger2,
+            documents,
+            hoisted: HashMap::new(),
+            taken,
+            hoisted_components: Vec::new(),
+            collisions: Vec::new(),
+        }
+    }
+
+    /// Rewrites the references within `value`, which is at `path` of the
+    /// bundle and was read from the document `base`.
+    fn rewrite(
+        &mut self,
+        value: &mut Value,
+        base: &str,
+        path: &mut Vec<String>,
+    ) -> Result<(), String> {
+        match value {
+            Value::Object(object) => {
+                if let Some(Value::String(reference)) = object.get("$ref") {
+                    let reference = reference.clone();
+                    match self.resolve(&reference, base, path)? {
+                        Resolved::Unchanged => {}
+                        Resolved::Reference(local) => {
+                            object.insert("$ref".to_string(), Value::String(local));
+                        }
+                        Resolved::Inline(Value::Object(mut target)) => {
+                            object.remove("$ref");
+                            for (key, mut sibling) in std::mem::take(object) {
+                                path.push(key.clone());
+                                self.rewrite(&mut sibling, base, path)?;
+                                path.pop();
+                                target.insert(key, sibling);
+                            }
+                            *object = target;
+                            return Ok(());
+                        }
+                        Resolved::Inline(target) => {
+                            *value = target;
+                            return Ok(());
+                        }
+                    }
+                }
+                for (key, child) in object.iter_mut() {
+                    if key == "$ref" {
+                        continue;
+                    }
+                    path.push(key.clone());
+                    self.rewrite(child, base, path)?;
+                    path.pop();
+                }
+            }
+            Value::Array(array) => {
+                for (i, child) in array.iter_mut().enumerate() {
+                    path.push(i.to_string());
+                    self.rewrite(child, base, path)?;
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// What `reference`, at `path` of the bundle and read from the document
+    /// `base`, becomes in the bundle.
+    fn resolve(
+        &mut self,
+        reference: &str,
+        base: &str,
+        path: &[String],
+    ) -> Result<Resolved, String> {
+        let (location, pointer) = split_reference(reference);
+        let document = match location {
+            "" => base.to_string(),
+            location => self.source(base)?.join(location)?.to_string(),
+        };
+        if document == self.root_key {
+            return Ok(match location {
+                "" => Resolved::Unchanged,
+                _ => Resolved::Reference(format!("#{}", pointer)),
+            });
+        }
+        let origin = format!("{}#{}", document, pointer);
+        let target = self
+            .documents
+            .get(&document)
+            .and_then(|(_, value)| {
+                super::intermediate::resolve_pointer(value, &format!("#{}", pointer))
+            })
+            .ok_or_else(|| format!("unresolvable reference {} (at {})", reference, origin))?
+            .clone();
+
+        let section = match placement(path, self.swagger2) {
+            Placement::Section(section) => section,
+            Placement::Inline => {
+                let mut target = target;
+                self.rewrite(&mut target, &document, &mut path.to_vec())?;
+                return Ok(Resolved::Inline(target));
+            }
+        };
+        let key = (document.clone(), pointer.to_string());
+        if let Some(local) = self.hoisted.get(&key) {
+            return Ok(Resolved::Reference(local.clone()));
+        }
+
+        let wanted = component_name(&document, pointer);
+        let taken = self.taken.entry(section).or_default();
+        let mut name = wanted.clone();
+        for i in 2.. {
+            match taken.get(&name) {
+                None => break,
+                Some((taken_by, existing)) if *existing == target => {
+                    let local = format!("#/{}/{}", section.join("/"), name);
+                    self.collisions.push(Collision {
+                        section: section.last().unwrap_or(&"").to_string(),
+                        name,
+                        origin,
+                        taken_by: taken_by.clone(),
+                        renamed_to: None,
+                    });
+                    self.hoisted.insert(key, local.clone());
+                    return Ok(Resolved::Reference(local));
+                }
+                Some(_) => name = format!("{}{}", wanted, i),
+            }
+        }
+        if name != wanted {
+            self.collisions.push(Collision {
+                section: section.last().unwrap_or(&"").to_string(),
+                name: wanted.clone(),
+                origin: origin.clone(),
+                taken_by: taken[&wanted].0.clone(),
+                renamed_to: Some(name.clone()),
+            });
+        }
+        taken.insert(name.clone(), (origin, target.clone()));
+        let local = format!("#/{}/{}", section.join("/"), name);
+        self.hoisted.insert(key, local.clone());
+
+        let mut component = target;
+        let mut component_path = section
+            .iter()
+            .map(|key| key.to_string())
+            .collect::<Vec<_>>();
+        component_path.push(name.clone());
+        self.rewrite(&mut component, &document, &mut component_path)?;
+        self.hoisted_components.push((section, name, component));
+        Ok(Resolved::Reference(local))
+    }
+
+    fn source(&self, document: &str) -> Result<SpecSource, String> {
+        if document == self.root_key {
+            // e.g. stdin, which its key doesn't parse back into
+            return Ok(self.root_source.clone());
+        }
+        match self.documents.get(document) {
+            Some((source, _)) => Ok(source.clone()),
+            None => SpecSource::parse(document),
+        }
+    }
+}
+
+enum Resolved {
+    /// A local reference of the root, which stays as it is.
+    Unchanged,
+    Reference(String),
+    Inline(Value),
+}
+
+const SECTIONS: [&[&str]; 8] = [
+    &["components", "schemas"],
+    &["components", "parameters"],
+    &["components", "responses"],
+    &["components", "requestBodies"],
+    &["components", "headers"],
+    &["components", "examples"],
+    &["components", "links"],
+    &["components", "callbacks"],
+];
+
+const SWAGGER2_SECTIONS: [&[&str]; 3] = [&["definitions"], &["parameters"], &["responses"]];
+
+/// Where the target of a reference at `path` of the bundle goes, judging
+/// by what is found at such a path.
+fn placement(path: &[String], swagger2: bool) -> Placement {
+    let path: Vec<&str> = path.iter().map(String::as_str).collect();
+    let section = |name: &str| {
+        let sections = match swagger2 {
+            true => &SWAGGER2_SECTIONS[..],
+            false => &SECTIONS[..],
+        };
+        let name = match (swagger2, name) {
+            (true, "schemas") => "definitions",
+            (_, name) => name,
+        };
+        sections
+            .iter()
+            .find(|section| section.last() == Some(&name))
+            .map_or(Placement::Inline, |section| Placement::Section(section))
+    };
+    match path[..] {
+        ["paths" | "webhooks", _] => return Placement::Inline,
+        ["components", "pathItems", _] => return Placement::Inline,
+        ["components", name, _] | [name @ ("definitions" | "parameters" | "responses"), _] => {
+            return match name {
+                "definitions" => section("schemas"),
+                name => section(name),
+            };
+        }
+        _ => {}
+    }
+    if path
+        .iter()
+        .any(|key| matches!(*key, "schema" | "schemas" | "definitions" | "properties"))
+    {
+        return section("schemas");
+    }
+    match path[..] {
+        [.., "callbacks", _, _] => Placement::Inline,
+        [.., "parameters", _] => section("parameters"),
+        [.., "requestBody"] => section("requestBodies"),
+        [.., "responses", _] => section("responses"),
+        [.., "headers", _] => section("headers"),
+        [.., "examples", _] => section("examples"),
+        [.., "links", _] => section("links"),
+        [.., "callbacks", _] => section("callbacks"),
+        _ => section("schemas"),
+    }
+}
+
+/// The name of the component at `pointer` of `document`: the last token of
+/// the pointer, or the name of the document for the whole of it, with runs
+/// of characters other than letters, digits and `_` replaced by `_`.
+fn component_name(document: &str, pointer: &str) -> String {
+    let token = super::intermediate::pointer_tokens(&format!("#{}", pointer))
+        .and_then(|tokens| tokens.last().cloned())
+        .filter(|token| !token.is_empty());
+    let name = match &token {
+        Some(token) => token,
+        None => {
+            let file = document.rsplit(['/', '\\']).next().unwrap_or(document);
+            file.split('.').next().unwrap_or(file)
+        }
+    };
+    let name = name
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    match name.is_empty() {
+        true => "Component".to_string(),
+        false => name,
+    }
+}
+
+/// `reference` split into the location of its document, empty for the
+/// document it is in, and the JSON pointer within it.
+fn split_reference(reference: &str) -> (&str, &str) {
+    reference.split_once('#').unwrap_or((reference, ""))
+}
+
+fn collect_references<'v>(value: &'v Value, references: &mut Vec<&'v str>) {
+    match value {
+        Value::Object(object) => {
+            for (key, child) in object {
+                match (key.as_str(), child) {
+                    ("$ref", Value::String(reference)) => references.push(reference),
+                    _ => collect_references(child, references),
+                }
+            }
+        }
+        Value::Array(array) => {
+            for child in array {
+                collect_references(child, references);
+            }
+        }
+        _ => {}
+    }
+}
@@ -1,15 +1,26 @@
+mod diagnostics;
+mod pointer;
 pub mod types;
+use std::borrow::Cow;
 use std::collections::{BTreeMap, HashMap, HashSet};
 
 use oas3::spec::Response as Responses;
 use oas3::spec::*;
+use oas3::spec::{SecurityScheme as SpecSecurityScheme, Server as SpecServer};
 pub use types::*;
+// named explicitly as `oas3` has types of the same names
+pub use diagnostics::{to_pointer, At, Diagnostic, Diagnostics, Severity};
+pub use pointer::{pointer_tokens, resolve_pointer};
+pub use types::{SecurityScheme, Server, ServerVariable};
 #[macro_use]
 mod macros;
 
 #[derive(Debug, Clone)]
 pub struct IntermediateArgs {
     pub ignore_deprecated_fields: bool,
+    /// Fail with [`Error::Unsupported`] instead of generating what the spec
+    /// describes less precisely, i.e. on any warning or error diagnostic.
+    pub strict: bool,
 }
 
 /// Context carried through the entire intermediate parse. Holds the user args
@@ -20,14 +31,97 @@ pub struct ParseCtx<'a> {
     pub args: IntermediateArgs,
     pub deprecated_schemes: HashSet<&'a str>,
     pub schemas: &'a BTreeMap<String, ObjectOrReference<ObjectSchema>>,
+    /// Used to resolve `$ref`s to shared parameters, request bodies and
+    /// responses.
+    pub components: &'a Components,
+    /// The JSON document of the spec, for what `oas3` doesn't model.
+    pub raw: &'a serde_json::Value,
+    /// The schemes synthesized for `$ref`s to nested schemas, see
+    /// [`SpecDocument`].
+    pub pointer_schemes: &'a BTreeMap<String, ObjectOrReference<ObjectSchema>>,
+    /// The `#/components/schemas/...` reference each other schema `$ref`
+    /// stands for.
+    pub ref_aliases: &'a BTreeMap<String, String>,
+    /// The problems found so far, and where the parse currently is.
+    pub diagnostics: Diagnostics,
 }
 
 impl<'a> ParseCtx<'a> {
     fn ref_targets_deprecated(&self, ref_path: &str) -> bool {
         // `strip_ref_prefix` is the single shared helper, defined in
         // `types.rs` and re-exported via `pub use types::*;` above.
+        let ref_path = self
+            .ref_aliases
+            .get(ref_path)
+            .map_or(ref_path, String::as_str);
         self.deprecated_schemes.contains(strip_ref_prefix(ref_path))
     }
+
+    /// The `#/components/schemas/...` form of the schema reference
+    /// `ref_path`, which is what the IR refers to schemes by.
+    fn local_ref(&self, ref_path: &'a str) -> &'a str {
+        self.ref_aliases
+            .get(ref_path)
+            .map_or(ref_path, String::as_str)
+    }
+
+    /// The top-level or synthesized scheme `name`.
+    fn scheme(&self, name: &str) -> Option<&'a ObjectOrReference<ObjectSchema>> {
+        self.schemas
+            .get(name)
+            .or_else(|| self.pointer_schemes.get(name))
+    }
+
+    /// The `security` of the operation `method` of `path`, falling back to
+    /// the document's.
+    pub fn operation_security(&self, path: &str, method: &Method) -> Vec<SecurityRequirement> {
+        self.raw
+            .get("paths")
+            .and_then(|paths| paths.get(path))
+            .and_then(|item| item.get(method_key(method)))
+            .and_then(|operation| operation.get("security"))
+            .or_else(|| self.raw.get("security"))
+            .map(parse_security_requirements)
+            .unwrap_or_default()
+    }
+}
+
+/// The key of the operations of `method` in a path item.
+fn method_key(method: &Method) -> &'static str {
+    match method {
+        Method::Get => "get",
+        Method::Post => "post",
+        Method::Put => "put",
+        Method::Delete => "delete",
+        Method::Patch => "patch",
+        Method::Head => "head",
+        Method::Options => "options",
+        Method::Trace => "trace",
+    }
+}
+
+/// The requirements of a `security` array.
+fn parse_security_requirements(security: &serde_json::Value) -> Vec<SecurityRequirement> {
+    security
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|requirement| requirement.as_object())
+        .map(|requirement| {
+            requirement
+                .iter()
+                .map(|(scheme, scopes)| {
+                    let scopes = scopes
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|scope| scope.as_str().map(str::to_string))
+                        .collect();
+                    (scheme.clone(), scopes)
+                })
+                .collect()
+        })
+        .collect()
 }
 
 /// Uniform deprecation check across the three IAST shapes. Used by the
@@ -44,9 +138,419 @@ fn iast_is_deprecated(iast: &IAST) -> bool {
 pub enum Error {
     NoComponents,
     ParseError(String),
+    /// What the spec describes can't be represented, see
+    /// [`IntermediateArgs::strict`].
+    Unsupported(Vec<Diagnostic>),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NoComponents => write!(f, "the spec has no components"),
+            Error::ParseError(message) => write!(f, "{}", message),
+            Error::Unsupported(diagnostics) => {
+                write!(f, "the spec can't be represented (strict mode):")?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n  {}", diagnostic)?;
+                }
+                Ok(())
+            }
+        }
+    }
 }
 
-pub fn parse(spec: &oas3::Spec, args: IntermediateArgs) -> Result<IntermediateFormat<'_>, Error> {
+/// A spec together with the JSON document it was deserialized from, for
+/// what `oas3` doesn't model: `security` requirements, the path items of
+/// `callbacks` and schemas referenced by arbitrary JSON pointers.
+pub struct SpecDocument<'a> {
+    pub spec: &'a oas3::Spec,
+    pub raw: &'a serde_json::Value,
+    callbacks: Vec<CallbackItem<'a>>,
+    /// The schemas `$ref`s point at within other schemas or elsewhere than
+    /// `components.schemas` (e.g. `#/components/schemas/Order/properties/items`),
+    /// hoisted into schemes of their own, by name.
+    pointer_schemes: BTreeMap<String, ObjectOrReference<ObjectSchema>>,
+    /// The `#/components/schemas/...` reference standing in for each schema
+    /// `$ref` that isn't of that form.
+    ref_aliases: BTreeMap<String, String>,
+    /// The reference tokens each of the `pointer_schemes` was hoisted from.
+    pointer_scheme_origins: BTreeMap<String, Vec<String>>,
+    /// The problems found reading the parts above.
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// A path item of the `callbacks` of an operation.
+struct CallbackItem<'a> {
+    /// The path and method of the operation.
+    path: &'a str,
+    method: Method,
+    /// The name of the callback.
+    name: &'a str,
+    /// The runtime expression of the URL requests are sent to.
+    expression: &'a str,
+    item: PathItem,
+}
+
+impl<'a> SpecDocument<'a> {
+    pub fn new(spec: &'a oas3::Spec, raw: &'a serde_json::Value) -> Self {
+        let diagnostics = Diagnostics::default();
+        let mut callbacks = Vec::new();
+        let paths = raw.get("paths").and_then(|paths| paths.as_object());
+        for (path, item) in paths.into_iter().flatten() {
+            let operations = [
+                (Method::Get, "get"),
+                (Method::Post, "post"),
+                (Method::Put, "put"),
+                (Method::Delete, "delete"),
+                (Method::Patch, "patch"),
+                (Method::Options, "options"),
+                (Method::Head, "head"),
+                (Method::Trace, "trace"),
+            ];
+            for (method, key) in operations {
+                let operation_callbacks = item
+                    .get(key)
+                    .and_then(|operation| operation.get("callbacks"))
+                    .and_then(|callbacks| callbacks.as_object());
+                for (name, callback) in operation_callbacks.into_iter().flatten() {
+                    let _at = diagnostics.at(&["paths", path, key, "callbacks", name]);
+                    let Some(callback) = resolve_callback(raw, callback) else {
+                        diagnostics.report(
+                            Severity::Error,
+                            "unresolvable-callback",
+                            "the callback's reference can't be resolved, it is skipped",
+                        );
+                        continue;
+                    };
+                    for (expression, item) in callback.as_object().into_iter().flatten() {
+                        match serde_json::from_value::<PathItem>(item.clone()) {
+                            Ok(item) => callbacks.push(CallbackItem {
+                                path,
+                                method,
+                                name,
+                                expression,
+                                item,
+                            }),
+                            Err(e) => {
+                                let _at = diagnostics.at(&[expression]);
+                                diagnostics.report(
+                                    Severity::Error,
+                                    "invalid-callback",
+                                    format!("not a path item, it is skipped: {}", e),
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        let PointerSchemes {
+            schemes: pointer_schemes,
+            aliases: ref_aliases,
+            origins: pointer_scheme_origins,
+        } = collect_pointer_schemes(raw, &diagnostics);
+        report_unsupported_keywords(raw, &mut Vec::new(), &diagnostics);
+        Self {
+            spec,
+            raw,
+            callbacks,
+            pointer_schemes,
+            ref_aliases,
+            pointer_scheme_origins,
+            diagnostics: diagnostics.into_vec(),
+        }
+    }
+}
+
+/// The schemes synthesized for schema `$ref`s that aren't plain
+/// `#/components/schemas/{name}` ones, see [`SpecDocument`].
+#[derive(Default)]
+struct PointerSchemes {
+    schemes: BTreeMap<String, ObjectOrReference<ObjectSchema>>,
+    aliases: BTreeMap<String, String>,
+    origins: BTreeMap<String, Vec<String>>,
+}
+
+/// The schemes to synthesize for the schema `$ref`s of `raw` that aren't
+/// plain `#/components/schemas/{name}` ones, and the local reference each of
+/// those stands for.
+fn collect_pointer_schemes(raw: &serde_json::Value, diagnostics: &Diagnostics) -> PointerSchemes {
+    let mut references = Vec::new();
+    collect_schema_refs(raw, &mut Vec::new(), &mut references);
+
+    let PointerSchemes {
+        mut schemes,
+        mut aliases,
+        mut origins,
+    } = PointerSchemes::default();
+    // the same target may be referenced through differently escaped pointers
+    let mut names: HashMap<Vec<String>, String> = HashMap::new();
+    let taken = |name: &str, schemes: &BTreeMap<String, _>| {
+        schemes.contains_key(name)
+            || raw
+                .get("components")
+                .and_then(|components| components.get("schemas"))
+                .and_then(|schemas| schemas.get(name))
+                .is_some()
+    };
+    for (location, reference) in references {
+        if aliases.contains_key(reference) {
+            continue;
+        }
+        let Some(tokens) = pointer_tokens(reference) else {
+            continue;
+        };
+        if let [components, schemas, name] = &tokens[..] {
+            if components == "components" && schemas == "schemas" {
+                let local = format!("#/components/schemas/{}", name);
+                if local != reference {
+                    aliases.insert(reference.to_string(), local);
+                }
+                continue;
+            }
+        }
+        if let Some(name) = names.get(&tokens) {
+            aliases.insert(
+                reference.to_string(),
+                format!("#/components/schemas/{}", name),
+            );
+            continue;
+        }
+        let Some(target) = resolve_pointer(raw, reference) else {
+            diagnostics.report_at(
+                Severity::Warning,
+                "unresolvable-reference",
+                location,
+                format!("{} can't be resolved", reference),
+            );
+            continue;
+        };
+        let schema = match serde_json::from_value(target.clone()) {
+            Ok(schema) => schema,
+            Err(e) => {
+                diagnostics.report_at(
+                    Severity::Warning,
+                    "invalid-referenced-schema",
+                    location,
+                    format!("{} is not a schema: {}", reference, e),
+                );
+                continue;
+            }
+        };
+        let wanted = pointer_scheme_name(&tokens);
+        let mut name = wanted.clone();
+        for i in 2.. {
+            if !taken(&name, &schemes) {
+                break;
+            }
+            name = format!("{}{}", wanted, i);
+        }
+        aliases.insert(
+            reference.to_string(),
+            format!("#/components/schemas/{}", name),
+        );
+        names.insert(tokens.clone(), name.clone());
+        origins.insert(name.clone(), tokens);
+        schemes.insert(name, schema);
+    }
+    PointerSchemes {
+        schemes,
+        aliases,
+        origins,
+    }
+}
+
+/// Collects the `$ref`s of `value`, at `path` of the document, that are
+/// in place of a schema, along with the references of discriminator
+/// mappings, each with the JSON pointer of where it is.
+fn collect_schema_refs<'v>(
+    value: &'v serde_json::Value,
+    path: &mut Vec<Cow<'v, str>>,
+    references: &mut Vec<(String, &'v str)>,
+) {
+    match value {
+        serde_json::Value::Object(object) => {
+            let tokens: Vec<&str> = path.iter().map(|token| token.as_ref()).collect();
+            if let Some(serde_json::Value::String(reference)) = object.get("$ref") {
+                if is_schema_position(&tokens) {
+                    references.push((to_pointer(tokens.iter().copied()), reference));
+                }
+            }
+            let mapping = object
+                .get("mapping")
+                .and_then(|mapping| mapping.as_object())
+                .filter(|_| tokens.last() == Some(&"discriminator"));
+            if let Some(mapping) = mapping {
+                let pointer = to_pointer(tokens.iter().copied().chain(["mapping"]));
+                references.extend(
+                    mapping
+                        .values()
+                        .filter_map(|reference| Some((pointer.clone(), reference.as_str()?))),
+                );
+            }
+            for (key, child) in object {
+                // literal values, whose keys mean nothing
+                if matches!(
+                    key.as_str(),
+                    "example" | "examples" | "default" | "enum" | "const"
+                ) {
+                    continue;
+                }
+                path.push(Cow::Borrowed(key));
+                collect_schema_refs(child, path, references);
+                path.pop();
+            }
+        }
+        serde_json::Value::Array(array) => {
+            for (i, child) in array.iter().enumerate() {
+                path.push(Cow::Owned(i.to_string()));
+                collect_schema_refs(child, path, references);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Reports the keywords of the schemas of `value`, at `path` of the
+/// document, that can't be represented and are ignored.
+fn report_unsupported_keywords<'v>(
+    value: &'v serde_json::Value,
+    path: &mut Vec<Cow<'v, str>>,
+    diagnostics: &Diagnostics,
+) {
+    const UNSUPPORTED: [&str; 5] = ["not", "if", "then", "else", "patternProperties"];
+    match value {
+        serde_json::Value::Object(object) => {
+            let tokens: Vec<&str> = path.iter().map(|token| token.as_ref()).collect();
+            if is_schema_position(&tokens) {
+                for keyword in UNSUPPORTED.into_iter().filter(|k| object.contains_key(*k)) {
+                    diagnostics.report_at(
+                        Severity::Warning,
+                        "unsupported-keyword",
+                        to_pointer(tokens.iter().copied().chain([keyword])),
+                        format!("`{}` isn't supported, it is ignored", keyword),
+                    );
+                }
+            }
+            for (key, child) in object {
+                if matches!(
+                    key.as_str(),
+                    "example" | "examples" | "default" | "enum" | "const"
+                ) {
+                    continue;
+                }
+                path.push(Cow::Borrowed(key));
+                report_unsupported_keywords(child, path, diagnostics);
+                path.pop();
+            }
+        }
+        serde_json::Value::Array(array) => {
+            for (i, child) in array.iter().enumerate() {
+                path.push(Cow::Owned(i.to_string()));
+                report_unsupported_keywords(child, path, diagnostics);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether a value at `path` of a document is a schema.
+fn is_schema_position(path: &[&str]) -> bool {
+    matches!(
+        path,
+        [.., "components", "schemas", _]
+            | [
+                ..,
+                "schema"
+                    | "items"
+                    | "not"
+                    | "additionalProperties"
+                    | "contains"
+                    | "propertyNames"
+                    | "if"
+                    | "then"
+                    | "else"
+            ]
+            | [
+                ..,
+                "properties"
+                    | "allOf"
+                    | "anyOf"
+                    | "oneOf"
+                    | "prefixItems"
+                    | "patternProperties"
+                    | "$defs"
+                    | "dependentSchemas",
+                _
+            ]
+    )
+}
+
+/// The name of the scheme synthesized for the schema at the pointer
+/// `tokens`, e.g. `Order_items` for `/components/schemas/Order/properties/items`
+/// and `users_get_200_application_json` for
+/// `/paths/~1users/get/responses/200/content/application~1json/schema`.
+fn pointer_scheme_name(tokens: &[String]) -> String {
+    const STRUCTURAL: [&str; 8] = [
+        "components",
+        "schemas",
+        "properties",
+        "paths",
+        "responses",
+        "content",
+        "schema",
+        "requestBody",
+    ];
+    let name = tokens
+        .iter()
+        .filter(|token| !STRUCTURAL.contains(&token.as_str()))
+        .map(|token| {
+            token
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join("_")
+        })
+        .filter(|token| !token.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    match name.is_empty() {
+        true => "Schema".to_string(),
+        false => name,
+    }
+}
+
+/// The JSON of a callback, following a `$ref` (usually into
+/// `components.callbacks`).
+fn resolve_callback<'a>(
+    raw: &'a serde_json::Value,
+    callback: &'a serde_json::Value,
+) -> Option<&'a serde_json::Value> {
+    let Some(ref_path) = callback.get("$ref").and_then(|r| r.as_str()) else {
+        return Some(callback);
+    };
+    let callback = resolve_pointer(raw, ref_path)?;
+    // references to references aren't followed
+    callback.get("$ref").is_none().then_some(callback)
+}
+
+/// Parses the spec of `document`, reading what `oas3` doesn't model from its
+/// JSON.
+pub fn parse_document<'a>(
+    document: &'a SpecDocument<'a>,
+    args: IntermediateArgs,
+) -> Result<IntermediateFormat<'a>, Error> {
+    let SpecDocument {
+        spec,
+        raw,
+        callbacks,
+        pointer_schemes,
+        ref_aliases,
+        pointer_scheme_origins,
+        diagnostics,
+    } = document;
     let mut schemes = Vec::new();
     let components = match &spec.components {
         Some(components) => components,
@@ -57,9 +561,8 @@ pub fn parse(spec: &oas3::Spec, args: IntermediateArgs) -> Result<IntermediateFo
     // schemes in the output (option A) so that sum-type variants and ref
     // passthroughs still resolve; the set is only used to decide whether a
     // property/parameter referencing such a scheme should be dropped.
-    let deprecated_schemes: HashSet<&str> = components
-        .schemas
-        .iter()
+    let all_schemas = || components.schemas.iter().chain(pointer_schemes);
+    let deprecated_schemes: HashSet<&str> = all_schemas()
         .filter_map(|(name, schema)| match schema {
             ObjectOrReference::Object(obj) if obj.deprecated == Some(true) => Some(name.as_str()),
             _ => None,
@@ -70,12 +573,45 @@ pub fn parse(spec: &oas3::Spec, args: IntermediateArgs) -> Result<IntermediateFo
         args,
         deprecated_schemes,
         schemas: &components.schemas,
+        components,
+        raw,
+        pointer_schemes,
+        ref_aliases,
+        diagnostics: Diagnostics::default(),
     };
+    ctx.diagnostics.extend(diagnostics.iter().cloned());
+
+    // `allOf` hierarchies: the schemes listing a base (see `hierarchy_base`) in
+    // their `allOf` extend it
+    let parents: HashMap<&str, &str> = all_schemas()
+        .filter_map(|(name, schema)| {
+            let ObjectOrReference::Object(obj) = schema else {
+                return None;
+            };
+            obj.all_of.iter().find_map(|member| match member {
+                ObjectOrReference::Ref { ref_path } => {
+                    let base = strip_ref_prefix(ctx.local_ref(ref_path));
+                    ctx.scheme(base)
+                        .and_then(hierarchy_base)
+                        .map(|_| (name.as_str(), base))
+                }
+                _ => None,
+            })
+        })
+        .collect();
 
-    for (name, schema) in components.schemas.iter() {
-        let obj = match parse_schema(&ctx, schema, false, false) {
-            Ok(obj) => obj,
-            Err(e) => return Err(e),
+    for (name, schema) in all_schemas() {
+        let _at = match pointer_scheme_origins.get(name) {
+            Some(origin) => ctx
+                .diagnostics
+                .at(&origin.iter().map(String::as_str).collect::<Vec<_>>()),
+            None => ctx.diagnostics.at(&["components", "schemas", name]),
+        };
+        let obj = match hierarchy_base(schema) {
+            Some((base, discriminator)) => {
+                parse_polymorphic(&ctx, name, base, discriminator, &parents)?
+            }
+            None => parse_schema(&ctx, schema, false, false)?,
         };
         schemes.push(Scheme {
             name: name.as_str(),
@@ -85,6 +621,7 @@ pub fn parse(spec: &oas3::Spec, args: IntermediateArgs) -> Result<IntermediateFo
                 IAST::Primitive(prim) => prim.nullable,
             },
             obj,
+            parent: parents.get(name.as_str()).copied(),
         });
     }
 
@@ -92,32 +629,15 @@ pub fn parse(spec: &oas3::Spec, args: IntermediateArgs) -> Result<IntermediateFo
         Some(paths) => {
             let mut routes = Vec::new();
             for (path, route) in paths.iter() {
+                println!("route: {}", path);
+                let _at = ctx.diagnostics.at(&["paths", path]);
+                let mut endpoints = parse_path_item(&ctx, path, route);
+
                 routes.push(Route {
                     path: path.as_str(),
                     description: route.description.as_deref(),
-                    endpoints: {
-                        let mut endpoints = Vec::new();
-
-                        let parser = macros::EndpointParser {
-                            ctx: &ctx,
-                            params_parser: &parse_params,
-                            request_parser: &parse_request,
-                            responses_parser: &parse_responses,
-                        };
-
-                        println!("route: {}", path);
-
-                        handle_endpoint!(&parser, &mut endpoints, &route.get, Method::Get);
-                        handle_endpoint!(&parser, &mut endpoints, &route.post, Method::Post);
-                        handle_endpoint!(&parser, &mut endpoints, &route.put, Method::Put);
-                        handle_endpoint!(&parser, &mut endpoints, &route.delete, Method::Delete);
-                        handle_endpoint!(&parser, &mut endpoints, &route.patch, Method::Patch);
-                        handle_endpoint!(&parser, &mut endpoints, &route.options, Method::Options);
-                        handle_endpoint!(&parser, &mut endpoints, &route.head, Method::Head);
-                        handle_endpoint!(&parser, &mut endpoints, &route.trace, Method::Trace);
-
-                        endpoints
-                    },
+                    path_params: split_path_params(&mut endpoints),
+                    endpoints,
                 });
             }
             routes
@@ -125,117 +645,590 @@ pub fn parse(spec: &oas3::Spec, args: IntermediateArgs) -> Result<IntermediateFo
         None => vec![],
     };
 
+    // requests the API sends: webhooks, then the callbacks of operations
+    let webhooks = spec.webhooks.iter().map(|(name, item)| {
+        let _at = ctx.diagnostics.at(&["webhooks", name]);
+        (
+            name.as_str(),
+            InboundOrigin::Webhook,
+            parse_path_item(&ctx, name, item),
+        )
+    });
+    let callbacks = callbacks.iter().map(|callback| {
+        let _at = ctx.diagnostics.at(&[
+            "paths",
+            callback.path,
+            method_key(&callback.method),
+            "callbacks",
+            callback.name,
+            callback.expression,
+        ]);
+        (
+            callback.name,
+            InboundOrigin::Callback {
+                path: callback.path,
+                method: callback.method,
+                expression: callback.expression,
+            },
+            parse_path_item(&ctx, callback.expression, &callback.item),
+        )
+    });
+    let inbound = webhooks
+        .chain(callbacks)
+        .flat_map(|(name, origin, endpoints)| {
+            endpoints.into_iter().map(move |mut endpoint| {
+                // the credentials the client sends don't apply to them
+                endpoint.security.clear();
+                InboundOperation {
+                    name,
+                    origin,
+                    endpoint,
+                }
+            })
+        })
+        .collect();
+
     let routes_tree = convert_routes_to_tree(&routes);
-    Ok(IntermediateFormat::new(schemes, routes, routes_tree))
+    let security_schemes = parse_security_schemes(&ctx, components);
+    let mut intermediate = IntermediateFormat::new(
+        schemes,
+        routes,
+        routes_tree,
+        security_schemes,
+        parse_servers(&spec.servers),
+        inbound,
+    );
+    let strict = ctx.args.strict;
+    intermediate.diagnostics = ctx.diagnostics.into_vec();
+    if strict {
+        let lossy: Vec<_> = intermediate
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity >= Severity::Warning)
+            .cloned()
+            .collect();
+        if !lossy.is_empty() {
+            return Err(Error::Unsupported(lossy));
+        }
+    }
+    Ok(intermediate)
 }
 
-fn parse_params<'a>(
+/// The endpoints of the operations of `item`, the path item of `path`.
+fn parse_path_item<'a>(ctx: &ParseCtx<'a>, path: &'a str, item: &'a PathItem) -> Vec<Endpoint<'a>> {
+    let mut endpoints = Vec::new();
+    let parser = macros::EndpointParser {
+        ctx,
+        params_parser: &parse_params,
+        request_parser: &parse_request,
+        responses_parser: &parse_responses,
+    };
+    handle_endpoint!(&parser, &mut endpoints, path, &item.get, Method::Get);
+    handle_endpoint!(&parser, &mut endpoints, path, &item.post, Method::Post);
+    handle_endpoint!(&parser, &mut endpoints, path, &item.put, Method::Put);
+    handle_endpoint!(&parser, &mut endpoints, path, &item.delete, Method::Delete);
+    handle_endpoint!(&parser, &mut endpoints, path, &item.patch, Method::Patch);
+    handle_endpoint!(
+        &parser,
+        &mut endpoints,
+        path,
+        &item.options,
+        Method::Options
+    );
+    handle_endpoint!(&parser, &mut endpoints, path, &item.head, Method::Head);
+    handle_endpoint!(&parser, &mut endpoints, path, &item.trace, Method::Trace);
+
+    if let Err(e) = merge_path_item_params(ctx, &item.parameters, &mut endpoints) {
+        let _at = ctx.diagnostics.at(&["parameters"]);
+        ctx.diagnostics.report(
+            Severity::Error,
+            "unparsable-parameters",
+            format!("the path's parameters are left out: {}", e),
+        );
+    }
+    // the path's servers stand in for those of operations without any
+    for endpoint in endpoints.iter_mut().filter(|e| e.servers.is_empty()) {
+        endpoint.servers = parse_servers(&item.servers);
+    }
+    endpoints
+}
+
+fn parse_servers(servers: &[SpecServer]) -> Vec<Server<'_>> {
+    servers
+        .iter()
+        .map(|server| Server {
+            url: &server.url,
+            description: server.description.as_deref(),
+            variables: server
+                .variables
+                .iter()
+                .map(|(name, variable)| ServerVariable {
+                    name,
+                    default: &variable.default,
+                    values: variable
+                        .substitutions_enum
+                        .iter()
+                        .map(String::as_str)
+                        .collect(),
+                    description: variable.description.as_deref(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+fn parse_security_schemes<'a>(
     ctx: &ParseCtx<'a>,
-    params: &'a Vec<ObjectOrReference<Parameter>>,
-) -> Result<Vec<Param<'a>>, Error> {
-    params
+    components: &'a Components,
+) -> Vec<SecurityScheme<'a>> {
+    components
+        .security_schemes
         .iter()
-        .map(|param| match param {
-            ObjectOrReference::Object(p) => {
-                let Parameter {
+        .filter_map(|(name, scheme)| {
+            let _at = ctx.diagnostics.at(&["components", "securitySchemes", name]);
+            let ObjectOrReference::Object(scheme) = scheme else {
+                ctx.diagnostics.report(
+                    Severity::Warning,
+                    "security-scheme-reference",
+                    "referenced security schemes aren't supported, it is skipped",
+                );
+                return None;
+            };
+            let (description, kind) = match scheme {
+                SpecSecurityScheme::ApiKey {
+                    description,
                     name,
                     location,
+                } => (
                     description,
-                    required,
-                    deprecated,
-                    schema,
-                    ..
-                } = p;
-                if location == &ParameterIn::Path {
+                    SecuritySchemeKind::ApiKey {
+                        name,
+                        location: match location.as_str() {
+                            "query" => ParamLocation::Query,
+                            "cookie" => ParamLocation::Cookie,
+                            _ => ParamLocation::Header,
+                        },
+                    },
+                ),
+                SpecSecurityScheme::Http {
+                    description,
+                    scheme,
+                    bearer_format,
+                } => (
+                    description,
+                    SecuritySchemeKind::Http {
+                        scheme,
+                        bearer_format: bearer_format.as_deref(),
+                    },
+                ),
+                SpecSecurityScheme::OAuth2 { description, flows } => {
+                    let scopes = [
+                        flows.implicit.as_ref().map(|f| &f.scopes),
+                        flows.password.as_ref().map(|f| &f.scopes),
+                        flows.client_credentials.as_ref().map(|f| &f.scopes),
+                        flows.authorization_code.as_ref().map(|f| &f.scopes),
+                    ];
+                    (
+                        description,
+                        SecuritySchemeKind::OAuth2 {
+                            scopes: scopes
+                                .into_iter()
+                                .flatten()
+                                .flatten()
+                                .map(|(scope, description)| (scope.as_str(), description.as_str()))
+                                .collect(),
+                        },
+                    )
+                }
+                SpecSecurityScheme::OpenIdConnect {
+                    description,
+                    open_id_connect_url,
+                } => (
+                    description,
+                    SecuritySchemeKind::OpenIdConnect {
+                        url: open_id_connect_url,
+                    },
+                ),
+                // nothing to send, the TLS connection authenticates
+                SpecSecurityScheme::MutualTls { .. } => {
+                    ctx.diagnostics.report(
+                        Severity::Info,
+                        "mutual-tls",
+                        "mutual TLS is up to the HTTP client, the scheme is skipped",
+                    );
                     return None;
                 }
-                if ctx.args.ignore_deprecated_fields {
-                    // skip params explicitly marked deprecated
-                    if deprecated.unwrap_or(false) {
+            };
+            Some(SecurityScheme {
+                name,
+                description: description.as_deref(),
+                kind,
+            })
+        })
+        .collect()
+}
+
+fn parse_params<'a>(
+    ctx: &ParseCtx<'a>,
+    params: &'a [ObjectOrReference<Parameter>],
+) -> Result<Vec<Param<'a>>, Error> {
+    params
+        .iter()
+        .enumerate()
+        .filter_map(|(i, param)| {
+            let _at = ctx.diagnostics.at(&["parameters", &i.to_string()]);
+            let p = match resolve_component(&ctx.components.parameters, "parameters", param) {
+                Ok(p) => p,
+                Err(e) => return Some(Err(e)),
+            };
+            let Parameter {
+                name,
+                location,
+                description,
+                required,
+                deprecated,
+                schema,
+                style,
+                explode,
+                extensions,
+                ..
+            } = p;
+            let location = match location {
+                ParameterIn::Path => ParamLocation::Path,
+                ParameterIn::Query => ParamLocation::Query,
+                ParameterIn::Header => ParamLocation::Header,
+                ParameterIn::Cookie => ParamLocation::Cookie,
+            };
+            // path params are part of the route itself, so they are kept
+            // even when deprecated
+            if ctx.args.ignore_deprecated_fields && location != ParamLocation::Path {
+                // skip params explicitly marked deprecated
+                if deprecated.unwrap_or(false) {
+                    return None;
+                }
+                // skip params whose schema is a $ref to a deprecated scheme
+                if let Some(ObjectOrReference::Ref { ref_path }) = schema {
+                    if ctx.ref_targets_deprecated(ref_path) {
                         return None;
                     }
-                    // skip params whose schema is a $ref to a deprecated scheme
-                    if let Some(ObjectOrReference::Ref { ref_path }) = schema {
-                        if ctx.ref_targets_deprecated(ref_path) {
-                            return None;
-                        }
+                }
+            }
+            // path params are always required, whatever the spec says
+            let required = location == ParamLocation::Path || required.unwrap_or(false);
+            let schema = match schema {
+                Some(schema) => {
+                    let _at = ctx.diagnostics.at(&["schema"]);
+                    match parse_schema(ctx, schema, !required, false) {
+                        Ok(schema) => schema,
+                        Err(e) => return Some(Err(e)),
                     }
                 }
-                Some(Ok(Param {
-                    name: name.as_str(),
+                // params described via `content` instead of `schema` are
+                // sent as an already serialized string
+                None => IAST::Primitive(AnnotatedObj {
+                    nullable: false,
+                    optional: !required,
+                    is_deprecated: deprecated.unwrap_or(false),
                     description: description.as_deref(),
-                    required: required.unwrap_or(false),
-                }))
+                    title: None,
+                    format: None,
+                    constraints: Constraints::default(),
+                    default: None,
+                    read_only: false,
+                    write_only: false,
+                    value: Primitive::String,
+                }),
+            };
+            let style = match style {
+                Some(ParameterStyle::Matrix) => ParamStyle::Matrix,
+                Some(ParameterStyle::Label) => ParamStyle::Label,
+                Some(ParameterStyle::Form) => ParamStyle::Form,
+                Some(ParameterStyle::Simple) => ParamStyle::Simple,
+                Some(ParameterStyle::SpaceDelimited) => ParamStyle::SpaceDelimited,
+                Some(ParameterStyle::PipeDelimited) => ParamStyle::PipeDelimited,
+                Some(ParameterStyle::DeepObject) => ParamStyle::DeepObject,
+                None => match location {
+                    ParamLocation::Query | ParamLocation::Cookie => ParamStyle::Form,
+                    ParamLocation::Path | ParamLocation::Header => ParamStyle::Simple,
+                },
+            };
+            // only the form style explodes by default
+            let explode = explode.unwrap_or(style == ParamStyle::Form);
+            let is_list = matches!(
+                &schema,
+                IAST::Primitive(AnnotatedObj {
+                    value: Primitive::List(_),
+                    ..
+                })
+            );
+            // set by the Swagger 2.0 conversion
+            if let Some(serde_json::Value::String(format)) = extensions.get("collection-format") {
+                ctx.diagnostics.report(
+                    Severity::Warning,
+                    "unsupported-param-style",
+                    format!(
+                        "the Swagger 2.0 `collectionFormat` `{}` has no OpenAPI 3 `style`, the \
+                         elements are sent comma-separated",
+                        format
+                    ),
+                );
             }
-            ObjectOrReference::Ref { ref_path, .. } => Some(Err(Error::ParseError(format!(
-                "Reference to {} not supported in params",
-                ref_path
-            )))),
+            // path params are rendered into the route, the others into the
+            // params map, which holds a single value per name
+            if is_list && location != ParamLocation::Path {
+                match style {
+                    ParamStyle::Form if explode => ctx.diagnostics.report(
+                        Severity::Warning,
+                        "unsupported-param-style",
+                        "exploded `form` arrays repeat the param, which the params map can't \
+                         hold, the elements are sent comma-separated",
+                    ),
+                    ParamStyle::Form
+                    | ParamStyle::Simple
+                    | ParamStyle::SpaceDelimited
+                    | ParamStyle::PipeDelimited => {}
+                    ParamStyle::Matrix | ParamStyle::Label | ParamStyle::DeepObject => {
+                        ctx.diagnostics.report(
+                            Severity::Warning,
+                            "unsupported-param-style",
+                            "the `style` isn't supported for arrays, the elements are sent \
+                             comma-separated",
+                        )
+                    }
+                }
+            }
+            Some(Ok(Param {
+                name: name.as_str(),
+                location,
+                description: description.as_deref(),
+                required,
+                schema,
+                style,
+                explode,
+            }))
         })
-        .filter_map(|param| param)
         .collect()
 }
 
+/// Add the params declared on the path item to every endpoint of the route.
+/// An operation-level param with the same name and location overrides the
+/// path-level one, as the OpenAPI spec demands.
+fn merge_path_item_params<'a>(
+    ctx: &ParseCtx<'a>,
+    params: &'a [ObjectOrReference<Parameter>],
+    endpoints: &mut [Endpoint<'a>],
+) -> Result<(), Error> {
+    if params.is_empty() {
+        return Ok(());
+    }
+    for endpoint in endpoints.iter_mut() {
+        // parsed once per endpoint as the IAST can not be cloned
+        let shared = parse_params(ctx, params)?;
+        let mut merged: Vec<Param<'a>> = shared
+            .into_iter()
+            .filter(|shared| {
+                !endpoint
+                    .params
+                    .iter()
+                    .any(|own| own.name == shared.name && own.location == shared.location)
+            })
+            .collect();
+        merged.append(&mut endpoint.params);
+        endpoint.params = merged;
+    }
+    Ok(())
+}
+
+/// Move the path params out of the endpoints' `params` into a single list
+/// for the route. Every operation of a route shares the same path, so the
+/// first declaration of a path param wins.
+fn split_path_params<'a>(endpoints: &mut [Endpoint<'a>]) -> Vec<Param<'a>> {
+    let mut path_params: Vec<Param<'a>> = Vec::new();
+    for endpoint in endpoints.iter_mut() {
+        let (path, rest) = std::mem::take(&mut endpoint.params)
+            .into_iter()
+            .partition::<Vec<_>, _>(|p| p.location == ParamLocation::Path);
+        endpoint.params = rest;
+        for param in path {
+            if !path_params.iter().any(|p| p.name == param.name) {
+                path_params.push(param);
+            }
+        }
+    }
+    path_params
+}
+
 fn parse_request<'a>(
     ctx: &ParseCtx<'a>,
     request: Option<&'a ObjectOrReference<RequestBody>>,
-) -> Result<IAST<'a>, Error> {
-    match request {
-        Some(ObjectOrReference::Object(req_body)) => {
-            //we only consider a single possible format (like application/json) for now
-            let scheme = req_body
-                .content
-                .iter()
-                .next()
-                .unwrap()
-                .1
-                .schema
-                .as_ref()
-                .unwrap();
-            parse_schema(ctx, scheme, false, false)
-        }
-        Some(ObjectOrReference::Ref { ref_path, .. }) => Ok(IAST::Reference(AnnotatedReference {
-            path: ref_path,
-            optional: false,
-            nullable: false,
-            is_deprecated: ctx.ref_targets_deprecated(ref_path),
-        })),
-        None => Err(Error::ParseError("No request body".to_string())),
-    }
+) -> Result<Option<Content<'a>>, Error> {
+    let Some(request) = request else {
+        return Ok(None);
+    };
+    let _at = ctx.diagnostics.at(&["requestBody"]);
+    let req_body = resolve_component(&ctx.components.request_bodies, "requestBodies", request)?;
+    let content = parse_content(ctx, &req_body.content)?;
+    // a body without any schema can not be typed, so it is not sent at all
+    Ok(if content.is_empty() {
+        ctx.diagnostics.report(
+            Severity::Warning,
+            "request-body-without-schema",
+            "no media type of the `content` has a `schema`, no body is sent",
+        );
+        None
+    } else {
+        Some(content)
+    })
 }
 fn parse_responses<'a>(
     ctx: &ParseCtx<'a>,
     responses: &'a BTreeMap<String, ObjectOrReference<Responses>>,
-) -> Result<BTreeMap<&'a String, IAST<'a>>, Error> {
+) -> Result<BTreeMap<&'a String, Content<'a>>, Error> {
     let mut map = BTreeMap::new();
     for (code, response) in responses {
-        match response {
-            ObjectOrReference::Object(req_body) => {
-                //we only consider a single possible format (like application/json) for now
-                let scheme_opt = &req_body.content.iter().next();
-                let scheme = match scheme_opt {
-                    Some(schema) => &schema.1.schema,
-                    None => return Err(Error::ParseError("No response body".to_string())),
-                };
-                if let Some(schema) = scheme {
-                    let schema = parse_schema(ctx, &schema, false, false).unwrap();
-                    map.insert(code, schema);
-                }
-            }
-            ObjectOrReference::Ref { ref_path, .. } => {
-                let schema = IAST::Reference(AnnotatedReference {
-                    path: ref_path,
-                    optional: false,
-                    nullable: false,
-                    is_deprecated: ctx.ref_targets_deprecated(ref_path),
-                });
-                map.insert(code, schema);
+        let _at = ctx.diagnostics.at(&["responses", code]);
+        let response = resolve_component(&ctx.components.responses, "responses", response)?;
+        let content = parse_content(ctx, &response.content)?;
+        if !content.is_empty() {
+            map.insert(code, content);
+        }
+    }
+    Ok(map)
+}
+fn parse_response_headers<'a>(
+    ctx: &ParseCtx<'a>,
+    responses: &'a BTreeMap<String, ObjectOrReference<Responses>>,
+) -> Result<BTreeMap<&'a String, Vec<ResponseHeader<'a>>>, Error> {
+    let mut map = BTreeMap::new();
+    for (code, response) in responses {
+        let _at = ctx.diagnostics.at(&["responses", code]);
+        let response = resolve_component(&ctx.components.responses, "responses", response)?;
+        let mut headers = Vec::new();
+        for (name, header) in &response.headers {
+            // the media type is told by the content, not by a header
+            if name.eq_ignore_ascii_case("content-type") {
+                continue;
             }
+            let _at = ctx.diagnostics.at(&["headers", name]);
+            let header = resolve_component(&ctx.components.headers, "headers", header)?;
+            let Some(schema) = &header.schema else {
+                ctx.diagnostics.report(
+                    Severity::Warning,
+                    "header-without-schema",
+                    "headers described by `content` aren't supported, it is skipped",
+                );
+                continue;
+            };
+            let _at = ctx.diagnostics.at(&["schema"]);
+            let required = header.required.unwrap_or(false);
+            headers.push(ResponseHeader {
+                name,
+                description: header.description.as_deref(),
+                required,
+                schema: parse_schema(ctx, schema, !required, false)?,
+            });
+        }
+        if !headers.is_empty() {
+            map.insert(code, headers);
         }
     }
     Ok(map)
 }
 
+fn parse_content<'a>(
+    ctx: &ParseCtx<'a>,
+    content: &'a BTreeMap<String, MediaType>,
+) -> Result<Content<'a>, Error> {
+    let mut map = BTreeMap::new();
+    for (media_type, media) in content {
+        if let Some(schema) = &media.schema {
+            let _at = ctx.diagnostics.at(&["content", media_type]);
+            map.insert(
+                media_type.as_str(),
+                Payload {
+                    schema: {
+                        let _at = ctx.diagnostics.at(&["schema"]);
+                        parse_schema(ctx, schema, false, false)?
+                    },
+                    format: PayloadFormat::of(media_type),
+                    encoding: media
+                        .encoding
+                        .iter()
+                        .map(|(property, encoding)| {
+                            let _at = ctx.diagnostics.at(&["encoding", property]);
+                            (property.as_str(), parse_property_encoding(ctx, encoding))
+                        })
+                        .collect(),
+                },
+            );
+        }
+    }
+    Ok(map)
+}
+
+fn parse_property_encoding<'a>(ctx: &ParseCtx<'a>, encoding: &'a Encoding) -> PropertyEncoding<'a> {
+    let style = match encoding.style.as_deref() {
+        None | Some("form") => FormStyle::Form,
+        Some("spaceDelimited") => FormStyle::SpaceDelimited,
+        Some("pipeDelimited") => FormStyle::PipeDelimited,
+        Some("deepObject") => FormStyle::DeepObject,
+        Some(style) => {
+            ctx.diagnostics.report(
+                Severity::Warning,
+                "unsupported-encoding-style",
+                format!("the `{}` style isn't supported, `form` is used", style),
+            );
+            FormStyle::Form
+        }
+    };
+    PropertyEncoding {
+        content_type: encoding.content_type.as_deref(),
+        style,
+        // only the form style explodes by default
+        explode: encoding.explode.unwrap_or(style == FormStyle::Form),
+    }
+}
+
+/// Resolve a (possibly referenced) component to its definition. References
+/// must point into `components.<kind>` (e.g. `#/components/parameters/Limit`)
+/// and are followed transitively, so a component that is itself just a
+/// `$ref` to another one resolves too. Dangling and cyclic references are
+/// reported as errors.
+fn resolve_component<'a, T>(
+    components: &'a BTreeMap<String, ObjectOrReference<T>>,
+    kind: &str,
+    component: &'a ObjectOrReference<T>,
+) -> Result<&'a T, Error> {
+    let mut visited: HashSet<String> = HashSet::new();
+    let mut current = component;
+    loop {
+        let ref_path = match current {
+            ObjectOrReference::Object(obj) => return Ok(obj),
+            ObjectOrReference::Ref { ref_path } => ref_path.as_str(),
+        };
+        let name = match pointer_tokens(ref_path).as_deref() {
+            Some([components, section, name]) if components == "components" && section == kind => {
+                name.clone()
+            }
+            _ => {
+                return Err(Error::ParseError(format!(
+                    "Reference to {} does not point into components.{}",
+                    ref_path, kind
+                )))
+            }
+        };
+        if !visited.insert(name.clone()) {
+            return Err(Error::ParseError(format!(
+                "Cyclic reference to {} in components.{}",
+                ref_path, kind
+            )));
+        }
+        current = components
+            .get(&name)
+            .ok_or_else(|| Error::ParseError(format!("Unresolvable reference to {}", ref_path)))?;
+    }
+}
+
 fn parse_schema<'a>(
     ctx: &ParseCtx<'a>,
     schema: &'a ObjectOrReference<ObjectSchema>,
@@ -246,14 +1239,32 @@ fn parse_schema<'a>(
     match schema {
         ObjectOrReference::Object(object) => parse_object(ctx, object, is_optional),
         ObjectOrReference::Ref { ref_path } => Ok(IAST::Reference(AnnotatedReference {
-            path: ref_path,
+            path: ctx.local_ref(ref_path),
             optional: is_optional,
             nullable: ref_is_nullable,
             is_deprecated: ctx.ref_targets_deprecated(ref_path),
+            read_only: false,
+            write_only: false,
         })),
     }
 }
 
+fn parse_constraints(object: &ObjectSchema) -> Constraints<'_> {
+    Constraints {
+        minimum: object.minimum.clone(),
+        exclusive_minimum: object.exclusive_minimum.clone(),
+        maximum: object.maximum.clone(),
+        exclusive_maximum: object.exclusive_maximum.clone(),
+        multiple_of: object.multiple_of.clone(),
+        min_length: object.min_length,
+        max_length: object.max_length,
+        pattern: object.pattern.as_deref(),
+        min_items: object.min_items,
+        max_items: object.max_items,
+        unique_items: object.unique_items.unwrap_or(false),
+    }
+}
+
 fn parse_object<'a>(
     ctx: &ParseCtx<'a>,
     object: &'a ObjectSchema,
@@ -266,6 +1277,11 @@ fn parse_object<'a>(
             is_deprecated: object.deprecated.unwrap_or(false),
             description: object.description.as_deref(),
             title: object.title.as_deref(),
+            format: object.format.as_deref(),
+            constraints: parse_constraints(object),
+            default: object.default.as_ref(),
+            read_only: object.read_only.unwrap_or(false),
+            write_only: object.write_only.unwrap_or(false),
             value: Primitive::Binary,
         }));
     }
@@ -278,6 +1294,11 @@ fn parse_object<'a>(
             is_deprecated: object.deprecated.unwrap_or(false),
             description: object.description.as_deref(),
             title: object.title.as_deref(),
+            format: object.format.as_deref(),
+            constraints: parse_constraints(object),
+            default: object.default.as_ref(),
+            read_only: object.read_only.unwrap_or(false),
+            write_only: object.write_only.unwrap_or(false),
             value: AlgType::Product(
                 match object
                     .properties
@@ -285,11 +1306,7 @@ fn parse_object<'a>(
                     .filter_map(|(name, schema)| {
                         let is_required =
                             object.required.iter().any(|n| n.as_str() == name.as_str());
-                        // println!("parsing property: {}, nullable: {}", name, !is_required);
-
-                        if name.starts_with("dummy") {
-                            println!("v1001: {} optional:{:?} {:?}", name, !is_required, schema);
-                        }
+                        let _at = ctx.diagnostics.at(&["properties", name]);
 
                         //TODO: there was a case where a required object could either be an object or null, but the oas3 spec properties where empty although the json had some, idk
                         match parse_schema(ctx, schema, !is_required, false) {
@@ -311,6 +1328,7 @@ fn parse_object<'a>(
                     Ok(types) => types,
                     Err(e) => return Err(e),
                 },
+                parse_additional_properties(ctx, object)?.map(Box::new),
             ),
         }))
     };
@@ -352,26 +1370,47 @@ fn parse_object<'a>(
             SchemaType::Boolean => Primitive::Boolean,
             SchemaType::Integer => Primitive::Integer,
             SchemaType::Number => Primitive::Number,
-            SchemaType::String => Primitive::String,
+            SchemaType::String => match object.format.as_deref() {
+                Some("date-time") => Primitive::DateTime,
+                Some("date") => Primitive::Date,
+                Some("uri") | Some("uri-reference") => Primitive::Uri,
+                Some("byte") => Primitive::Base64,
+                _ => Primitive::String,
+            },
             SchemaType::Null => Primitive::Never,
-            //TODO: maybe parse properties here
-            SchemaType::Object => match parse_properties() {
-                Ok(obj) => Primitive::Map(Box::new(obj)),
+            SchemaType::Object => match parse_additional_properties(ctx, object) {
+                Ok(Some(values)) => Primitive::Map(Box::new(values)),
+                Ok(None) => Primitive::Map(Box::new(dynamic_iast())),
                 Err(e) => {
-                    println!("error parsing object: {:?}", e);
+                    ctx.diagnostics.report(
+                        Severity::Warning,
+                        "unparsable-additional-properties",
+                        format!("{}, the values are generated as `dynamic`", e),
+                    );
                     Primitive::Dynamic
                 }
             },
             SchemaType::Array => match &object.items {
-                Some(items) => match parse_schema(ctx, &items, false, false) {
-                    Ok(obj) => Primitive::List(Box::new(obj)),
-                    Err(e) => {
-                        println!("error parsing list: {:?}", e);
-                        Primitive::Dynamic
+                Some(items) => {
+                    let _at = ctx.diagnostics.at(&["items"]);
+                    match parse_schema(ctx, items, false, false) {
+                        Ok(obj) => Primitive::List(Box::new(obj)),
+                        Err(e) => {
+                            ctx.diagnostics.report(
+                                Severity::Warning,
+                                "unparsable-items",
+                                format!("{}, the list is generated as `dynamic`", e),
+                            );
+                            Primitive::Dynamic
+                        }
                     }
-                },
+                }
                 None => {
-                    println!("error parsing list, no items");
+                    ctx.diagnostics.report(
+                        Severity::Warning,
+                        "list-without-items",
+                        "the array has no `items`, it is generated as `dynamic`",
+                    );
                     Primitive::Dynamic
                 }
             },
@@ -379,9 +1418,59 @@ fn parse_object<'a>(
     };
     // if type is set, we can return a primitive type
     if let Some(types) = &object.schema_type {
+        // several types (besides null) make a union of them
+        if let SchemaTypeSet::Multiple(types) = types {
+            let variants: Vec<&SchemaType> = types
+                .iter()
+                .filter(|typ| typ != &&SchemaType::Null)
+                .collect();
+            // enum values are shared by all of them, so stay a single enum
+            if variants.len() > 1 && object.enum_values.is_empty() && object.const_value.is_none() {
+                return Ok(IAST::Object(AnnotatedObj {
+                    nullable: types.contains(&SchemaType::Null),
+                    optional: is_optional,
+                    is_deprecated: object.deprecated.unwrap_or(false),
+                    description: object.description.as_deref(),
+                    title: object.title.as_deref(),
+                    format: object.format.as_deref(),
+                    constraints: Constraints::default(),
+                    default: object.default.as_ref(),
+                    read_only: object.read_only.unwrap_or(false),
+                    write_only: object.write_only.unwrap_or(false),
+                    value: AlgType::Sum(
+                        variants
+                            .into_iter()
+                            .map(|schema_type| {
+                                let typ = match schema_type {
+                                    SchemaType::Object if !object.properties.is_empty() => {
+                                        parse_properties()?
+                                    }
+                                    _ => IAST::Primitive(AnnotatedObj {
+                                        nullable: false,
+                                        optional: false,
+                                        is_deprecated: false,
+                                        description: None,
+                                        title: None,
+                                        format: object.format.as_deref(),
+                                        constraints: parse_constraints(object),
+                                        default: None,
+                                        read_only: false,
+                                        write_only: false,
+                                        value: parse_prim_type(schema_type),
+                                    }),
+                                };
+                                Ok(types::SumVariant {
+                                    name: schema_type_name(schema_type).to_string(),
+                                    typ,
+                                })
+                            })
+                            .collect::<Result<_, Error>>()?,
+                    ),
+                }));
+            }
+        }
         let prim_type = match types {
             SchemaTypeSet::Single(typ) => typ,
-            // we currently don't support multiple primitive types
             SchemaTypeSet::Multiple(types) => types
                 .iter()
                 .filter(|typ| typ != &&SchemaType::Null)
@@ -389,6 +1478,26 @@ fn parse_object<'a>(
                 .first()
                 .unwrap_or(&&SchemaType::Null),
         };
+        if let SchemaTypeSet::Multiple(types) = types {
+            let dropped: Vec<_> = types
+                .iter()
+                .filter(|typ| typ != &prim_type && typ != &&SchemaType::Null)
+                .map(schema_type_name)
+                .collect();
+            // the enum of other types is kept by `parse_prim_type`, an
+            // object's isn't
+            if prim_type == &SchemaType::Object && !dropped.is_empty() {
+                let _at = ctx.diagnostics.at(&["type"]);
+                ctx.diagnostics.report(
+                    Severity::Warning,
+                    "truncated-type",
+                    format!(
+                        "only `object` of the `type`s is used, ignoring `{}`",
+                        dropped.join("`, `")
+                    ),
+                );
+            }
+        }
         if prim_type != &SchemaType::Object {
             let value = parse_prim_type(prim_type);
             return Ok(IAST::Primitive(AnnotatedObj {
@@ -397,6 +1506,11 @@ fn parse_object<'a>(
                 is_deprecated: object.deprecated.unwrap_or(false),
                 description: object.description.as_deref(),
                 title: object.title.as_deref(),
+                format: object.format.as_deref(),
+                constraints: parse_constraints(object),
+                default: object.default.as_ref(),
+                read_only: object.read_only.unwrap_or(false),
+                write_only: object.write_only.unwrap_or(false),
                 value,
             }));
         }
@@ -406,49 +1520,42 @@ fn parse_object<'a>(
     // 1: if its has any_of or one_of set, we need to return a sum type
     // 2: if its has properties set, we need to return a product type
     // 3  if it has all of set its probably a nullable ref weird edge case situation
+    // 4: if it only allows additional properties, we need to return a map
 
     // 1:
     if !object.any_of.is_empty() || !object.one_of.is_empty() {
         // it cannot have both so we just precede with any_of
-        let union_types = if !object.any_of.is_empty() {
-            &object.any_of
+        let (keyword, union_types) = if !object.any_of.is_empty() {
+            ("anyOf", &object.any_of)
         } else {
-            &object.one_of
+            ("oneOf", &object.one_of)
         };
         let discrimination = match &object.discriminator {
-            Some(Discriminator {
-                mapping: Some(mapping),
-                property_name,
-            }) => Some(Discrimination {
-                key: property_name.as_str(),
-                // theoretisch können die auch mal kein mapping haben, dann muss die value hinter dem key in dem object den Namen des objects haben
-                map: mapping
-                    .into_iter()
-                    .map(|(k, v)| {
-                        (
-                            k.as_str(),
-                            AnnotatedReference {
-                                path: v.as_str(),
-                                optional: false,
-                                nullable: false,
-                                // sum-type variants are KEPT even if deprecated, but we still
-                                // surface the flag so codegen can annotate them appropriately
-                                is_deprecated: ctx.ref_targets_deprecated(v.as_str()),
-                            },
-                        )
-                    })
-                    .collect(),
-            }),
+            // without an explicit mapping, each variant has to be a named scheme
+            Some(discriminator)
+                if discriminator.mapping.is_some()
+                    || union_types
+                        .iter()
+                        .all(|schema| matches!(schema, ObjectOrReference::Ref { .. })) =>
+            {
+                Some(parse_discrimination(
+                    ctx,
+                    discriminator,
+                    union_types.iter().filter_map(|schema| match schema {
+                        ObjectOrReference::Ref { ref_path } => Some(ref_path.as_str()),
+                        ObjectOrReference::Object(_) => None,
+                    }),
+                ))
+            }
             _ => None,
         };
         let nullable = union_types.iter().any(|schema| match schema {
             ObjectOrReference::Object(schema) => {
                 schema.is_nullable().unwrap_or(false)
-                    || if let Some(SchemaTypeSet::Single(SchemaType::Null)) = schema.schema_type {
-                        true
-                    } else {
-                        false
-                    }
+                    || matches!(
+                        schema.schema_type,
+                        Some(SchemaTypeSet::Single(SchemaType::Null))
+                    )
             }
             ObjectOrReference::Ref { .. } => false,
         });
@@ -458,11 +1565,17 @@ fn parse_object<'a>(
             is_deprecated: object.deprecated.unwrap_or(false),
             description: object.description.as_deref(),
             title: object.title.as_deref(),
+            format: object.format.as_deref(),
+            constraints: parse_constraints(object),
+            default: object.default.as_ref(),
+            read_only: object.read_only.unwrap_or(false),
+            write_only: object.write_only.unwrap_or(false),
             value: match union_types
                 .iter()
                 .enumerate()
-                .map(
-                    |(idx, schema)| match parse_schema(ctx, schema, false, nullable) {
+                .map(|(idx, schema)| {
+                    let _at = ctx.diagnostics.at(&[keyword, &idx.to_string()]);
+                    match parse_schema(ctx, schema, false, nullable) {
                         Ok(obj) => Ok(types::SumVariant {
                             name: match &obj {
                                 IAST::Reference(refe) => strip_ref_prefix(refe.path).to_string(),
@@ -470,11 +1583,9 @@ fn parse_object<'a>(
                             },
                             typ: obj,
                         }),
-                        Err(e) => {
-                            return Err(e);
-                        }
-                    },
-                )
+                        Err(e) => Err(e),
+                    }
+                })
                 .collect::<Result<Vec<_>, _>>()
             {
                 Ok(types) => match discrimination {
@@ -523,10 +1634,12 @@ fn parse_object<'a>(
                 .find(|s| matches!(s, ObjectOrReference::Ref { .. }))
             {
                 return Ok(IAST::Reference(AnnotatedReference {
-                    path: ref_path,
+                    path: ctx.local_ref(ref_path),
                     optional: is_optional,
                     nullable: allows_null,
                     is_deprecated: ctx.ref_targets_deprecated(ref_path),
+                    read_only: object.read_only.unwrap_or(false),
+                    write_only: object.write_only.unwrap_or(false),
                 }));
             }
         } else {
@@ -542,13 +1655,64 @@ fn parse_object<'a>(
                     is_deprecated: object.deprecated.unwrap_or(false),
                     description: object.description.as_deref(),
                     title: object.title.as_deref(),
-                    value: AlgType::Product(merged),
+                    format: object.format.as_deref(),
+                    constraints: parse_constraints(object),
+                    default: object.default.as_ref(),
+                    read_only: object.read_only.unwrap_or(false),
+                    write_only: object.write_only.unwrap_or(false),
+                    value: AlgType::Product(
+                        merged,
+                        parse_additional_properties(ctx, object)?.map(Box::new),
+                    ),
                 }));
             }
         }
     }
 
-    println!("got to an empty object");
+    // 4: no fixed properties, but arbitrary ones: a dictionary
+    if let Some(values) = parse_additional_properties(ctx, object)? {
+        return Ok(IAST::Primitive(AnnotatedObj {
+            nullable: object.is_nullable().unwrap_or(false),
+            optional: is_optional,
+            is_deprecated: object.deprecated.unwrap_or(false),
+            description: object.description.as_deref(),
+            title: object.title.as_deref(),
+            format: object.format.as_deref(),
+            constraints: parse_constraints(object),
+            default: object.default.as_ref(),
+            read_only: object.read_only.unwrap_or(false),
+            write_only: object.write_only.unwrap_or(false),
+            value: Primitive::Map(Box::new(values)),
+        }));
+    }
+
+    // 5: `{}`, which accepts any value
+    if accepts_any_value(ctx) {
+        ctx.diagnostics.report(
+            Severity::Info,
+            "any-schema",
+            "the schema accepts any value, it is generated as `dynamic`",
+        );
+        return Ok(IAST::Primitive(AnnotatedObj {
+            nullable: false,
+            optional: is_optional,
+            is_deprecated: object.deprecated.unwrap_or(false),
+            description: object.description.as_deref(),
+            title: object.title.as_deref(),
+            format: None,
+            constraints: Constraints::default(),
+            default: object.default.as_ref(),
+            read_only: object.read_only.unwrap_or(false),
+            write_only: object.write_only.unwrap_or(false),
+            value: Primitive::Dynamic,
+        }));
+    }
+
+    ctx.diagnostics.report(
+        Severity::Warning,
+        "untyped-schema",
+        "the schema has no type, properties or composition, it is generated as an unknown object",
+    );
 
     //TODO: hmm
     Ok(IAST::Primitive(AnnotatedObj {
@@ -564,10 +1728,174 @@ fn parse_object<'a>(
             }
         },
         title: object.title.as_deref(),
+        format: object.format.as_deref(),
+        constraints: parse_constraints(object),
+        default: object.default.as_ref(),
+        read_only: object.read_only.unwrap_or(false),
+        write_only: object.write_only.unwrap_or(false),
         value: Primitive::Never,
     }))
 }
 
+/// The values of `discriminator`: those of its `mapping`, and the names of the
+/// schemes `implicit` points at which it doesn't cover.
+fn parse_discrimination<'a>(
+    ctx: &ParseCtx<'a>,
+    discriminator: &'a Discriminator,
+    implicit: impl Iterator<Item = &'a str>,
+) -> Discrimination<'a> {
+    let reference = |path: &'a str| AnnotatedReference {
+        path: ctx.local_ref(path),
+        optional: false,
+        nullable: false,
+        // sum-type variants are KEPT even if deprecated, but we still
+        // surface the flag so codegen can annotate them appropriately
+        is_deprecated: ctx.ref_targets_deprecated(path),
+        read_only: false,
+        write_only: false,
+    };
+    let mut map: BTreeMap<&'a str, AnnotatedReference<'a>> = discriminator
+        .mapping
+        .iter()
+        .flatten()
+        .map(|(value, path)| (value.as_str(), reference(path.as_str())))
+        .collect();
+    for path in implicit {
+        let name = strip_ref_prefix(ctx.local_ref(path));
+        if !map.values().any(|r| strip_ref_prefix(r.path) == name) {
+            map.insert(name, reference(path));
+        }
+    }
+    Discrimination {
+        key: discriminator.property_name.as_str(),
+        map,
+    }
+}
+
+/// The object and `discriminator` of a scheme that is the base of an `allOf`
+/// hierarchy, i.e. which has a discriminator but isn't a union itself.
+fn hierarchy_base(
+    schema: &ObjectOrReference<ObjectSchema>,
+) -> Option<(&ObjectSchema, &Discriminator)> {
+    match schema {
+        ObjectOrReference::Object(obj) if obj.any_of.is_empty() && obj.one_of.is_empty() => {
+            Some((obj, obj.discriminator.as_ref()?))
+        }
+        _ => None,
+    }
+}
+
+/// The base scheme `name` of an `allOf` hierarchy. Its discrimination only
+/// keeps the schemes extending it; those the mapping leaves out are referenced
+/// by their bare scheme name.
+fn parse_polymorphic<'a>(
+    ctx: &ParseCtx<'a>,
+    name: &'a str,
+    base: &'a ObjectSchema,
+    discriminator: &'a Discriminator,
+    parents: &HashMap<&'a str, &'a str>,
+) -> Result<IAST<'a>, Error> {
+    let mut properties = HashMap::new();
+    collect_object_properties(ctx, base, &mut properties, &mut HashSet::from([name]))?;
+    let mut discrimination = parse_discrimination(
+        ctx,
+        discriminator,
+        parents
+            .iter()
+            .filter(|(_, parent)| **parent == name)
+            .map(|(subtype, _)| *subtype),
+    );
+    discrimination
+        .map
+        .retain(|_, r| parents.get(strip_ref_prefix(r.path)) == Some(&name));
+    Ok(IAST::Object(AnnotatedObj {
+        nullable: base.is_nullable().unwrap_or(false),
+        optional: false,
+        is_deprecated: base.deprecated.unwrap_or(false),
+        description: base.description.as_deref(),
+        title: base.title.as_deref(),
+        format: base.format.as_deref(),
+        constraints: parse_constraints(base),
+        default: base.default.as_ref(),
+        read_only: base.read_only.unwrap_or(false),
+        write_only: base.write_only.unwrap_or(false),
+        value: AlgType::Polymorphic(properties, discrimination),
+    }))
+}
+
+/// The schema of the properties an object allows beyond its fixed ones:
+/// `None` when `additionalProperties` is absent or `false`, a `Dynamic` value
+/// for `true`.
+fn parse_additional_properties<'a>(
+    ctx: &ParseCtx<'a>,
+    object: &'a ObjectSchema,
+) -> Result<Option<IAST<'a>>, Error> {
+    match &object.additional_properties {
+        None | Some(Schema::Boolean(BooleanSchema(false))) => Ok(None),
+        Some(Schema::Boolean(BooleanSchema(true))) => Ok(Some(dynamic_iast())),
+        Some(Schema::Object(schema)) => {
+            let _at = ctx.diagnostics.at(&["additionalProperties"]);
+            parse_schema(ctx, schema, false, false).map(Some)
+        }
+    }
+}
+
+/// Whether the schema at the current location has nothing but annotations,
+/// looked up in the raw document as `oas3` drops keywords it doesn't model,
+/// like `not`.
+fn accepts_any_value(ctx: &ParseCtx) -> bool {
+    const ANNOTATIONS: [&str; 11] = [
+        "title",
+        "description",
+        "default",
+        "deprecated",
+        "readOnly",
+        "writeOnly",
+        "example",
+        "examples",
+        "nullable",
+        "externalDocs",
+        "$comment",
+    ];
+    match resolve_pointer(ctx.raw, &ctx.diagnostics.pointer()) {
+        Some(serde_json::Value::Object(schema)) => schema
+            .keys()
+            .all(|key| ANNOTATIONS.contains(&key.as_str()) || key.starts_with("x-")),
+        Some(serde_json::Value::Bool(true)) => true,
+        _ => false,
+    }
+}
+
+/// An IAST accepting any JSON value.
+fn dynamic_iast<'a>() -> IAST<'a> {
+    IAST::Primitive(AnnotatedObj {
+        nullable: false,
+        optional: false,
+        is_deprecated: false,
+        description: None,
+        title: None,
+        format: None,
+        constraints: Constraints::default(),
+        default: None,
+        read_only: false,
+        write_only: false,
+        value: Primitive::Dynamic,
+    })
+}
+
+/// The name of `typ` in a schema's `type`.
+fn schema_type_name(typ: &SchemaType) -> &'static str {
+    match typ {
+        SchemaType::Boolean => "boolean",
+        SchemaType::Integer => "integer",
+        SchemaType::Number => "number",
+        SchemaType::String => "string",
+        SchemaType::Array => "array",
+        SchemaType::Object => "object",
+        SchemaType::Null => "null",
+    }
+}
+
 /// Whether an object schema permits `null` — either via the OAS 3.0
 /// `nullable: true` keyword or an OAS 3.1 type set that includes `"null"`
 /// (e.g. `{"type": ["object", "null"]}`). Used to detect the nullable-`$ref`
@@ -600,9 +1928,9 @@ fn collect_all_of_properties<'a>(
                 collect_object_properties(ctx, obj, out, visited)?;
             }
             ObjectOrReference::Ref { ref_path } => {
-                let target = strip_ref_prefix(ref_path);
+                let target = strip_ref_prefix(ctx.local_ref(ref_path));
                 if visited.insert(target) {
-                    if let Some(ObjectOrReference::Object(obj)) = ctx.schemas.get(target) {
+                    if let Some(ObjectOrReference::Object(obj)) = ctx.scheme(target) {
                         collect_object_properties(ctx, obj, out, visited)?;
                     }
                 }
@@ -635,10 +1963,11 @@ fn collect_object_properties<'a>(
     Ok(())
 }
 
-fn convert_routes_to_tree<'a>(routes: &Vec<Route>) -> RouteFragment {
+fn convert_routes_to_tree(routes: &[Route]) -> RouteFragment {
     let mut root_fragment = RouteFragmentNodeData {
         path_fragment_name: "".to_string(),
         is_param: false,
+        param: None,
         children: vec![],
     };
     let mut branchless_route_trees = Vec::new();
@@ -657,9 +1986,23 @@ fn convert_routes_to_tree<'a>(routes: &Vec<Route>) -> RouteFragment {
                 .trim_matches('{')
                 .trim_matches('}');
 
+            let param = if is_param {
+                route
+                    .path_params
+                    .iter()
+                    .position(|p| p.name == sanitized_segment)
+                    .map(|param_idx| PathParamRef {
+                        route_idx: idx,
+                        param_idx,
+                    })
+            } else {
+                None
+            };
+
             let node = RouteFragment::Node(RouteFragmentNodeData {
                 path_fragment_name: sanitized_segment.to_string(),
                 is_param,
+                param,
                 children: vec![current_node],
             });
             current_node = node;
@@ -691,6 +2034,11 @@ fn merge_branchless_tree(main_tree: &mut RouteFragmentNodeData, branchless_tree:
             }
             match matched_node {
                 Some(node) => {
+                    // not every route sharing this segment necessarily declares
+                    // the param, so keep the first declaration we come across
+                    if node.param.is_none() {
+                        node.param = branchless_node.param.take();
+                    }
                     merge_branchless_tree(node, branchless_node.children.pop().unwrap());
                 }
                 None => main_tree
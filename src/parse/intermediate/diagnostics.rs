@@ -0,0 +1,127 @@
+//! Problems found while parsing, each located by the JSON pointer of the
+//! node it is about.
+
+use std::cell::RefCell;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Something unsupported was left out on purpose.
+    Info,
+    /// Something was generated less precisely than the spec describes it,
+    /// e.g. as `dynamic`.
+    Warning,
+    /// Something could not be parsed and is missing from the output.
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Identifies the kind of problem, e.g. `list-without-items`; stable
+    /// across versions, so it can be matched on.
+    pub code: &'static str,
+    /// The JSON pointer of the node the problem is about, e.g.
+    /// `#/components/schemas/Pet/properties/tags`.
+    pub pointer: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}[{}] at {}: {}",
+            self.severity, self.code, self.pointer, self.message
+        )
+    }
+}
+
+/// Collects the [`Diagnostic`]s of a parse, keeping track of where in the
+/// document the parse currently is.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    entries: RefCell<Vec<Diagnostic>>,
+    /// The reference tokens of the current location.
+    location: RefCell<Vec<String>>,
+}
+
+impl Diagnostics {
+    /// Descends into `tokens` of the current location until the returned
+    /// guard is dropped.
+    pub fn at(&self, tokens: &[&str]) -> At<'_> {
+        let mut location = self.location.borrow_mut();
+        let depth = location.len();
+        location.extend(tokens.iter().map(|token| token.to_string()));
+        At {
+            diagnostics: self,
+            depth,
+        }
+    }
+
+    /// The JSON pointer of the current location.
+    pub fn pointer(&self) -> String {
+        to_pointer(self.location.borrow().iter().map(String::as_str))
+    }
+
+    /// Records a problem with the node at the current location.
+    pub fn report(&self, severity: Severity, code: &'static str, message: impl Into<String>) {
+        self.report_at(severity, code, self.pointer(), message);
+    }
+
+    /// Records a problem with the node at `pointer`.
+    pub fn report_at(
+        &self,
+        severity: Severity,
+        code: &'static str,
+        pointer: String,
+        message: impl Into<String>,
+    ) {
+        self.entries.borrow_mut().push(Diagnostic {
+            severity,
+            code,
+            pointer,
+            message: message.into(),
+        });
+    }
+
+    pub fn extend(&self, diagnostics: impl IntoIterator<Item = Diagnostic>) {
+        self.entries.borrow_mut().extend(diagnostics);
+    }
+
+    pub fn into_vec(self) -> Vec<Diagnostic> {
+        self.entries.into_inner()
+    }
+}
+
+/// A location entered through [`Diagnostics::at`], left again when dropped.
+pub struct At<'d> {
+    diagnostics: &'d Diagnostics,
+    depth: usize,
+}
+
+impl Drop for At<'_> {
+    fn drop(&mut self) {
+        self.diagnostics.location.borrow_mut().truncate(self.depth);
+    }
+}
+
+/// The JSON pointer, as a URI fragment, of the reference `tokens`.
+pub fn to_pointer<'t>(tokens: impl IntoIterator<Item = &'t str>) -> String {
+    let mut pointer = "#".to_string();
+    for token in tokens {
+        pointer.push('/');
+        pointer.push_str(&token.replace('~', "~0").replace('/', "~1"));
+    }
+    pointer
+}
@@ -1,60 +1,107 @@
 use crate::parse::intermediate::*;
 
+/// Signature of [`parse_params`](super::parse_params).
+pub type ParamsParser<'a> = dyn for<'b> Fn(
+    &'b ParseCtx<'a>,
+    &'a [ObjectOrReference<Parameter>],
+) -> Result<Vec<Param<'a>>, Error>;
+
+/// Signature of [`parse_request`](super::parse_request).
+pub type RequestParser<'a> = dyn for<'b> Fn(
+    &'b ParseCtx<'a>,
+    Option<&'a ObjectOrReference<RequestBody>>,
+) -> Result<Option<Content<'a>>, Error>;
+
+/// Signature of [`parse_responses`](super::parse_responses).
+pub type ResponsesParser<'a> = dyn for<'b> Fn(
+    &'b ParseCtx<'a>,
+    &'a BTreeMap<String, ObjectOrReference<Responses>>,
+) -> Result<BTreeMap<&'a String, Content<'a>>, Error>;
+
 /// `'p` is the lifetime of the borrows held by the parser itself (function
 /// pointers and the ctx reference). `'a` is the lifetime of the OpenAPI spec
 /// data that the produced IAST borrows from. They are intentionally distinct:
 /// the parser is a short-lived local value while the spec data outlives it.
 pub struct EndpointParser<'p, 'a> {
     pub ctx: &'p ParseCtx<'a>,
-    pub params_parser: &'p dyn for<'b> Fn(
-        &'b ParseCtx<'a>,
-        &'a Vec<ObjectOrReference<Parameter>>,
-    ) -> Result<Vec<Param<'a>>, Error>,
-    pub request_parser: &'p dyn for<'b> Fn(
-        &'b ParseCtx<'a>,
-        Option<&'a ObjectOrReference<RequestBody>>,
-    ) -> Result<IAST<'a>, Error>,
-    pub responses_parser: &'p dyn for<'b> Fn(
-        &'b ParseCtx<'a>,
-        &'a BTreeMap<String, ObjectOrReference<Responses>>,
-    ) -> Result<BTreeMap<&'a String, IAST<'a>>, Error>,
+    pub params_parser: &'p ParamsParser<'a>,
+    pub request_parser: &'p RequestParser<'a>,
+    pub responses_parser: &'p ResponsesParser<'a>,
 }
 
 // this could be a function
 #[macro_export]
 macro_rules! handle_endpoint {
-    ($parser:expr, $endpoints:expr, $route_part:expr, $method:expr) => {{
+    ($parser:expr, $endpoints:expr, $path:expr, $route_part:expr, $method:expr) => {{
         if let Some(endpoint) = $route_part {
             // Skip whole operations marked deprecated when the flag is on.
             let skip_deprecated =
                 $parser.ctx.args.ignore_deprecated_fields && endpoint.deprecated.unwrap_or(false);
             if !skip_deprecated {
+                let _at = $parser.ctx.diagnostics.at(&[method_key(&$method)]);
                 $endpoints.push(Endpoint {
                     method: $method,
                     description: endpoint.description.as_deref(),
                     summary: endpoint.summary.as_deref(),
-                    params: ($parser.params_parser)($parser.ctx, &endpoint.parameters)
-                        .unwrap_or_default(),
+                    params: match ($parser.params_parser)($parser.ctx, &endpoint.parameters) {
+                        Ok(params) => params,
+                        Err(e) => {
+                            $parser.ctx.diagnostics.report(
+                                Severity::Error,
+                                "unparsable-parameters",
+                                format!("{}, the parameters are left out", e),
+                            );
+                            Vec::new()
+                        }
+                    },
                     request: match ($parser.request_parser)(
                         $parser.ctx,
                         endpoint.request_body.as_ref(),
                     ) {
-                        Ok(request) => Some(request),
+                        Ok(request) => request,
                         Err(e) => {
-                            println!("error parsing request: {:?}", e);
+                            $parser.ctx.diagnostics.report(
+                                Severity::Error,
+                                "unparsable-request-body",
+                                format!("{}, the request body is left out", e),
+                            );
                             None
                         }
                     },
-                    responses: match ($parser.responses_parser)(
-                        $parser.ctx,
-                        &endpoint.responses.as_ref().unwrap(),
-                    ) {
+                    responses: match endpoint
+                        .responses
+                        .as_ref()
+                        .map_or(Ok(BTreeMap::new()), |responses| {
+                            ($parser.responses_parser)($parser.ctx, responses)
+                        }) {
                         Ok(responses) => responses,
                         Err(e) => {
-                            println!("error parsing responses: {:?}", e);
+                            $parser.ctx.diagnostics.report(
+                                Severity::Error,
+                                "unparsable-responses",
+                                format!("{}, the responses are left out", e),
+                            );
+                            BTreeMap::new()
+                        }
+                    },
+                    security: $parser.ctx.operation_security($path, &$method),
+                    response_headers: match endpoint
+                        .responses
+                        .as_ref()
+                        .map_or(Ok(BTreeMap::new()), |responses| {
+                            parse_response_headers($parser.ctx, responses)
+                        }) {
+                        Ok(headers) => headers,
+                        Err(e) => {
+                            $parser.ctx.diagnostics.report(
+                                Severity::Error,
+                                "unparsable-response-headers",
+                                format!("{}, the response headers are left out", e),
+                            );
                             BTreeMap::new()
                         }
                     },
+                    servers: parse_servers(&endpoint.servers),
                 });
             }
         }
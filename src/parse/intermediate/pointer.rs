@@ -0,0 +1,69 @@
+//! RFC 6901 JSON pointers, as found in the fragments of `$ref`s.
+
+/// The reference tokens of the JSON pointer in the fragment of the local
+/// `reference`, e.g. `["paths", "/users", "get"]` for
+/// `#/paths/~1users/get`. `None` if `reference` is not a local reference
+/// or its fragment is not a pointer.
+pub fn pointer_tokens(reference: &str) -> Option<Vec<String>> {
+    let fragment = percent_decode(reference.strip_prefix('#')?)?;
+    if fragment.is_empty() {
+        return Some(Vec::new());
+    }
+    let pointer = fragment.strip_prefix('/')?;
+    Some(
+        pointer
+            .split('/')
+            // `~1` first, so `~01` stays `~1`
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+/// The value the local `reference` points at within `document`.
+pub fn resolve_pointer<'v>(
+    document: &'v serde_json::Value,
+    reference: &str,
+) -> Option<&'v serde_json::Value> {
+    pointer_tokens(reference)?
+        .iter()
+        .try_fold(document, |value, token| match value {
+            serde_json::Value::Object(object) => object.get(token),
+            serde_json::Value::Array(array) => {
+                // array indices have no leading zeros, and `-` (the element
+                // after the last) never exists
+                let canonical = token == "0" || !token.starts_with('0');
+                let index = token.parse::<usize>().ok().filter(|_| canonical)?;
+                array.get(index)
+            }
+            _ => None,
+        })
+}
+
+/// `fragment` with its `%XX` escapes decoded, `None` if they are malformed
+/// or don't decode to UTF-8.
+fn percent_decode(fragment: &str) -> Option<String> {
+    if !fragment.contains('%') {
+        return Some(fragment.to_string());
+    }
+    let bytes = fragment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3)?;
+                if !hex.iter().all(u8::is_ascii_hexdigit) {
+                    return None;
+                }
+                let hex = std::str::from_utf8(hex).ok()?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
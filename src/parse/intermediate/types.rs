@@ -12,6 +12,24 @@ pub fn strip_ref_prefix(p: &str) -> &str {
     p.strip_prefix("#/components/schemas/").unwrap_or(p)
 }
 
+/// Whether `media_type` (e.g. `application/json; charset=utf-8` or
+/// `application/problem+json`) denotes a JSON document.
+pub fn is_json_media_type(media_type: &str) -> bool {
+    PayloadFormat::of(media_type) == PayloadFormat::Json
+}
+
+/// The media type generators use by default: the first JSON one if any,
+/// the first one otherwise.
+pub fn preferred_media_type<'c, 'a>(
+    content: &'c Content<'a>,
+) -> Option<(&'a str, &'c Payload<'a>)> {
+    content
+        .iter()
+        .find(|(media_type, _)| is_json_media_type(media_type))
+        .or_else(|| content.iter().next())
+        .map(|(media_type, payload)| (*media_type, payload))
+}
+
 /// The result of resolving a `$ref` (or an `IAST`) by transitively
 /// following references to a concrete top-level scheme.
 ///
@@ -76,6 +94,14 @@ pub struct IntermediateFormat<'a> {
     pub schemes: Vec<Scheme<'a>>,
     pub routes_tree: RouteFragment,
     pub routes: Vec<Route<'a>>,
+    pub security_schemes: Vec<SecurityScheme<'a>>,
+    /// The servers of the API, in order of preference.
+    pub servers: Vec<Server<'a>>,
+    /// The requests the API sends: its webhooks and the callbacks of its
+    /// operations.
+    pub inbound: Vec<InboundOperation<'a>>,
+    /// The problems found while parsing, in the order they were found.
+    pub diagnostics: Vec<super::Diagnostic>,
     /// `scheme name -> index into `schemes`. Built once in [`Self::new`]
     /// so look-ups by ref name are O(1) instead of O(n).
     scheme_indices: HashMap<&'a str, usize>,
@@ -86,6 +112,9 @@ impl<'a> IntermediateFormat<'a> {
         schemes: Vec<Scheme<'a>>,
         routes: Vec<Route<'a>>,
         routes_tree: RouteFragment,
+        security_schemes: Vec<SecurityScheme<'a>>,
+        servers: Vec<Server<'a>>,
+        inbound: Vec<InboundOperation<'a>>,
     ) -> Self {
         let scheme_indices = schemes
             .iter()
@@ -96,6 +125,10 @@ impl<'a> IntermediateFormat<'a> {
             schemes,
             routes_tree,
             routes,
+            security_schemes,
+            servers,
+            inbound,
+            diagnostics: Vec::new(),
             scheme_indices,
         }
     }
@@ -143,6 +176,11 @@ impl<'a> IntermediateFormat<'a> {
         }
     }
 
+    /// The path param a [`RouteFragmentNodeData`] links to.
+    pub fn path_param(&self, param_ref: &PathParamRef) -> &Param<'a> {
+        &self.routes[param_ref.route_idx].path_params[param_ref.param_idx]
+    }
+
     /// Resolve any [`IAST`] node to a [`ResolvedRef`]. For inline
     /// `Object`/`Primitive` nodes the answer is immediate; for
     /// `Reference` nodes this delegates to [`Self::resolve_ref`] and so
@@ -158,12 +196,118 @@ impl<'a> IntermediateFormat<'a> {
             IAST::Reference(r) => self.resolve_ref(r.path),
         }
     }
+
+    /// The names of the schemes containing a `readOnly` or `writeOnly`
+    /// property, either themselves or through the schemes they reference.
+    /// These differ between requests and responses.
+    pub fn schemes_with_read_write_only(&self) -> HashSet<&'a str> {
+        let mut found = HashSet::new();
+        let mut pending = Vec::new();
+        for scheme in self.schemes.iter() {
+            // subtypes get request/response variants together with their base
+            let mut referenced = scheme.parent.into_iter().collect();
+            if collect_read_write_only(&scheme.obj, &mut referenced) {
+                found.insert(scheme.name);
+            } else {
+                pending.push((scheme.name, referenced));
+            }
+        }
+        // propagate to the referencing schemes until nothing changes
+        loop {
+            let before = found.len();
+            pending.retain(|(name, referenced)| {
+                if referenced.iter().any(|r| found.contains(r)) {
+                    found.insert(*name);
+                    false
+                } else {
+                    true
+                }
+            });
+            if found.len() == before {
+                return found;
+            }
+        }
+    }
+
+    /// The [`Primitive`] an [`IAST`] stands for after transitively
+    /// following `Reference`s, or `None` for objects, cycles and unknown
+    /// references.
+    pub fn resolve_primitive<'s>(
+        &'s self,
+        iast: &'s IAST<'a>,
+    ) -> Option<&'s AnnotatedObj<'a, Primitive<'a>>> {
+        let mut current = iast;
+        let mut visited: HashSet<&'a str> = HashSet::new();
+        loop {
+            match current {
+                IAST::Object(_) => return None,
+                IAST::Primitive(p) => return Some(p),
+                IAST::Reference(r) => {
+                    if !visited.insert(strip_ref_prefix(r.path)) {
+                        return None;
+                    }
+                    current = &self.find_scheme(r.path)?.obj;
+                }
+            }
+        }
+    }
+}
+
+/// Whether `iast` has a `readOnly` or `writeOnly` property (in any nested
+/// inline schema), collecting the names of the schemes it references on the
+/// way.
+fn collect_read_write_only<'a>(iast: &IAST<'a>, referenced: &mut Vec<&'a str>) -> bool {
+    let mut found = false;
+    match iast {
+        IAST::Object(object) => match &object.value {
+            AlgType::Product(properties, additional) => {
+                for property in properties.values().chain(additional.as_deref()) {
+                    found |= property.is_read_only() || property.is_write_only();
+                    found |= collect_read_write_only(property, referenced);
+                }
+            }
+            AlgType::Sum(variants) => {
+                for variant in variants.iter() {
+                    found |= collect_read_write_only(&variant.typ, referenced);
+                }
+            }
+            AlgType::Polymorphic(properties, discrimination) => {
+                for property in properties.values() {
+                    found |= property.is_read_only() || property.is_write_only();
+                    found |= collect_read_write_only(property, referenced);
+                }
+                referenced.extend(
+                    discrimination
+                        .map
+                        .values()
+                        .map(|r| strip_ref_prefix(r.path)),
+                );
+            }
+            AlgType::DiscriminatedSum(discrimination) => {
+                referenced.extend(
+                    discrimination
+                        .map
+                        .values()
+                        .map(|r| strip_ref_prefix(r.path)),
+                );
+            }
+        },
+        IAST::Primitive(primitive) => {
+            if let Primitive::List(inner) | Primitive::Map(inner) = &primitive.value {
+                found |= collect_read_write_only(inner, referenced);
+            }
+        }
+        IAST::Reference(reference) => referenced.push(strip_ref_prefix(reference.path)),
+    }
+    found
 }
 
 pub struct Scheme<'a> {
     pub name: &'a str,
     pub is_inherently_nullable: bool,
     pub obj: IAST<'a>,
+    /// The [`AlgType::Polymorphic`] scheme this one extends through `allOf`.
+    pub parent: Option<&'a str>,
 }
 
 pub enum RouteFragment {
@@ -183,6 +327,10 @@ pub enum RouteFragment {
 pub struct RouteFragmentNodeData {
     pub path_fragment_name: String,
     pub is_param: bool,
+    /// The declaration of the path param this fragment stands for, if the
+    /// spec declares one (`None` for literal fragments and for `{params}`
+    /// no operation declares).
+    pub param: Option<PathParamRef>,
     pub children: Vec<RouteFragment>,
 }
 
@@ -196,9 +344,18 @@ pub struct RouteFragmentLeafData {
     pub route_idx: usize,
 }
 
+/// Points at `routes[route_idx].path_params[param_idx]`.
+pub struct PathParamRef {
+    pub route_idx: usize,
+    pub param_idx: usize,
+}
+
 pub struct Route<'a> {
     pub path: &'a str,
     pub description: Option<&'a str>,
+    /// The path params of this route, shared by all of its endpoints (whose
+    /// `params` therefore only hold query/header/cookie params).
+    pub path_params: Vec<Param<'a>>,
     pub endpoints: Vec<Endpoint<'a>>,
 }
 
@@ -207,16 +364,223 @@ pub struct Endpoint<'a> {
     pub description: Option<&'a str>,
     pub summary: Option<&'a str>,
     pub params: Vec<Param<'a>>,
-    pub request: Option<IAST<'a>>,
-    pub responses: BTreeMap<&'a String, IAST<'a>>,
+    pub request: Option<Content<'a>>,
+    /// Keyed by status code (or `default`). Codes without any typed content
+    /// are left out.
+    pub responses: BTreeMap<&'a String, Content<'a>>,
+    /// The alternative ways of authenticating a request, the document's
+    /// unless the operation has its own. Empty for public endpoints.
+    pub security: Vec<SecurityRequirement>,
+    /// The headers each response declares, keyed by status code (or
+    /// `default`). Codes without any declared header are left out.
+    pub response_headers: BTreeMap<&'a String, Vec<ResponseHeader<'a>>>,
+    /// The servers of the operation (or else of its path) when they replace
+    /// the API's, empty otherwise.
+    pub servers: Vec<Server<'a>>,
+}
+
+/// A request the API sends to its clients.
+pub struct InboundOperation<'a> {
+    /// The name of the webhook or callback.
+    pub name: &'a str,
+    pub origin: InboundOrigin<'a>,
+    /// The request and the responses expected to it. Its security
+    /// requirements are always empty.
+    pub endpoint: Endpoint<'a>,
+}
+
+#[derive(Clone, Copy)]
+pub enum InboundOrigin<'a> {
+    /// A webhook of the document's `webhooks`.
+    Webhook,
+    /// A callback of the operation `method` of `path`, sent to the URL the
+    /// runtime `expression` evaluates to.
+    Callback {
+        path: &'a str,
+        method: Method,
+        expression: &'a str,
+    },
+}
+
+/// A header of a response, e.g. `X-Total-Count` or `ETag`.
+pub struct ResponseHeader<'a> {
+    pub name: &'a str,
+    pub description: Option<&'a str>,
+    pub required: bool,
+    pub schema: IAST<'a>,
+}
+
+/// A server the API is served from.
+pub struct Server<'a> {
+    /// The URL, which may be relative and has a `{name}` placeholder per
+    /// variable.
+    pub url: &'a str,
+    pub description: Option<&'a str>,
+    pub variables: Vec<ServerVariable<'a>>,
+}
+
+/// A variable of a [`Server`] URL.
+pub struct ServerVariable<'a> {
+    pub name: &'a str,
+    pub default: &'a str,
+    /// The values it may take, empty if it may take any.
+    pub values: Vec<&'a str>,
+    pub description: Option<&'a str>,
+}
+
+/// The security schemes (by name) which together authenticate a request,
+/// with the OAuth2 scopes each needs. An empty one makes authentication
+/// optional.
+pub type SecurityRequirement = BTreeMap<String, Vec<String>>;
+
+/// A scheme of `components.securitySchemes`.
+pub struct SecurityScheme<'a> {
+    pub name: &'a str,
+    pub description: Option<&'a str>,
+    pub kind: SecuritySchemeKind<'a>,
+}
+
+pub enum SecuritySchemeKind<'a> {
+    /// A key sent as the header, query parameter or cookie `name`.
+    ApiKey {
+        name: &'a str,
+        location: ParamLocation,
+    },
+    /// HTTP authentication of the given `Authorization` scheme, e.g. `basic`
+    /// or `bearer`.
+    Http {
+        scheme: &'a str,
+        bearer_format: Option<&'a str>,
+    },
+    /// An OAuth2 access token, with the scopes its flows offer.
+    OAuth2 { scopes: BTreeMap<&'a str, &'a str> },
+    /// An access token of the OpenID Connect provider discovered at `url`.
+    OpenIdConnect { url: &'a str },
+}
+
+/// The typed `content` of a request body or response, keyed by media type
+/// (e.g. `application/json`). Media types without a schema are left out.
+pub type Content<'a> = BTreeMap<&'a str, Payload<'a>>;
+
+/// A request body or response in one media type.
+pub struct Payload<'a> {
+    pub schema: IAST<'a>,
+    pub format: PayloadFormat,
+    /// The `encoding` of the properties of a form body, by property name.
+    pub encoding: BTreeMap<&'a str, PropertyEncoding<'a>>,
+}
+
+/// How a [`Payload`] goes over the wire, derived from its media type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadFormat {
+    /// `application/json` and `+json` media types.
+    Json,
+    /// `multipart/form-data`: one part per property, files sent as is.
+    MultipartForm,
+    /// `application/x-www-form-urlencoded`: properties serialized like query
+    /// params, after their [`PropertyEncoding`].
+    UrlEncodedForm,
+    Other,
+}
+
+impl PayloadFormat {
+    pub fn of(media_type: &str) -> Self {
+        let essence = media_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        if essence == "application/json" || essence.ends_with("+json") {
+            PayloadFormat::Json
+        } else if essence == "multipart/form-data" {
+            PayloadFormat::MultipartForm
+        } else if essence == "application/x-www-form-urlencoded" {
+            PayloadFormat::UrlEncodedForm
+        } else {
+            PayloadFormat::Other
+        }
+    }
+}
+
+/// The `encoding` object of a single form property.
+pub struct PropertyEncoding<'a> {
+    /// The `Content-Type` of the property's part, e.g. `image/png`.
+    pub content_type: Option<&'a str>,
+    /// How arrays and objects are serialized in urlencoded forms.
+    pub style: FormStyle,
+    /// Whether array elements and object properties of urlencoded forms are
+    /// separate fields. Defaults to `true` for [`FormStyle::Form`] only.
+    pub explode: bool,
+}
+
+impl Default for PropertyEncoding<'_> {
+    fn default() -> Self {
+        Self {
+            content_type: None,
+            style: FormStyle::Form,
+            explode: true,
+        }
+    }
+}
+
+/// The `style` of a [`PropertyEncoding`], as for query params.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormStyle {
+    /// `a=1&a=2` exploded, `a=1,2` otherwise.
+    Form,
+    /// `a=1%202`
+    SpaceDelimited,
+    /// `a=1|2`
+    PipeDelimited,
+    /// `a[x]=1&a[y]=2`, objects only.
+    DeepObject,
 }
 
 pub struct Param<'a> {
     pub name: &'a str,
+    pub location: ParamLocation,
     pub description: Option<&'a str>,
     pub required: bool,
+    /// The parsed `schema` of the parameter, used by the generators to type
+    /// the value and to decide how it is rendered into the request.
+    pub schema: IAST<'a>,
+    /// How array and object values are serialized.
+    pub style: ParamStyle,
+    /// Whether the elements of array values (and the properties of object
+    /// values) are separate values of the param.
+    pub explode: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamLocation {
+    Path,
+    Query,
+    Header,
+    Cookie,
+}
+
+/// The `style` of a [`Param`]. Defaults to `form` for query and cookie
+/// params, and to `simple` for path and header ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamStyle {
+    /// `;a=1,2`
+    Matrix,
+    /// `.1.2`
+    Label,
+    /// `a=1&a=2` exploded, `a=1,2` otherwise.
+    Form,
+    /// `1,2`
+    Simple,
+    /// `a=1%202`
+    SpaceDelimited,
+    /// `a=1|2`
+    PipeDelimited,
+    /// `a[x]=1&a[y]=2`, objects only.
+    DeepObject,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Method {
     Get,
     Post,
@@ -234,15 +598,55 @@ pub struct AnnotatedObj<'a, T> {
     pub is_deprecated: bool,
     pub description: Option<&'a str>,
     pub title: Option<&'a str>,
+    /// The raw `format` of the schema (e.g. `uuid` or `int64`). Formats
+    /// changing the representation of a value are parsed into their own
+    /// [`Primitive`]s already, this is kept for documentation.
+    pub format: Option<&'a str>,
+    /// Validation keywords of the schema.
+    pub constraints: Constraints<'a>,
+    /// The `default` of the schema, as written in the spec.
+    pub default: Option<&'a serde_json::Value>,
+    /// `readOnly`: only ever sent by the server.
+    pub read_only: bool,
+    /// `writeOnly`: only ever sent by the client.
+    pub write_only: bool,
     pub value: T,
 }
 
+/// The validation keywords of a schema. Numbers are kept as written in the
+/// spec.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Constraints<'a> {
+    pub minimum: Option<serde_json::Number>,
+    pub exclusive_minimum: Option<serde_json::Number>,
+    pub maximum: Option<serde_json::Number>,
+    pub exclusive_maximum: Option<serde_json::Number>,
+    pub multiple_of: Option<serde_json::Number>,
+    pub min_length: Option<u64>,
+    pub max_length: Option<u64>,
+    pub pattern: Option<&'a str>,
+    pub min_items: Option<u64>,
+    pub max_items: Option<u64>,
+    pub unique_items: bool,
+}
+
+impl Constraints<'_> {
+    /// Whether the schema has no validation keyword at all.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct AnnotatedReference<'a> {
     pub path: &'a str,
     pub optional: bool,
     pub nullable: bool,
     pub is_deprecated: bool,
+    /// `readOnly` of an `allOf`-wrapped reference.
+    pub read_only: bool,
+    /// `writeOnly` of an `allOf`-wrapped reference.
+    pub write_only: bool,
 }
 
 /// Intermediate Abstract Syntax Tree
@@ -255,13 +659,38 @@ pub enum IAST<'a> {
     Primitive(AnnotatedObj<'a, Primitive<'a>>),
 }
 
+impl IAST<'_> {
+    /// Whether the schema is `readOnly`.
+    pub fn is_read_only(&self) -> bool {
+        match self {
+            IAST::Object(o) => o.read_only,
+            IAST::Primitive(p) => p.read_only,
+            IAST::Reference(r) => r.read_only,
+        }
+    }
+
+    /// Whether the schema is `writeOnly`.
+    pub fn is_write_only(&self) -> bool {
+        match self {
+            IAST::Object(o) => o.write_only,
+            IAST::Primitive(p) => p.write_only,
+            IAST::Reference(r) => r.write_only,
+        }
+    }
+}
+
 /// Algebraic Type
 #[derive(Debug, PartialEq, Eq)]
 pub enum AlgType<'a> {
     Sum(Vec<SumVariant<'a>>),
     // basically the same as a sum type, but can only have references
     DiscriminatedSum(Discrimination<'a>),
-    Product(HashMap<&'a str, IAST<'a>>),
+    /// The fixed properties, and the schema of any further (`additionalProperties`)
+    /// ones if they are allowed.
+    Product(HashMap<&'a str, IAST<'a>>, Option<Box<IAST<'a>>>),
+    /// The base of an `allOf` hierarchy: the properties its subtypes share, and
+    /// the discriminator choosing among the schemes extending it.
+    Polymorphic(HashMap<&'a str, IAST<'a>>, Discrimination<'a>),
 }
 
 /// A single variant of a [`AlgType::Sum`] union: the variant `name`
@@ -290,10 +719,20 @@ pub enum Primitive<'a> {
     Boolean,
     Never,
     List(Box<IAST<'a>>),
+    /// An object without fixed properties, mapping arbitrary keys to values
+    /// of the given (`additionalProperties`) schema.
     Map(Box<IAST<'a>>),
     Enum(Vec<EnumValue>),
     Dynamic,
     Binary,
+    /// A string of `format: date-time`, an ISO-8601 timestamp.
+    DateTime,
+    /// A string of `format: date`, an ISO-8601 calendar date.
+    Date,
+    /// A string of `format: uri` (or `uri-reference`).
+    Uri,
+    /// A string of `format: byte`, base64 encoded bytes.
+    Base64,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -1 +1,92 @@
+pub mod bundle;
 pub mod intermediate;
+pub mod source;
+pub mod swagger2;
+
+/// The formats a spec can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecFormat {
+    Json,
+    Yaml,
+}
+
+impl SpecFormat {
+    /// The format of a `Content-Type`, if it names one.
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let media_type = content_type.split(';').next()?.trim().to_ascii_lowercase();
+        if media_type.ends_with("/json") || media_type.ends_with("+json") {
+            Some(Self::Json)
+        } else if media_type.ends_with("yaml") || media_type.ends_with("yml") {
+            Some(Self::Yaml)
+        } else {
+            None
+        }
+    }
+
+    /// The format of a path or URL by its extension, if it has a known one.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let path = path.split(['?', '#']).next()?;
+        let (_, extension) = path.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    /// The format of `text` by its content: JSON documents start with an
+    /// object, anything else is taken to be YAML (of which JSON is a
+    /// subset anyway).
+    pub fn sniff(text: &str) -> Self {
+        match text
+            .trim_start_matches('\u{feff}')
+            .trim_start()
+            .starts_with('{')
+        {
+            true => Self::Json,
+            false => Self::Yaml,
+        }
+    }
+}
+
+/// Reads the spec `text`, sniffing whether it is JSON or YAML, into the
+/// typed document and the raw JSON it was read from, see [`read_spec_as`].
+pub fn read_spec(text: &str) -> Result<(oas3::Spec, serde_json::Value), String> {
+    read_spec_as(text, SpecFormat::sniff(text))
+}
+
+/// Reads the spec `text` written in `format` into the typed document and
+/// the raw JSON it was read from, converting Swagger 2.0 documents to
+/// OpenAPI 3 on the way.
+pub fn read_spec_as(
+    text: &str,
+    format: SpecFormat,
+) -> Result<(oas3::Spec, serde_json::Value), String> {
+    read_spec_value(read_document(text, format)?)
+}
+
+/// Reads the `raw` JSON of a spec into the typed document and the raw JSON
+/// of the OpenAPI 3 document, converting Swagger 2.0 documents.
+pub fn read_spec_value(
+    mut raw: serde_json::Value,
+) -> Result<(oas3::Spec, serde_json::Value), String> {
+    if swagger2::is_swagger2(&raw) {
+        raw =
+            swagger2::to_openapi3(&raw).map_err(|e| format!("convert swagger 2.0 spec: {}", e))?;
+    }
+    let spec = serde_json::from_value(raw.clone()).map_err(|e| format!("parse spec: {:?}", e))?;
+    Ok((spec, raw))
+}
+
+/// Reads the document `text` written in `format` into plain JSON.
+pub fn read_document(text: &str, format: SpecFormat) -> Result<serde_json::Value, String> {
+    // both report the line and column of syntax errors in their message
+    match format {
+        SpecFormat::Json => {
+            serde_json::from_str(text).map_err(|e| format!("parse spec (json): {}", e))
+        }
+        SpecFormat::Yaml => {
+            serde_yaml::from_str(text).map_err(|e| format!("parse spec (yaml): {}", e))
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Where specs are loaded from: HTTP(S) URLs, local files and stdin.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use super::SpecFormat;
+
+/// A place a spec can be loaded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpecSource {
+    /// An `http://` or `https://` URL.
+    Url(String),
+    /// A local file, given as a path or a `file://` URL.
+    File(PathBuf),
+    /// The standard input, given as `-`.
+    Stdin,
+}
+
+impl SpecSource {
+    /// The source `location` refers to: `-` for stdin, a `file://` URL or
+    /// an HTTP(S) URL, and a plain path otherwise.
+    pub fn parse(location: &str) -> Result<Self, String> {
+        if location == "-" {
+            return Ok(Self::Stdin);
+        }
+        match reqwest::Url::parse(location) {
+            Ok(url) if url.scheme() == "file" => url
+                .to_file_path()
+                .map(Self::File)
+                .map_err(|()| format!("not a local file: {}", location)),
+            Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(Self::Url(location.into())),
+            // anything else, including windows drive letters parsed as
+            // schemes, is a path
+            _ => Ok(Self::File(PathBuf::from(location))),
+        }
+    }
+
+    /// The source `reference` refers to relative to this one, e.g. the
+    /// document part of a `$ref`.
+    pub fn join(&self, reference: &str) -> Result<Self, String> {
+        let absolute = reqwest::Url::parse(reference)
+            .is_ok_and(|url| matches!(url.scheme(), "http" | "https" | "file"));
+        if absolute {
+            return match (self, Self::parse(reference)?) {
+                // a remote spec must not read local files into the output
+                (SpecSource::Url(url), SpecSource::File(_)) => Err(format!(
+                    "{} may not reference the local file {}",
+                    url, reference
+                )),
+                (_, joined) => Ok(joined),
+            };
+        }
+        match self {
+            SpecSource::Url(url) => reqwest::Url::parse(url)
+                .and_then(|url| url.join(reference))
+                .map(|url| Self::Url(url.into()))
+                .map_err(|e| format!("resolve {} against {}: {}", reference, url, e)),
+            SpecSource::File(path) => {
+                let joined = path.parent().unwrap_or(Path::new("")).join(reference);
+                // `..`s resolved, so that the same file is the same source
+                Ok(Self::File(std::fs::canonicalize(&joined).unwrap_or(joined)))
+            }
+            // stdin has no location, so the current directory stands in
+            SpecSource::Stdin => {
+                let joined = std::env::current_dir()
+                    .map_err(|e| format!("resolve {} against <stdin>: {}", reference, e))?
+                    .join(reference);
+                Ok(Self::File(std::fs::canonicalize(&joined).unwrap_or(joined)))
+            }
+        }
+    }
+
+    /// Whether both sources are URLs of the same scheme, host and port.
+    pub fn same_origin(&self, other: &SpecSource) -> bool {
+        let origin = |source: &SpecSource| match source {
+            SpecSource::Url(url) => reqwest::Url::parse(url).ok().map(|url| url.origin()),
+            _ => None,
+        };
+        match (origin(self), origin(other)) {
+            (Some(origin), Some(other)) => origin.is_tuple() && origin == other,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for SpecSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpecSource::Url(url) => write!(f, "{}", url),
+            SpecSource::File(path) => write!(f, "{}", path.display()),
+            SpecSource::Stdin => write!(f, "<stdin>"),
+        }
+    }
+}
+
+/// The text of a spec, as loaded by a [`SpecLoader`].
+#[derive(Debug, Clone)]
+pub struct LoadedSpec {
+    pub text: String,
+    /// The format told by the response's content type or the source's
+    /// extension, if any.
+    pub format: Option<SpecFormat>,
+}
+
+impl LoadedSpec {
+    /// The format of the spec: the one it was loaded with, else the
+    /// sniffed one.
+    pub fn format(&self) -> SpecFormat {
+        self.format.unwrap_or_else(|| SpecFormat::sniff(&self.text))
+    }
+
+    /// Reads the document into plain JSON, see
+    /// [`read_document`](super::read_document).
+    pub fn document(&self) -> Result<serde_json::Value, String> {
+        super::read_document(&self.text, self.format())
+    }
+
+    /// Reads the spec, see [`read_spec_as`](super::read_spec_as).
+    pub fn read(&self) -> Result<(oas3::Spec, serde_json::Value), String> {
+        super::read_spec_as(&self.text, self.format())
+    }
+}
+
+/// Loads specs from [`SpecSource`]s.
+#[derive(Debug, Clone)]
+pub struct SpecLoader {
+    /// The user name and password to authenticate HTTP requests with.
+    pub basic_auth: Option<(String, Option<String>)>,
+    /// Shared by the loaders scoped from this one.
+    client: reqwest::Client,
+}
+
+impl Default for SpecLoader {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl SpecLoader {
+    /// A loader authenticating its HTTP requests with `basic_auth`.
+    pub fn new(basic_auth: Option<(String, Option<String>)>) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(concat!(
+                env!("CARGO_PKG_NAME"),
+                "/",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .build()
+            .expect("the HTTP client builds");
+        Self { basic_auth, client }
+    }
+
+    /// The loader for `source`, referenced by the spec at `root`: without
+    /// credentials unless both share the origin, so that they are not sent
+    /// to whichever host a `$ref` names.
+    pub fn scoped_to(&self, root: &SpecSource, source: &SpecSource) -> Self {
+        Self {
+            basic_auth: self.basic_auth.clone().filter(|_| root.same_origin(source)),
+            client: self.client.clone(),
+        }
+    }
+
+    pub async fn load(&self, source: &SpecSource) -> Result<LoadedSpec, String> {
+        match source {
+            SpecSource::Url(url) => self.fetch(url).await.map_err(|e| format!("{:?}", e)),
+            SpecSource::File(path) => {
+                let text = std::fs::read_to_string(path)
+                    .map_err(|e| format!("read {}: {}", path.display(), e))?;
+                Ok(LoadedSpec {
+                    text,
+                    format: SpecFormat::from_path(&path.to_string_lossy()),
+                })
+            }
+            SpecSource::Stdin => {
+                let mut text = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut text)
+                    .map_err(|e| format!("read stdin: {}", e))?;
+                Ok(LoadedSpec { text, format: None })
+            }
+        }
+    }
+
+    async fn fetch(&self, url: &str) -> Result<LoadedSpec, reqwest::Error> {
+        let request = self.client.get(url);
+        let response = match &self.basic_auth {
+            Some((username, password)) => request.basic_auth(username, password.as_ref()),
+            None => request,
+        }
+        .send()
+        .await?
+        // an error page is no spec
+        .error_for_status()?;
+        let format = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .and_then(SpecFormat::from_content_type)
+            .or_else(|| SpecFormat::from_path(url));
+        let text = response.text().await?;
+        Ok(LoadedSpec { text, format })
+    }
+}
@@ -0,0 +1,559 @@
+//! Swagger 2.0 front end.
+//!
+//! Swagger 2.0 documents are converted into the OpenAPI 3 document they
+//! describe, which then goes through `oas3` and
+//! [`intermediate`](super::intermediate) like any other spec, so both
+//! versions end up in the same `IntermediateFormat`.
+
+use serde_json::{json, Map, Value};
+
+/// True iff `document` is a Swagger 2.0 document.
+pub fn is_swagger2(document: &Value) -> bool {
+    document
+        .get("swagger")
+        .and_then(Value::as_str)
+        .is_some_and(|version| version.starts_with('2'))
+}
+
+/// Converts the Swagger 2.0 `document` into an OpenAPI 3.0 document:
+///
+/// - `definitions`, `parameters`, `responses` and `securityDefinitions`
+///   become the `components` of the same names,
+/// - `in: body` and `formData` parameters become request bodies, in the
+///   media types the operation `consumes`,
+/// - response schemas become content in the media types it `produces`,
+/// - `host`, `basePath` and `schemes` become `servers`.
+pub fn to_openapi3(document: &Value) -> Result<Value, String> {
+    let document = document
+        .as_object()
+        .ok_or("a Swagger 2.0 document must be an object")?;
+    let converter = Converter {
+        parameters: object(document, "parameters"),
+        consumes: strings(document.get("consumes")),
+        produces: strings(document.get("produces")),
+    };
+
+    let mut out = Map::new();
+    out.insert("openapi".to_string(), json!("3.0.3"));
+    for key in ["info", "tags", "externalDocs", "security"] {
+        if let Some(value) = document.get(key) {
+            out.insert(key.to_string(), value.clone());
+        }
+    }
+    out.insert("servers".to_string(), servers(document));
+
+    let mut paths = Map::new();
+    for (path, item) in object(document, "paths") {
+        paths.insert(path.clone(), converter.path_item(item));
+    }
+    out.insert("paths".to_string(), Value::Object(paths));
+
+    let mut components = Map::new();
+    components.insert(
+        "schemas".to_string(),
+        Value::Object(
+            object(document, "definitions")
+                .iter()
+                .map(|(name, schema)| (name.clone(), schema_of(schema)))
+                .collect(),
+        ),
+    );
+    let (bodies, parameters): (Vec<_>, Vec<_>) = converter
+        .parameters
+        .iter()
+        .partition(|(_, parameter)| is_body(parameter));
+    components.insert(
+        "parameters".to_string(),
+        Value::Object(
+            parameters
+                .into_iter()
+                .filter(|(_, parameter)| !is_form(parameter))
+                .map(|(name, parameter)| (name.clone(), parameter_of(parameter)))
+                .collect(),
+        ),
+    );
+    components.insert(
+        "requestBodies".to_string(),
+        Value::Object(
+            bodies
+                .into_iter()
+                .map(|(name, parameter)| {
+                    (
+                        name.clone(),
+                        converter.body_of(parameter, &converter.consumes),
+                    )
+                })
+                .collect(),
+        ),
+    );
+    components.insert(
+        "responses".to_string(),
+        Value::Object(
+            object(document, "responses")
+                .iter()
+                .map(|(name, response)| {
+                    (
+                        name.clone(),
+                        converter.response_of(response, &converter.produces),
+                    )
+                })
+                .collect(),
+        ),
+    );
+    components.insert(
+        "securitySchemes".to_string(),
+        Value::Object(
+            object(document, "securityDefinitions")
+                .iter()
+                .map(|(name, scheme)| (name.clone(), security_scheme_of(scheme)))
+                .collect(),
+        ),
+    );
+    out.insert("components".to_string(), Value::Object(components));
+    Ok(Value::Object(out))
+}
+
+/// The document-wide defaults operations fall back to.
+struct Converter<'s> {
+    /// The document's shared `parameters`.
+    parameters: &'s Map<String, Value>,
+    consumes: Vec<&'s str>,
+    produces: Vec<&'s str>,
+}
+
+impl<'s> Converter<'s> {
+    fn path_item(&self, item: &'s Value) -> Value {
+        let Some(item) = item.as_object() else {
+            return item.clone();
+        };
+        let shared = array(item.get("parameters"));
+        let mut out = Map::new();
+        for (key, value) in item {
+            match key.as_str() {
+                "get" | "put" | "post" | "delete" | "options" | "head" | "patch" => {
+                    out.insert(key.clone(), self.operation(value, &shared));
+                }
+                // body and form parameters of the path go to its operations
+                "parameters" => {
+                    let parameters = self.parameters_of(&shared);
+                    out.insert(key.clone(), Value::Array(parameters));
+                }
+                "$ref" => {
+                    out.insert(key.clone(), value.clone());
+                }
+                _ if key.starts_with("x-") => {
+                    out.insert(key.clone(), value.clone());
+                }
+                _ => {}
+            }
+        }
+        Value::Object(out)
+    }
+
+    /// Converts an operation, which inherits the body and form parameters of
+    /// its path item from `shared`.
+    fn operation(&self, operation: &'s Value, shared: &[&'s Value]) -> Value {
+        let Some(operation) = operation.as_object() else {
+            return operation.clone();
+        };
+        let consumes = match operation.get("consumes") {
+            Some(consumes) => strings(Some(consumes)),
+            None => self.consumes.clone(),
+        };
+        let produces = match operation.get("produces") {
+            Some(produces) => strings(Some(produces)),
+            None => self.produces.clone(),
+        };
+        let own = array(operation.get("parameters"));
+        let mut out = Map::new();
+        for (key, value) in operation {
+            match key.as_str() {
+                "consumes" | "produces" | "schemes" | "parameters" => {}
+                "responses" => {
+                    let responses = value
+                        .as_object()
+                        .into_iter()
+                        .flatten()
+                        .map(|(code, response)| {
+                            (code.clone(), self.response_of(response, &produces))
+                        })
+                        .collect();
+                    out.insert(key.clone(), Value::Object(responses));
+                }
+                _ => {
+                    out.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        out.insert(
+            "parameters".to_string(),
+            Value::Array(self.parameters_of(&own)),
+        );
+
+        // the operation's own body and form parameters replace those of its
+        // path of the same name and location
+        let key = |parameter: &'s Value| {
+            let parameter = self.resolve(parameter);
+            (parameter.get("name"), parameter.get("in"))
+        };
+        let mut inherited: Vec<&Value> = shared
+            .iter()
+            .copied()
+            .filter(|shared| !own.iter().any(|own| key(own) == key(shared)))
+            .collect();
+        inherited.extend(own);
+        if let Some(body) = inherited
+            .iter()
+            .find(|parameter| is_body(self.resolve(parameter)))
+        {
+            let body = match body.get("$ref").and_then(Value::as_str) {
+                // the shared request body is in the document's media types
+                Some(reference) if !operation.contains_key("consumes") => {
+                    json!({ "$ref": reference.replace("#/parameters/", "#/components/requestBodies/") })
+                }
+                _ => self.body_of(self.resolve(body), &consumes),
+            };
+            out.insert("requestBody".to_string(), body);
+        } else {
+            let form: Vec<&Value> = inherited
+                .iter()
+                .map(|parameter| self.resolve(parameter))
+                .filter(|parameter| is_form(parameter))
+                .collect();
+            if !form.is_empty() {
+                out.insert("requestBody".to_string(), form_body_of(&form, &consumes));
+            }
+        }
+        Value::Object(out)
+    }
+
+    /// The parameters of `parameters` that stay parameters, i.e. neither
+    /// body nor form ones.
+    fn parameters_of(&self, parameters: &[&'s Value]) -> Vec<Value> {
+        parameters
+            .iter()
+            .filter(|parameter| {
+                let parameter = self.resolve(parameter);
+                !is_body(parameter) && !is_form(parameter)
+            })
+            .map(|parameter| match parameter.get("$ref").and_then(Value::as_str) {
+                Some(reference) => json!({ "$ref": reference.replace("#/parameters/", "#/components/parameters/") }),
+                None => parameter_of(parameter),
+            })
+            .collect()
+    }
+
+    /// `parameter`, following a reference into the shared parameters.
+    fn resolve(&self, parameter: &'s Value) -> &'s Value {
+        parameter
+            .get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|reference| reference.strip_prefix("#/parameters/"))
+            .and_then(|name| self.parameters.get(name))
+            .unwrap_or(parameter)
+    }
+
+    /// The request body of the `in: body` parameter `parameter`.
+    fn body_of(&self, parameter: &Value, consumes: &[&str]) -> Value {
+        let schema = schema_of(parameter.get("schema").unwrap_or(&json!({})));
+        let mut body = Map::new();
+        if let Some(description) = parameter.get("description") {
+            body.insert("description".to_string(), description.clone());
+        }
+        body.insert(
+            "required".to_string(),
+            parameter.get("required").cloned().unwrap_or(json!(false)),
+        );
+        body.insert("content".to_string(), content_of(&schema, consumes));
+        Value::Object(body)
+    }
+
+    fn response_of(&self, response: &Value, produces: &[&str]) -> Value {
+        if let Some(reference) = response.get("$ref").and_then(Value::as_str) {
+            return json!({ "$ref": reference.replace("#/responses/", "#/components/responses/") });
+        }
+        let mut out = Map::new();
+        out.insert(
+            "description".to_string(),
+            response.get("description").cloned().unwrap_or(json!("")),
+        );
+        if let Some(schema) = response.get("schema") {
+            out.insert(
+                "content".to_string(),
+                content_of(&schema_of(schema), produces),
+            );
+        }
+        if let Some(headers) = response.get("headers").and_then(Value::as_object) {
+            let headers = headers
+                .iter()
+                .map(|(name, header)| {
+                    let mut out = Map::new();
+                    if let Some(description) = header.get("description") {
+                        out.insert("description".to_string(), description.clone());
+                    }
+                    out.insert("schema".to_string(), simple_schema_of(header));
+                    (name.clone(), Value::Object(out))
+                })
+                .collect();
+            out.insert("headers".to_string(), Value::Object(headers));
+        }
+        Value::Object(out)
+    }
+}
+
+/// The `servers` of `host`, `basePath` and `schemes`.
+fn servers(document: &Map<String, Value>) -> Value {
+    let base_path = document
+        .get("basePath")
+        .and_then(Value::as_str)
+        .unwrap_or("");
+    let Some(host) = document.get("host").and_then(Value::as_str) else {
+        return match base_path {
+            "" => json!([]),
+            base_path => json!([{ "url": base_path }]),
+        };
+    };
+    let schemes = match strings(document.get("schemes")) {
+        schemes if schemes.is_empty() => vec!["https"],
+        schemes => schemes,
+    };
+    Value::Array(
+        schemes
+            .into_iter()
+            .map(|scheme| json!({ "url": format!("{}://{}{}", scheme, host, base_path) }))
+            .collect(),
+    )
+}
+
+/// A (non-body, non-form) parameter, whose type is described by `type`,
+/// `format`, `items`, ... right on it rather than by a `schema`.
+fn parameter_of(parameter: &Value) -> Value {
+    let mut out = Map::new();
+    for key in ["name", "in", "description", "required", "deprecated"] {
+        if let Some(value) = parameter.get(key) {
+            out.insert(key.to_string(), value.clone());
+        }
+    }
+    if parameter.get("allowEmptyValue").is_some() {
+        out.insert(
+            "allowEmptyValue".to_string(),
+            parameter["allowEmptyValue"].clone(),
+        );
+    }
+    out.insert("schema".to_string(), simple_schema_of(parameter));
+    // `collectionFormat` becomes the style of the array
+    match parameter.get("collectionFormat").and_then(Value::as_str) {
+        Some("multi") => {
+            out.insert("style".to_string(), json!("form"));
+            out.insert("explode".to_string(), json!(true));
+        }
+        Some("ssv") => {
+            out.insert("style".to_string(), json!("spaceDelimited"));
+        }
+        Some("pipes") => {
+            out.insert("style".to_string(), json!("pipeDelimited"));
+        }
+        // has no style, so it is kept for the parser to report
+        Some("tsv") => {
+            out.insert("explode".to_string(), json!(false));
+            out.insert("x-collection-format".to_string(), json!("tsv"));
+        }
+        // `csv` is the default
+        _ if parameter.get("type").and_then(Value::as_str) == Some("array") => {
+            if parameter.get("in").and_then(Value::as_str) == Some("query") {
+                out.insert("style".to_string(), json!("form"));
+            }
+            out.insert("explode".to_string(), json!(false));
+        }
+        _ => {}
+    }
+    Value::Object(out)
+}
+
+/// The request body of the `formData` parameters `form`: an object of
+/// them, as `multipart/form-data` if they include a file or the operation
+/// consumes it, as `application/x-www-form-urlencoded` otherwise.
+fn form_body_of(form: &[&Value], consumes: &[&str]) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for parameter in form {
+        let Some(name) = parameter.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        let mut schema = simple_schema_of(parameter);
+        if let (Some(schema), Some(description)) =
+            (schema.as_object_mut(), parameter.get("description"))
+        {
+            schema.insert("description".to_string(), description.clone());
+        }
+        properties.insert(name.to_string(), schema);
+        if parameter.get("required") == Some(&json!(true)) {
+            required.push(json!(name));
+        }
+    }
+    let has_file = form
+        .iter()
+        .any(|parameter| parameter.get("type").and_then(Value::as_str) == Some("file"));
+    let media_types: Vec<&str> = consumes
+        .iter()
+        .copied()
+        .filter(|media_type| {
+            media_type.starts_with("multipart/form-data")
+                || (!has_file && media_type.starts_with("application/x-www-form-urlencoded"))
+        })
+        .collect();
+    let media_types = match media_types.is_empty() {
+        true if has_file => vec!["multipart/form-data"],
+        true => vec!["application/x-www-form-urlencoded"],
+        false => media_types,
+    };
+    let mut schema = json!({ "type": "object", "properties": properties });
+    if !required.is_empty() {
+        schema["required"] = Value::Array(required);
+    }
+    json!({ "content": content_of(&schema, &media_types) })
+}
+
+/// The `content` of `schema` in each of `media_types`, JSON if there are
+/// none.
+fn content_of(schema: &Value, media_types: &[&str]) -> Value {
+    let media_types = match media_types.is_empty() {
+        true => &["application/json"][..],
+        false => media_types,
+    };
+    Value::Object(
+        media_types
+            .iter()
+            .map(|media_type| (media_type.to_string(), json!({ "schema": schema })))
+            .collect(),
+    )
+}
+
+/// The schema of a parameter, header or `items` described by `type`,
+/// `format`, `items`, ... right on it.
+fn simple_schema_of(simple: &Value) -> Value {
+    let mut schema = Map::new();
+    for (key, value) in simple.as_object().into_iter().flatten() {
+        match key.as_str() {
+            "type" if value == "file" => {
+                schema.insert("type".to_string(), json!("string"));
+                schema.insert("format".to_string(), json!("binary"));
+            }
+            "items" => {
+                schema.insert(key.clone(), simple_schema_of(value));
+            }
+            "type" | "format" | "default" | "maximum" | "exclusiveMaximum" | "minimum"
+            | "exclusiveMinimum" | "maxLength" | "minLength" | "pattern" | "maxItems"
+            | "minItems" | "uniqueItems" | "enum" | "multipleOf" => {
+                schema.insert(key.clone(), value.clone());
+            }
+            // a `schema` within `items` is a proper one
+            "$ref" => {
+                return schema_of(simple);
+            }
+            _ => {}
+        }
+    }
+    Value::Object(schema)
+}
+
+/// Converts a Swagger 2.0 schema: references point into `components`,
+/// files become binary strings, `x-nullable` becomes `nullable` and the
+/// `discriminator` property name becomes a discriminator object.
+fn schema_of(schema: &Value) -> Value {
+    match schema {
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, value)| match (key.as_str(), value) {
+                    ("$ref", Value::String(reference)) => (
+                        key.clone(),
+                        json!(reference.replace("#/definitions/", "#/components/schemas/")),
+                    ),
+                    ("type", Value::String(typ)) if typ == "file" => (key.clone(), json!("string")),
+                    ("x-nullable", _) => ("nullable".to_string(), value.clone()),
+                    ("discriminator", Value::String(property)) => {
+                        (key.clone(), json!({ "propertyName": property }))
+                    }
+                    // maps of schemas, whose keys are names rather than keywords
+                    ("properties" | "definitions", Value::Object(schemas)) => (
+                        key.clone(),
+                        Value::Object(
+                            schemas
+                                .iter()
+                                .map(|(name, schema)| (name.clone(), schema_of(schema)))
+                                .collect(),
+                        ),
+                    ),
+                    ("example" | "default" | "enum", _) => (key.clone(), value.clone()),
+                    _ => (key.clone(), schema_of(value)),
+                })
+                .chain(
+                    (object.get("type") == Some(&json!("file")))
+                        .then(|| ("format".to_string(), json!("binary"))),
+                )
+                .collect(),
+        ),
+        Value::Array(schemas) => Value::Array(schemas.iter().map(schema_of).collect()),
+        _ => schema.clone(),
+    }
+}
+
+fn security_scheme_of(scheme: &Value) -> Value {
+    let mut out = match scheme.get("type").and_then(Value::as_str) {
+        Some("basic") => json!({ "type": "http", "scheme": "basic" }),
+        Some("apiKey") => json!({ "type": "apiKey", "name": scheme["name"], "in": scheme["in"] }),
+        Some("oauth2") => {
+            let flow = match scheme.get("flow").and_then(Value::as_str) {
+                Some("password") => "password",
+                Some("application") => "clientCredentials",
+                Some("accessCode") => "authorizationCode",
+                _ => "implicit",
+            };
+            let mut details = Map::new();
+            for key in ["authorizationUrl", "tokenUrl"] {
+                if let Some(url) = scheme.get(key) {
+                    details.insert(key.to_string(), url.clone());
+                }
+            }
+            details.insert(
+                "scopes".to_string(),
+                scheme.get("scopes").cloned().unwrap_or(json!({})),
+            );
+            json!({ "type": "oauth2", "flows": { flow: details } })
+        }
+        _ => scheme.clone(),
+    };
+    if let (Some(out), Some(description)) = (out.as_object_mut(), scheme.get("description")) {
+        out.insert("description".to_string(), description.clone());
+    }
+    out
+}
+
+fn is_body(parameter: &Value) -> bool {
+    parameter.get("in").and_then(Value::as_str) == Some("body")
+}
+
+fn is_form(parameter: &Value) -> bool {
+    parameter.get("in").and_then(Value::as_str) == Some("formData")
+}
+
+/// The object under `key` of `map`, empty if there is none.
+fn object<'s>(map: &'s Map<String, Value>, key: &str) -> &'s Map<String, Value> {
+    static EMPTY: std::sync::OnceLock<Map<String, Value>> = std::sync::OnceLock::new();
+    map.get(key)
+        .and_then(Value::as_object)
+        .unwrap_or_else(|| EMPTY.get_or_init(Map::new))
+}
+
+fn array(value: Option<&Value>) -> Vec<&Value> {
+    value
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+fn strings(value: Option<&Value>) -> Vec<&str> {
+    array(value).into_iter().filter_map(Value::as_str).collect()
+}
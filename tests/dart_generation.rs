@@ -30,7 +30,7 @@
 //! against accidental regressions where we'd emit both, or flip back to
 //! the wrong one.
 
-use openapi_parser::generate::File;
+use openapi_parser::generate::{File, GenerationArgs};
 
 // ---------------------------------------------------------------------------
 // Helpers
@@ -40,12 +40,22 @@ use openapi_parser::generate::File;
 /// indexed by their project-relative path (as a forward-slash string for
 /// portable assertions).
 fn generate(spec_json: &str) -> std::collections::HashMap<String, String> {
+    generate_with_args(spec_json, GenerationArgs::default())
+}
+
+/// Like [`generate`], with explicit [`GenerationArgs`].
+fn generate_with_args(
+    spec_json: &str,
+    args: GenerationArgs,
+) -> std::collections::HashMap<String, String> {
     let rt = tokio::runtime::Builder::new_current_thread()
         .enable_all()
         .build()
         .expect("build tokio runtime");
     let files: Vec<File> = rt
-        .block_on(openapi_parser::generate_dart_files(spec_json, false))
+        .block_on(openapi_parser::generate_dart_files_with_args(
+            spec_json, args,
+        ))
         .expect("generation should succeed");
     files
         .into_iter()
@@ -680,11 +690,14 @@ fn resolve_ref_accepts_both_bare_and_full_paths() {
         },
         "paths": {}
     }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
     let spec = oas3::from_json(spec_json).expect("valid spec");
-    let intermediate = intermediate::parse(
-        &spec,
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
         IntermediateArgs {
             ignore_deprecated_fields: false,
+            strict: false,
         },
     )
     .expect("intermediate parses");
@@ -747,11 +760,14 @@ fn all_of_merges_ref_and_inline_properties() {
         },
         "paths": {}
     }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
     let spec = oas3::from_json(spec_json).expect("valid spec");
-    let intermediate = intermediate::parse(
-        &spec,
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
         IntermediateArgs {
             ignore_deprecated_fields: false,
+            strict: false,
         },
     )
     .expect("intermediate parses");
@@ -763,7 +779,7 @@ fn all_of_merges_ref_and_inline_properties() {
     );
     match &derived.obj {
         IAST::Object(obj) => match &obj.value {
-            AlgType::Product(props) => {
+            AlgType::Product(props, _) => {
                 let mut keys: Vec<&str> = props.keys().copied().collect();
                 keys.sort();
                 assert_eq!(
@@ -857,18 +873,21 @@ fn all_of_nullable_ref_stays_reference() {
         },
         "paths": {}
     }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
     let spec = oas3::from_json(spec_json).expect("valid spec");
-    let im = intermediate::parse(
-        &spec,
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let im = intermediate::parse_document(
+        &document,
         IntermediateArgs {
             ignore_deprecated_fields: false,
+            strict: false,
         },
     )
     .expect("parses");
     let outer = im.find_scheme("Outer").expect("Outer exists");
     match &outer.obj {
         IAST::Object(o) => match &o.value {
-            AlgType::Product(props) => match props.get("p").expect("has p") {
+            AlgType::Product(props, _) => match props.get("p").expect("has p") {
                 IAST::Reference(r) => {
                     assert!(r.nullable, "nullable-ref must be nullable");
                     assert!(
@@ -1380,3 +1399,2866 @@ fn endpoint_request_body_ref_to_never_calls_tojson() {
         "$ref-to-never request body must serialize via .toJson",
     );
 }
+
+/// Query params are typed after their schema in the `_P_<method>` record
+/// (instead of always `String`) and rendered into `paramsJson` accordingly:
+/// primitives via `.toString()`, enums via their JSON value and lists as a
+/// comma separated string.
+#[test]
+fn endpoint_query_params_are_typed_after_their_schema() {
+    let spec = r##"{
+        "openapi": "3.1.0",
+        "info": { "title": "t", "version": "0" },
+        "components": {
+            "schemas": {
+                "Tag": { "type": "string", "enum": ["a", "b"] }
+            }
+        },
+        "paths": {
+            "/users": {
+                "get": {
+                    "parameters": [
+                        { "name": "limit", "in": "query", "required": true, "schema": { "type": "integer" } },
+                        { "name": "verbose", "in": "query", "schema": { "type": "boolean" } },
+                        { "name": "q", "in": "query", "schema": { "type": "string" } },
+                        { "name": "status", "in": "query", "schema": { "type": "string", "enum": ["active", "banned"] } },
+                        { "name": "tag", "in": "query", "schema": { "$ref": "#/components/schemas/Tag" } },
+                        { "name": "ids", "in": "query", "schema": { "type": "array", "items": { "type": "integer" } } }
+                    ],
+                    "responses": { "204": { "description": "" } }
+                }
+            }
+        }
+    }"##;
+    let files = generate(spec);
+    let route = file(&files, "endpoints/routes/_users.dart");
+
+    assert_contains(route, "  int limit,", "integer param is an int");
+    assert_contains(route, "  bool? verbose,", "optional boolean param");
+    assert_contains(route, "  String? q,", "string param stays a String");
+    assert_contains(
+        route,
+        "  BEAM_usersMethods_get_status? status,",
+        "inline enum param gets its own enum class",
+    );
+    assert_contains(
+        route,
+        "enum BEAM_usersMethods_get_status implements BEAMSerde",
+        "inline enum class is emitted next to the params typedef",
+    );
+    assert_contains(route, "  BEAMTagModel? tag,", "$ref enum param");
+    assert_contains(route, "schemes/Tag.dart';", "referenced scheme is imported");
+    assert_contains(route, "  List<int>? ids,", "list param");
+
+    assert_contains(
+        route,
+        "'limit': params.limit.toString(),",
+        "int is stringified",
+    );
+    assert_contains(
+        route,
+        "if (params.q != null) 'q': params.q!,",
+        "strings are passed as is",
+    );
+    assert_contains(
+        route,
+        "'status': params.status!.toJson().toString(),",
+        "enums are sent as their JSON value",
+    );
+    assert_contains(
+        route,
+        "'ids': params.ids!.map((e) => e.toString()).join(','),",
+        "lists are sent comma separated",
+    );
+    assert_not_contains(route, "String limit", "no more untyped params");
+}
+
+/// Array params are joined after their `style`; exploded `form` arrays,
+/// which would repeat the param, are reported as unsupported.
+#[test]
+fn array_params_follow_their_style() {
+    let spec = r##"{
+        "openapi": "3.1.0",
+        "info": { "title": "t", "version": "0" },
+        "components": { "schemas": {} },
+        "paths": {
+            "/users": {
+                "get": {
+                    "parameters": [
+                        { "name": "ids", "in": "query", "schema": { "type": "array", "items": { "type": "integer" } } },
+                        { "name": "csv", "in": "query", "explode": false, "schema": { "type": "array", "items": { "type": "integer" } } },
+                        { "name": "pipes", "in": "query", "style": "pipeDelimited", "explode": false, "schema": { "type": "array", "items": { "type": "string" } } },
+                        { "name": "spaces", "in": "query", "style": "spaceDelimited", "explode": false, "schema": { "type": "array", "items": { "type": "string" } } },
+                        { "name": "X-Tags", "in": "header", "schema": { "type": "array", "items": { "type": "string" } } }
+                    ],
+                    "responses": { "204": { "description": "" } }
+                }
+            }
+        }
+    }"##;
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("build tokio runtime");
+    let generated = rt
+        .block_on(openapi_parser::generate_dart(spec, Default::default()))
+        .expect("generation succeeds");
+    let route = &generated
+        .files
+        .iter()
+        .find(|f| f.path.ends_with("endpoints/routes/_users.dart"))
+        .expect("route file")
+        .content;
+
+    assert_contains(
+        route,
+        "'csv': params.csv!.map((e) => e.toString()).join(','),",
+        "form arrays are comma separated",
+    );
+    assert_contains(
+        route,
+        "'pipes': params.pipes!.map((e) => e).join('|'),",
+        "pipeDelimited arrays are joined by pipes",
+    );
+    assert_contains(
+        route,
+        "'spaces': params.spaces!.map((e) => e).join(' '),",
+        "spaceDelimited arrays are joined by spaces",
+    );
+    assert_contains(
+        route,
+        "'X-Tags': params.X_Tags!.map((e) => e).join(','),",
+        "simple header arrays are comma separated",
+    );
+    let unsupported: Vec<_> = generated
+        .diagnostics
+        .iter()
+        .filter(|d| d.code == "unsupported-param-style")
+        .map(|d| d.pointer.as_str())
+        .collect();
+    assert_eq!(unsupported, ["#/paths/~1users/get/parameters/0"]);
+}
+
+/// Declared path params are parsed into the IR, linked to the route
+/// fragment standing for them, and typed in the generated fragment
+/// accessor. Their values are percent-encoded when interpolated.
+#[test]
+fn path_params_type_the_route_fragment_accessor() {
+    use openapi_parser::parse::intermediate::{self, IntermediateArgs, RouteFragment};
+
+    let spec_json = r##"{
+        "openapi": "3.1.0",
+        "info": { "title": "t", "version": "0" },
+        "components": {},
+        "paths": {
+            "/users/{userId}": {
+                "get": {
+                    "parameters": [
+                        {
+                            "name": "userId",
+                            "in": "path",
+                            "required": true,
+                            "description": "the user",
+                            "schema": { "type": "integer" }
+                        },
+                        { "name": "q", "in": "query", "schema": { "type": "string" } }
+                    ],
+                    "responses": {}
+                }
+            }
+        }
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+
+    let route = &intermediate.routes[0];
+    assert_eq!(route.path_params.len(), 1);
+    assert_eq!(route.path_params[0].name, "userId");
+    assert_eq!(route.path_params[0].description, Some("the user"));
+    // path params are not sent as query params
+    assert_eq!(route.endpoints[0].params.len(), 1);
+    assert_eq!(route.endpoints[0].params[0].name, "q");
+
+    let RouteFragment::Node(root) = &intermediate.routes_tree else {
+        panic!("root is a node");
+    };
+    let RouteFragment::Node(users) = &root.children[0] else {
+        panic!("users is a node");
+    };
+    let RouteFragment::Node(user_id) = &users.children[0] else {
+        panic!("userId is a node");
+    };
+    let param_ref = user_id.param.as_ref().expect("userId links its param");
+    assert_eq!(intermediate.path_param(param_ref).name, "userId");
+
+    let files = generate(spec_json);
+    let users_frag = file(&files, "endpoints/_frags/users.dart");
+    assert_contains(
+        users_frag,
+        "BEAMroot__usersFrag_userId userId(int userId) => BEAMroot__usersFrag_userId(parent: this, param: userId, deps: this.deps);",
+        "accessor is typed and named after the param",
+    );
+    assert_contains(users_frag, "/// the user", "accessor documents the param");
+
+    let user_id_frag = file(&files, "endpoints/_frags/users_frags/userId.dart");
+    assert_contains(
+        user_id_frag,
+        "required int param}) : super(ownFragment: Uri.encodeComponent(param.toString()));",
+        "fragment takes the typed value and percent-encodes it",
+    );
+}
+
+/// Parameters, request bodies and responses may be shared through
+/// `components` and referenced via `$ref`, possibly through a chain of refs.
+/// They must resolve to their definitions instead of being dropped.
+#[test]
+fn component_refs_for_params_bodies_and_responses_are_resolved() {
+    use openapi_parser::parse::intermediate::{self, IntermediateArgs, IAST};
+
+    let spec_json = r##"{
+        "openapi": "3.1.0",
+        "info": { "title": "t", "version": "1" },
+        "components": {
+            "schemas": {
+                "User": { "type": "object", "properties": { "id": { "type": "integer" } } }
+            },
+            "parameters": {
+                "Limit": { "name": "limit", "in": "query", "description": "page size", "schema": { "type": "integer" } },
+                "PageSize": { "$ref": "#/components/parameters/Limit" }
+            },
+            "requestBodies": {
+                "NewUser": { "content": { "application/json": { "schema": { "$ref": "#/components/schemas/User" } } } },
+                "UserBody": { "$ref": "#/components/requestBodies/NewUser" }
+            },
+            "responses": {
+                "UserResponse": { "description": "ok", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/User" } } } },
+                "Ok": { "$ref": "#/components/responses/UserResponse" }
+            }
+        },
+        "paths": {
+            "/users": {
+                "post": {
+                    "parameters": [ { "$ref": "#/components/parameters/PageSize" } ],
+                    "requestBody": { "$ref": "#/components/requestBodies/UserBody" },
+                    "responses": { "200": { "$ref": "#/components/responses/Ok" } }
+                }
+            }
+        }
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+
+    let endpoint = &intermediate.routes[0].endpoints[0];
+    assert_eq!(endpoint.params.len(), 1);
+    assert_eq!(endpoint.params[0].name, "limit");
+    assert_eq!(endpoint.params[0].description, Some("page size"));
+    let user_ref =
+        |iast: &IAST| matches!(iast, IAST::Reference(r) if r.path == "#/components/schemas/User");
+    assert!(user_ref(
+        endpoint
+            .request
+            .as_ref()
+            .and_then(|c| c.get("application/json"))
+            .map(|payload| &payload.schema)
+            .expect("request body resolved")
+    ));
+    assert!(user_ref(
+        endpoint
+            .responses
+            .values()
+            .next()
+            .and_then(|c| c.get("application/json"))
+            .map(|payload| &payload.schema)
+            .expect("response resolved")
+    ));
+
+    let files = generate(spec_json);
+    let route = file(&files, "endpoints/routes/_users.dart");
+    assert_contains(
+        route,
+        "BEAMCachedResponse<BEAMUserModel> post(_P_post params, {required BEAMUserModel body})",
+        "body and response are typed after the resolved schema",
+    );
+    assert_contains(
+        route,
+        "int? limit,",
+        "referenced param is part of the typedef",
+    );
+}
+
+/// Params declared on the path item apply to every operation of the route;
+/// an operation-level param with the same name and location replaces them.
+#[test]
+fn path_item_params_are_merged_into_every_endpoint() {
+    use openapi_parser::parse::intermediate::{self, IntermediateArgs, ParamLocation};
+
+    let spec_json = r##"{
+        "openapi": "3.1.0",
+        "info": { "title": "t", "version": "1" },
+        "components": { "schemas": {} },
+        "paths": {
+            "/users/{userId}": {
+                "parameters": [
+                    { "name": "userId", "in": "path", "required": true, "schema": { "type": "integer" } },
+                    { "name": "tenant", "in": "header", "required": true, "schema": { "type": "string" } },
+                    { "name": "limit", "in": "query", "schema": { "type": "integer" } }
+                ],
+                "get": {
+                    "parameters": [
+                        { "name": "limit", "in": "query", "description": "overridden", "schema": { "type": "string" } }
+                    ],
+                    "responses": {}
+                },
+                "delete": { "responses": {} }
+            }
+        }
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+
+    let route = &intermediate.routes[0];
+    assert_eq!(route.path_params.len(), 1, "path-level path param is kept");
+    assert_eq!(route.path_params[0].name, "userId");
+    for endpoint in &route.endpoints {
+        let names: Vec<_> = endpoint
+            .params
+            .iter()
+            .map(|p| (p.name, p.location))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("tenant", ParamLocation::Header),
+                ("limit", ParamLocation::Query)
+            ]
+        );
+    }
+    assert_eq!(route.endpoints[0].params[1].description, Some("overridden"));
+
+    let files = generate(spec_json);
+    let route_file = file(&files, "endpoints/routes/_users__userId_.dart");
+    assert_contains(
+        route_file,
+        "/// overridden\n  String? limit,",
+        "get uses its own declaration of limit",
+    );
+    assert_contains(
+        route_file,
+        "typedef _P_delete = (\n{\n  /// \n  String tenant,\n  /// \n  int? limit,",
+        "delete inherits the path-level params",
+    );
+}
+
+/// Every media type of a request body or response is kept in the IR. The
+/// default Dart method speaks JSON; other media types get dedicated methods
+/// that tell the handler which media type to send or accept.
+#[test]
+fn endpoints_offering_several_media_types_get_a_method_per_media_type() {
+    use openapi_parser::parse::intermediate::{self, IntermediateArgs};
+
+    let spec_json = r##"{
+        "openapi": "3.1.0",
+        "info": { "title": "t", "version": "1" },
+        "components": { "schemas": {
+            "Note": { "type": "object", "properties": { "text": { "type": "string" } } }
+        } },
+        "paths": {
+            "/notes": {
+                "post": {
+                    "requestBody": { "content": {
+                        "application/json": { "schema": { "$ref": "#/components/schemas/Note" } },
+                        "text/plain": { "schema": { "type": "string" } }
+                    } },
+                    "responses": {
+                        "200": { "description": "ok", "content": {
+                            "application/json": { "schema": { "$ref": "#/components/schemas/Note" } },
+                            "text/csv": { "schema": { "type": "string" } }
+                        } },
+                        "204": { "description": "nothing" }
+                    }
+                }
+            }
+        }
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+
+    let endpoint = &intermediate.routes[0].endpoints[0];
+    let request = endpoint.request.as_ref().expect("request body");
+    assert_eq!(
+        request.keys().copied().collect::<Vec<_>>(),
+        vec!["application/json", "text/plain"]
+    );
+    assert_eq!(endpoint.responses.len(), 1, "204 has no content to type");
+    let ok = endpoint.responses.values().next().unwrap();
+    assert_eq!(
+        ok.keys().copied().collect::<Vec<_>>(),
+        vec!["application/json", "text/csv"]
+    );
+
+    let files = generate(spec_json);
+    let route = file(&files, "endpoints/routes/_notes.dart");
+    assert_contains(
+        route,
+        "BEAMCachedResponse<BEAMNoteModel> post( {required BEAMNoteModel body}){",
+        "default method is JSON",
+    );
+    assert_contains(
+        route,
+        "body: body?.toJson(), expectedResponseType: BEAMExpectedResponseType.json).then((json) => BEAMNoteModel.fromJson(json));",
+        "default method passes no media type",
+    );
+    assert_contains(
+        route,
+        "BEAMCachedResponse<BEAMNoteModel> postTextPlain( {required String body}){",
+        "text/plain body gets its own method",
+    );
+    assert_contains(
+        route,
+        "body: body, contentType: 'text/plain',",
+        "content type is passed on",
+    );
+    assert_contains(
+        route,
+        "BEAMCachedResponse<String> postAcceptingTextCsv( {required BEAMNoteModel body}){",
+        "text/csv response gets its own method",
+    );
+    assert_contains(
+        route,
+        "accept: 'text/csv',",
+        "accepted media type is passed on",
+    );
+    assert_contains(
+        file(&files, "endpoints/endpoints.dart"),
+        "abstract interface class BeamMediaTypeAwareHandler",
+        "handlers can opt into media types",
+    );
+}
+
+/// `multipart/form-data` bodies are typed as a form record whose binary
+/// properties are files, and are sent as a `BEAMMultipartBody` through the
+/// multipart handler capability.
+#[test]
+fn multipart_bodies_are_sent_as_typed_forms() {
+    use openapi_parser::parse::intermediate::{self, IntermediateArgs, PayloadFormat};
+
+    let spec_json = r##"{
+        "openapi": "3.1.0",
+        "info": { "title": "t", "version": "1" },
+        "components": { "schemas": {
+            "User": { "type": "object", "properties": { "id": { "type": "integer" } } },
+            "AvatarUpload": {
+                "type": "object",
+                "required": ["avatar", "userId"],
+                "properties": {
+                    "avatar": { "type": "string", "format": "binary", "description": "the image" },
+                    "userId": { "type": "integer" },
+                    "tags": { "type": "array", "items": { "type": "string" } },
+                    "attachments": { "type": "array", "items": { "type": "string", "format": "binary" } }
+                }
+            }
+        } },
+        "paths": {
+            "/avatars": {
+                "post": {
+                    "requestBody": { "content": {
+                        "multipart/form-data": {
+                            "schema": { "$ref": "#/components/schemas/AvatarUpload" },
+                            "encoding": { "avatar": { "contentType": "image/png" } }
+                        }
+                    } },
+                    "responses": { "200": { "description": "ok", "content": {
+                        "application/json": { "schema": { "$ref": "#/components/schemas/User" } }
+                    } } }
+                }
+            }
+        }
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+    let request = intermediate.routes[0].endpoints[0]
+        .request
+        .as_ref()
+        .expect("request body");
+    let form = &request["multipart/form-data"];
+    assert_eq!(form.format, PayloadFormat::MultipartForm);
+    assert_eq!(form.encoding["avatar"].content_type, Some("image/png"));
+
+    let files = generate(spec_json);
+    let route = file(&files, "endpoints/routes/_avatars.dart");
+    assert_contains(
+        route,
+        "BEAMCachedResponse<BEAMUserModel> post( {required _F_post body}){",
+        "body is the form record",
+    );
+    assert_contains(
+        route,
+        "  /// the image\n  BEAMMultipartFile avatar,",
+        "binary property is a file",
+    );
+    assert_contains(
+        route,
+        "  List<BEAMMultipartFile>? attachments,",
+        "binary array is a list of files",
+    );
+    assert_contains(route, "  int userId,", "other properties are typed");
+    assert_contains(
+        route,
+        "MapEntry('avatar', body.avatar.withFallbackContentType('image/png')),",
+        "encoding content type is the file's fallback",
+    );
+    assert_contains(
+        route,
+        "if (body.attachments != null) for (final e in body.attachments!) MapEntry('attachments', e),",
+        "every file of an array is a part",
+    );
+    assert_contains(
+        route,
+        "MapEntry('userId', body.userId.toString()),",
+        "text fields are stringified",
+    );
+    assert_contains(route, "body: formBody,", "the form is sent");
+    let endpoints = file(&files, "endpoints/endpoints.dart");
+    assert_contains(
+        endpoints,
+        "abstract interface class BeamMultipartHandler implements JsonRequestHandler",
+        "multipart handler capability exists",
+    );
+    assert_contains(
+        endpoints,
+        "abstract interface class JsonRequestHandler {",
+        "json handlers are untouched",
+    );
+}
+
+/// `application/x-www-form-urlencoded` bodies are typed as a form record and
+/// serialized after the `style`/`explode` of each property's `encoding`.
+#[test]
+fn urlencoded_bodies_honor_their_encoding() {
+    use openapi_parser::parse::intermediate::{self, FormStyle, IntermediateArgs, PayloadFormat};
+
+    let spec_json = r##"{
+        "openapi": "3.1.0",
+        "info": { "title": "t", "version": "1" },
+        "components": { "schemas": {
+            "Filter": { "type": "object", "properties": { "color": { "type": "string" } } }
+        } },
+        "paths": {
+            "/token": {
+                "post": {
+                    "requestBody": { "content": {
+                        "application/x-www-form-urlencoded": {
+                            "schema": {
+                                "type": "object",
+                                "required": ["grant_type"],
+                                "properties": {
+                                    "grant_type": { "type": "string" },
+                                    "scope": { "type": "array", "items": { "type": "string" } },
+                                    "ids": { "type": "array", "items": { "type": "integer" } },
+                                    "filter": { "$ref": "#/components/schemas/Filter" }
+                                }
+                            },
+                            "encoding": {
+                                "scope": { "style": "spaceDelimited" },
+                                "filter": { "style": "deepObject", "explode": true }
+                            }
+                        }
+                    } },
+                    "responses": {}
+                }
+            }
+        }
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+    let form = &intermediate.routes[0].endpoints[0]
+        .request
+        .as_ref()
+        .expect("request body")["application/x-www-form-urlencoded"];
+    assert_eq!(form.format, PayloadFormat::UrlEncodedForm);
+    assert_eq!(form.encoding["scope"].style, FormStyle::SpaceDelimited);
+    assert!(
+        !form.encoding["scope"].explode,
+        "only form explodes by default"
+    );
+    assert_eq!(form.encoding["filter"].style, FormStyle::DeepObject);
+
+    let files = generate(spec_json);
+    let route = file(&files, "endpoints/routes/_token.dart");
+    assert_contains(
+        route,
+        "final formBody = BEAMUrlEncodedBody([",
+        "body is a urlencoded form",
+    );
+    assert_contains(
+        route,
+        "MapEntry('grant_type', body.grant_type),",
+        "scalars are single fields",
+    );
+    assert_contains(
+        route,
+        "...BEAMUrlEncodedBody.array('scope', body.scope!.map((e) => e), style: BEAMFormStyle.spaceDelimited, explode: false),",
+        "explicit style is honored",
+    );
+    assert_contains(
+        route,
+        "...BEAMUrlEncodedBody.array('ids', body.ids!.map((e) => e.toString()), style: BEAMFormStyle.form, explode: true),",
+        "arrays default to exploded form style",
+    );
+    assert_contains(
+        route,
+        "...BEAMUrlEncodedBody.object('filter', body.filter!.toJson(), style: BEAMFormStyle.deepObject, explode: true),",
+        "objects are serialized after their style",
+    );
+    assert_contains(
+        route,
+        "body: formBody, contentType: 'application/x-www-form-urlencoded',",
+        "the content type is handed on",
+    );
+    assert_contains(
+        file(&files, "endpoints/endpoints.dart"),
+        "abstract interface class BeamFormUrlEncodedHandler",
+        "urlencoded handler capability exists",
+    );
+}
+
+/// String formats with a richer Dart type are parsed into their own
+/// primitives: `date-time`/`date` become `DateTime`, `uri` becomes `Uri` and
+/// `byte` becomes base64 decoded `Uint8List`, each converted through
+/// `BEAMFormats`. Other formats are kept for documentation.
+#[test]
+fn formatted_strings_map_to_dart_types() {
+    use openapi_parser::parse::intermediate::{self, IntermediateArgs, Primitive, IAST};
+    let spec_json = r##"{
+        "openapi": "3.0.0",
+        "info": { "title": "t", "version": "1" },
+        "paths": {
+            "/events": {
+                "get": {
+                    "parameters": [
+                        { "name": "since", "in": "query", "schema": { "type": "string", "format": "date-time" } }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "ok",
+                            "content": { "application/json": { "schema": {
+                                "type": "array", "items": { "$ref": "#/components/schemas/Day" }
+                            } } }
+                        }
+                    }
+                }
+            }
+        },
+        "components": {
+            "schemas": {
+                "Day": { "type": "string", "format": "date" },
+                "Event": {
+                    "type": "object",
+                    "required": ["id", "createdAt"],
+                    "properties": {
+                        "id": { "type": "string", "format": "uuid" },
+                        "createdAt": { "type": "string", "format": "date-time" },
+                        "day": { "$ref": "#/components/schemas/Day" },
+                        "link": { "type": "string", "format": "uri" },
+                        "blob": { "type": "string", "format": "byte" },
+                        "count": { "type": "integer", "format": "int64" },
+                        "history": { "type": "array", "items": { "type": "string", "format": "date-time" } }
+                    }
+                }
+            }
+        }
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+    let day = intermediate.find_scheme("Day").expect("Day scheme");
+    assert!(matches!(
+        &day.obj,
+        IAST::Primitive(p) if matches!(p.value, Primitive::Date) && p.format == Some("date")
+    ));
+
+    let files = generate(spec_json);
+    let event = file(&files, "schemes/Event.dart");
+    assert_contains(event, "DateTime createdAt;", "date-time is a DateTime");
+    assert_contains(event, "Uri? link;", "uri is a Uri");
+    assert_contains(event, "Uint8List? blob;", "byte is a Uint8List");
+    assert_contains(event, "/// FORMAT: uuid", "uuid is documented");
+    assert_contains(event, "/// FORMAT: int64", "int64 is documented");
+    assert_contains(
+        event,
+        "createdAt: BEAMFormats.dateTimeFromJson(json['createdAt']),",
+        "date-time is parsed from ISO-8601",
+    );
+    assert_contains(
+        event,
+        "'createdAt': BEAMFormats.dateTimeToJson(createdAt),",
+        "date-time is written as ISO-8601",
+    );
+    assert_contains(
+        event,
+        "day: json['day'] == null ? null : BEAMFormats.dateFromJson(json['day']),",
+        "refs to formatted typedefs are converted too",
+    );
+    assert_contains(
+        event,
+        "if(blob != null) 'blob': BEAMFormats.bytesToJson(blob!),",
+        "bytes are written as base64",
+    );
+    assert_contains(
+        event,
+        "(json['history'] as List).map((e) => BEAMFormats.dateTimeFromJson(e)).toList()",
+        "list elements are converted",
+    );
+    assert_not_contains(event, "val is DateTime", "formatted values are not cast");
+
+    let route = file(&files, "endpoints/routes/_events.dart");
+    assert_contains(route, "DateTime? since,", "params are typed");
+    assert_contains(
+        route,
+        "'since': BEAMFormats.dateTimeToJson(params.since!),",
+        "params are sent as ISO-8601",
+    );
+    assert_contains(
+        route,
+        "(json as List).map((e) => BEAMFormats.dateFromJson(e)).toList()",
+        "responses are converted",
+    );
+    let serde = file(&files, "utils/serde.dart");
+    assert_contains(
+        serde,
+        "abstract final class BEAMFormats",
+        "format helpers exist",
+    );
+    assert_contains(
+        serde,
+        "value.toUtc().toIso8601String()",
+        "date-times are sent with an offset, as RFC 3339 requires",
+    );
+}
+
+/// Validation keywords are kept in the IR. With `generate_validators`,
+/// generated classes get a `validate()` method reporting violations with
+/// their JSON paths, descending into nested classes and list elements.
+#[test]
+fn validators_check_the_validation_keywords() {
+    use openapi_parser::parse::intermediate::{self, AlgType, IntermediateArgs, IAST};
+    let spec_json = r##"{
+        "openapi": "3.1.0",
+        "info": { "title": "t", "version": "1" },
+        "paths": {},
+        "components": {
+            "schemas": {
+                "Name": { "type": "string", "minLength": 1, "maxLength": 20, "pattern": "^[a-z]+$" },
+                "User": {
+                    "type": "object",
+                    "required": ["name"],
+                    "properties": {
+                        "name": { "$ref": "#/components/schemas/Name" },
+                        "age": { "type": "integer", "minimum": 0, "exclusiveMaximum": 150 },
+                        "score": { "type": "number", "multipleOf": 0.5 },
+                        "tags": {
+                            "type": "array", "minItems": 1, "uniqueItems": true,
+                            "items": { "type": "string", "maxLength": 8 }
+                        },
+                        "friends": { "type": "array", "items": { "$ref": "#/components/schemas/User" } },
+                        "x-id": { "type": "string", "pattern": "it's" }
+                    }
+                }
+            }
+        }
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+    let IAST::Object(user) = &intermediate.find_scheme("User").expect("User").obj else {
+        panic!("User is an object");
+    };
+    let AlgType::Product(props, _) = &user.value else {
+        panic!("User is a product");
+    };
+    let IAST::Primitive(age) = &props["age"] else {
+        panic!("age is primitive");
+    };
+    assert_eq!(age.constraints.minimum, Some(0.into()));
+    assert_eq!(age.constraints.exclusive_maximum, Some(150.into()));
+    let IAST::Primitive(tags) = &props["tags"] else {
+        panic!("tags is primitive");
+    };
+    assert_eq!(tags.constraints.min_items, Some(1));
+    assert!(tags.constraints.unique_items);
+
+    let without = generate(spec_json);
+    assert_not_contains(
+        file(&without, "schemes/User.dart"),
+        "validate(",
+        "validators are opt-in",
+    );
+
+    let files = generate_with_args(
+        spec_json,
+        GenerationArgs {
+            generate_validators: true,
+            ..Default::default()
+        },
+    );
+    let user = file(&files, "schemes/User.dart");
+    assert_contains(
+        user,
+        "class BEAMUserModel implements BEAMSerde, BEAMValidatable {",
+        "classes are validatable",
+    );
+    assert_contains(
+        user,
+        "List<BEAMViolation> validate([String path = r'$']) {",
+        "validate method exists",
+    );
+    assert_contains(
+        user,
+        "BEAMValidate.number(violations, '$path.age', age, minimum: 0, exclusiveMaximum: 150);",
+        "numeric bounds are checked",
+    );
+    assert_contains(
+        user,
+        "BEAMValidate.number(violations, '$path.score', score, multipleOf: 0.5);",
+        "multipleOf is checked",
+    );
+    assert_contains(
+        user,
+        "BEAMValidate.string(violations, '$path.name', name, minLength: 1, maxLength: 20, pattern: r'^[a-z]+$');",
+        "constraints of referenced schemes are checked",
+    );
+    assert_contains(
+        user,
+        "BEAMValidate.list(violations, '$path.tags', tags, minItems: 1, uniqueItems: true);",
+        "list constraints are checked",
+    );
+    assert_contains(
+        user,
+        "for (final (i0, e0) in BEAMValidate.items(tags)) {\n      BEAMValidate.string(violations, '$path.tags[$i0]', e0, maxLength: 8);",
+        "list elements are checked",
+    );
+    assert_contains(
+        user,
+        "BEAMValidate.nested(violations, '$path.friends[$i0]', e0);",
+        "nested classes are validated",
+    );
+    assert_contains(
+        user,
+        r#"BEAMValidate.string(violations, '$path["x-id"]', x_id, pattern: r"it's");"#,
+        "odd names and patterns are quoted",
+    );
+    assert_contains(
+        file(&files, "utils/serde.dart"),
+        "class BEAMViolation {",
+        "violations are structured",
+    );
+}
+
+/// A `default` is kept in the IR. Primitive and enum properties with one
+/// are non-null and default to it in the constructor and when missing from
+/// the JSON; optional query params fall back to theirs when sent.
+#[test]
+fn schema_defaults_become_constructor_defaults() {
+    use openapi_parser::parse::intermediate::{self, AlgType, IntermediateArgs, IAST};
+    let spec_json = r##"{
+        "openapi": "3.0.0",
+        "info": { "title": "t", "version": "1" },
+        "paths": {
+            "/items": {
+                "get": {
+                    "parameters": [
+                        { "name": "limit", "in": "query", "schema": { "type": "integer", "default": 20 } },
+                        { "name": "sort", "in": "query", "schema": { "$ref": "#/components/schemas/Sort" } }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "ok",
+                            "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Page" } } }
+                        }
+                    }
+                }
+            }
+        },
+        "components": {
+            "schemas": {
+                "Sort": { "type": "string", "enum": ["name", "date"], "default": "date" },
+                "Page": {
+                    "type": "object",
+                    "properties": {
+                        "size": { "type": "integer", "default": 10 },
+                        "title": { "type": "string", "default": "untitled" },
+                        "active": { "type": "boolean", "default": true },
+                        "mode": { "type": "string", "enum": ["a", "b"], "default": "b" },
+                        "sort": { "$ref": "#/components/schemas/Sort" },
+                        "plain": { "type": "string" }
+                    }
+                }
+            }
+        }
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+    let IAST::Object(page) = &intermediate.find_scheme("Page").expect("Page").obj else {
+        panic!("Page is an object");
+    };
+    let AlgType::Product(props, _) = &page.value else {
+        panic!("Page is a product");
+    };
+    let IAST::Primitive(size) = &props["size"] else {
+        panic!("size is primitive");
+    };
+    assert_eq!(size.default, Some(&serde_json::json!(10)));
+
+    let files = generate(spec_json);
+    let page = file(&files, "schemes/Page.dart");
+    assert_contains(page, "  int size;", "defaulted properties are non-null");
+    assert_contains(page, "  String? plain;", "others stay nullable");
+    assert_contains(page, "    this.size = 10,", "numbers default");
+    assert_contains(page, "    this.title = r'untitled',", "strings default");
+    assert_contains(page, "    this.active = true,", "booleans default");
+    assert_contains(
+        page,
+        "    this.mode = BEAMPage_mode.t_b,",
+        "inline enums default",
+    );
+    assert_contains(
+        page,
+        "    this.sort = BEAMSortModel.t_date,",
+        "referenced enums default",
+    );
+    assert_contains(
+        page,
+        "(json['size'] ?? 10)",
+        "missing JSON values take the default",
+    );
+    assert_contains(
+        page,
+        "mode: json['mode'] == null ? BEAMPage_mode.t_b : BEAMPage_mode.fromJson(json['mode']),",
+        "missing enums take the default",
+    );
+    assert_contains(
+        page,
+        "    'size': size,",
+        "defaulted values are always sent",
+    );
+
+    let route = file(&files, "endpoints/routes/_items.dart");
+    assert_contains(
+        route,
+        "/// Defaults to `20`.",
+        "param defaults are documented",
+    );
+    assert_contains(
+        route,
+        "'limit': (params.limit ?? 20).toString(),",
+        "params fall back to their default",
+    );
+    assert_contains(
+        route,
+        "'sort': (params.sort ?? BEAMSortModel.t_date).toJson().toString(),",
+        "enum params fall back to their default",
+    );
+}
+
+/// `additionalProperties` schemas are kept in the IR: dictionaries become
+/// typed maps whose values are (de)serialized one by one, and objects with
+/// fixed properties keep their extra ones in an `additional` map.
+#[test]
+fn additional_properties_become_typed_maps() {
+    use openapi_parser::parse::intermediate::{self, AlgType, IntermediateArgs, Primitive, IAST};
+
+    let spec_json = r##"{
+        "openapi": "3.1.0",
+        "info": { "title": "t", "version": "1" },
+        "paths": {
+            "/foos": {
+                "get": {
+                    "responses": {
+                        "200": {
+                            "description": "ok",
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/FooMap" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        "components": {
+            "schemas": {
+                "Foo": {
+                    "type": "object",
+                    "properties": { "id": { "type": "string" } },
+                    "required": ["id"]
+                },
+                "FooMap": {
+                    "type": "object",
+                    "additionalProperties": { "$ref": "#/components/schemas/Foo" }
+                },
+                "Holder": {
+                    "type": "object",
+                    "properties": {
+                        "byName": {
+                            "type": "object",
+                            "additionalProperties": { "$ref": "#/components/schemas/Foo" }
+                        },
+                        "foos": { "$ref": "#/components/schemas/FooMap" },
+                        "seen": {
+                            "type": "object",
+                            "additionalProperties": { "type": "string", "format": "date-time" }
+                        }
+                    },
+                    "required": ["byName"]
+                },
+                "Extensible": {
+                    "type": "object",
+                    "properties": { "name": { "type": "string" } },
+                    "required": ["name"],
+                    "additionalProperties": { "$ref": "#/components/schemas/Foo" }
+                },
+                "Closed": {
+                    "type": "object",
+                    "properties": { "name": { "type": "string" } },
+                    "additionalProperties": false
+                }
+            }
+        }
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+    let IAST::Primitive(foo_map) = &intermediate.find_scheme("FooMap").expect("FooMap").obj else {
+        panic!("FooMap is primitive");
+    };
+    let Primitive::Map(values) = &foo_map.value else {
+        panic!("FooMap is a map");
+    };
+    assert!(matches!(&**values, IAST::Reference(r) if r.path == "#/components/schemas/Foo"));
+    let IAST::Object(closed) = &intermediate.find_scheme("Closed").expect("Closed").obj else {
+        panic!("Closed is an object");
+    };
+    assert!(matches!(closed.value, AlgType::Product(_, None)));
+
+    let files = generate(spec_json);
+    assert_contains(
+        file(&files, "schemes/FooMap.dart"),
+        "typedef BEAMFooMapModel = Map<String, BEAMFooModel>;",
+        "dictionaries become typed maps",
+    );
+
+    let holder = file(&files, "schemes/Holder.dart");
+    assert_contains(
+        holder,
+        "  Map<String, BEAMFooModel> byName;",
+        "inline dictionaries are typed",
+    );
+    assert_contains(
+        holder,
+        "byName:  (json['byName'] as Map).map((k, e) => MapEntry(k as String, BEAMFooModel.fromJson(e))),",
+        "values are decoded one by one",
+    );
+    assert_contains(
+        holder,
+        "'byName': byName?.map((k, e) => MapEntry(k, e.toJson())),",
+        "values are encoded one by one",
+    );
+    assert_contains(
+        holder,
+        "foos: json['foos'] == null ? null :  (json['foos'] as Map).map((k, e) => MapEntry(k as String, BEAMFooModel.fromJson(e))),",
+        "referenced dictionaries are decoded one by one",
+    );
+    assert_contains(
+        holder,
+        "MapEntry(k as String, BEAMFormats.dateTimeFromJson(e))",
+        "formatted values go through their codec",
+    );
+
+    let extensible = file(&files, "schemes/Extensible.dart");
+    assert_contains(
+        extensible,
+        "  Map<String, BEAMFooModel> additional;",
+        "extra properties are kept",
+    );
+    assert_contains(
+        extensible,
+        "    this.additional = const {},",
+        "extra properties are optional",
+    );
+    assert_contains(
+        extensible,
+        "if (!const <String>{r'name'}.contains(key)) key: BEAMFooModel.fromJson(e),",
+        "only unknown keys are extra",
+    );
+    assert_contains(
+        extensible,
+        "    ...additional.map((k, e) => MapEntry(k, e.toJson())),",
+        "extra properties are sent",
+    );
+    assert_not_contains(
+        file(&files, "schemes/Closed.dart"),
+        "additional",
+        "closed objects have no extras",
+    );
+
+    assert_contains(
+        file(&files, "endpoints/routes/_foos.dart"),
+        ".then((json) => (json as Map).map((k, e) => MapEntry(k as String, BEAMFooModel.fromJson(e))));",
+        "map responses are decoded one by one",
+    );
+}
+
+/// `readOnly` / `writeOnly` are kept in the IR. By default they are only
+/// documented; with `ReadWriteModels::OptionalFields` the shared model
+/// makes such properties optional; with `ReadWriteModels::Separate` the
+/// endpoints send `...Request` models without the `readOnly` properties and
+/// return `...Response` models without the `writeOnly` ones, also for the
+/// schemes referencing them.
+#[test]
+fn read_only_and_write_only_properties() {
+    use openapi_parser::generate::ReadWriteModels;
+    use openapi_parser::parse::intermediate::{self, AlgType, IntermediateArgs, IAST};
+
+    let spec_json = r##"{
+        "openapi": "3.1.0",
+        "info": { "title": "t", "version": "1" },
+        "paths": {
+            "/users": {
+                "post": {
+                    "requestBody": {
+                        "content": {
+                            "application/json": {
+                                "schema": { "$ref": "#/components/schemas/User" }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "ok",
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/Team" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        "components": {
+            "schemas": {
+                "User": {
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "string", "readOnly": true },
+                        "name": { "type": "string" },
+                        "password": { "type": "string", "writeOnly": true }
+                    },
+                    "required": ["id", "name", "password"]
+                },
+                "Team": {
+                    "type": "object",
+                    "properties": { "lead": { "$ref": "#/components/schemas/User" } },
+                    "required": ["lead"]
+                }
+            }
+        }
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+    let IAST::Object(user) = &intermediate.find_scheme("User").expect("User").obj else {
+        panic!("User is an object");
+    };
+    let AlgType::Product(props, _) = &user.value else {
+        panic!("User is a product");
+    };
+    assert!(props["id"].is_read_only() && !props["id"].is_write_only());
+    assert!(props["password"].is_write_only() && !props["password"].is_read_only());
+    assert_eq!(
+        intermediate.schemes_with_read_write_only(),
+        ["User", "Team"].into_iter().collect()
+    );
+
+    let files = generate(spec_json);
+    let user = file(&files, "schemes/User.dart");
+    assert_contains(user, "  String id;", "readOnly stays required by default");
+    assert_contains(
+        user,
+        "  String password;",
+        "writeOnly stays required by default",
+    );
+    assert_contains(user, "\t/// READ ONLY\n", "readOnly is documented");
+    assert!(!files.contains_key("schemes/UserRequest.dart"));
+
+    let files = generate_with_args(
+        spec_json,
+        GenerationArgs {
+            read_write_models: ReadWriteModels::OptionalFields,
+            ..Default::default()
+        },
+    );
+    let user = file(&files, "schemes/User.dart");
+    assert_contains(user, "  String? id;", "readOnly is optional");
+    assert_contains(user, "  String name;", "others stay required");
+    assert_contains(user, "  String? password;", "writeOnly is optional");
+    assert_contains(user, "\t/// READ ONLY\n", "readOnly is documented");
+    assert!(!files.contains_key("schemes/UserRequest.dart"));
+
+    let files = generate_with_args(
+        spec_json,
+        GenerationArgs {
+            read_write_models: ReadWriteModels::Separate,
+            ..Default::default()
+        },
+    );
+    let request = file(&files, "schemes/UserRequest.dart");
+    assert_not_contains(request, " id;", "requests lack readOnly");
+    assert_contains(request, "  String password;", "requests need writeOnly");
+    let response = file(&files, "schemes/UserResponse.dart");
+    assert_contains(response, "  String id;", "responses have readOnly");
+    assert_not_contains(response, "password", "responses lack writeOnly");
+    assert_contains(
+        file(&files, "schemes/TeamResponse.dart"),
+        "  BEAMUserResponseModel lead;",
+        "referencing schemes use the variants",
+    );
+    let route = file(&files, "endpoints/routes/_users.dart");
+    assert_contains(
+        route,
+        "BEAMCachedResponse<BEAMTeamResponseModel> post( {required BEAMUserRequestModel body})",
+        "endpoints send requests and return responses",
+    );
+}
+
+/// A `discriminator` without `mapping` selects the variants by their scheme
+/// name, and a base scheme with a `discriminator` which other schemes `allOf`
+/// becomes a sealed class with a subclass per subtype.
+#[test]
+fn implicit_discriminator_mapping_and_all_of_hierarchies() {
+    use openapi_parser::parse::intermediate::{self, AlgType, IntermediateArgs, IAST};
+
+    let spec_json = r##"{
+        "openapi": "3.0.0",
+        "info": { "title": "t", "version": "1" },
+        "paths": {},
+        "components": {
+            "schemas": {
+                "Shape": {
+                    "oneOf": [
+                        { "$ref": "#/components/schemas/Circle" },
+                        { "$ref": "#/components/schemas/Square" }
+                    ],
+                    "discriminator": { "propertyName": "kind" }
+                },
+                "Circle": { "type": "object", "properties": { "r": { "type": "number" } } },
+                "Square": { "type": "object", "properties": { "a": { "type": "number" } } },
+                "Pet": {
+                    "type": "object",
+                    "discriminator": {
+                        "propertyName": "petType",
+                        "mapping": { "dog": "#/components/schemas/Dog" }
+                    },
+                    "properties": {
+                        "petType": { "type": "string" },
+                        "name": { "type": "string" }
+                    },
+                    "required": ["petType", "name"]
+                },
+                "Cat": {
+                    "allOf": [
+                        { "$ref": "#/components/schemas/Pet" },
+                        {
+                            "type": "object",
+                            "properties": { "meow": { "type": "boolean" } },
+                            "required": ["meow"]
+                        }
+                    ]
+                },
+                "Dog": {
+                    "allOf": [
+                        { "$ref": "#/components/schemas/Pet" },
+                        { "type": "object", "properties": { "bark": { "type": "integer" } } }
+                    ]
+                }
+            }
+        }
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+    let IAST::Object(shape) = &intermediate.find_scheme("Shape").expect("Shape").obj else {
+        panic!("Shape is an object");
+    };
+    let AlgType::DiscriminatedSum(discrimination) = &shape.value else {
+        panic!("Shape is discriminated");
+    };
+    assert_eq!(
+        discrimination.map.keys().copied().collect::<Vec<_>>(),
+        ["Circle", "Square"]
+    );
+    let IAST::Object(pet) = &intermediate.find_scheme("Pet").expect("Pet").obj else {
+        panic!("Pet is an object");
+    };
+    let AlgType::Polymorphic(props, discrimination) = &pet.value else {
+        panic!("Pet is polymorphic");
+    };
+    assert!(props.contains_key("name"));
+    assert_eq!(
+        discrimination.map.keys().copied().collect::<Vec<_>>(),
+        ["Cat", "dog"]
+    );
+    assert_eq!(intermediate.find_scheme("Cat").unwrap().parent, Some("Pet"));
+
+    let files = generate(spec_json);
+    assert_contains(
+        file(&files, "schemes/Shape.dart"),
+        "'Circle' => BEAMShapeCircleModel_(BEAMCircleModel.fromJson(json)),",
+        "the implicit mapping uses the scheme names",
+    );
+    let pet = file(&files, "schemes/Pet.dart");
+    assert_contains(
+        pet,
+        "sealed class BEAMPetModel implements",
+        "the base is sealed",
+    );
+    assert_contains(
+        pet,
+        "  String get petType;\n",
+        "the base leaves the discriminator open",
+    );
+    assert_contains(
+        pet,
+        "    r'dog' => BEAMDogModel.fromJson(json),\n",
+        "the base picks the subclass",
+    );
+    assert_contains(
+        pet,
+        "class BEAMCatModel extends BEAMPetModel {",
+        "subclasses live next to the base",
+    );
+    assert_contains(
+        pet,
+        "  String get petType => r'Cat';\n",
+        "subclasses name themselves",
+    );
+    assert_contains(
+        pet,
+        "    required super.name,\n    required this.meow,\n",
+        "subclasses pass inherited properties on",
+    );
+    assert_contains(
+        pet,
+        "    'petType': petType,\n",
+        "the discriminator is serialized",
+    );
+    assert_eq!(file(&files, "schemes/Cat.dart"), "export 'Pet.dart';\n");
+}
+
+/// A `type` listing several types is a union of them, decoded by the type of
+/// the JSON value instead of by trying each variant.
+#[test]
+fn multi_type_schemas_become_unions() {
+    use openapi_parser::parse::intermediate::{
+        self, AlgType, AnnotatedObj, IntermediateArgs, IAST,
+    };
+
+    let spec_json = r##"{
+        "openapi": "3.1.0",
+        "info": { "title": "t", "version": "1" },
+        "paths": {},
+        "components": {
+            "schemas": {
+                "Id": { "type": ["string", "integer", "number"] },
+                "Payload": {
+                    "type": ["object", "array", "null"],
+                    "properties": { "a": { "type": "string" } },
+                    "items": { "type": "integer" }
+                },
+                "Mixed": {
+                    "oneOf": [
+                        { "type": "object", "properties": { "a": { "type": "string" } } },
+                        { "type": "object", "properties": { "b": { "type": "string" } } }
+                    ]
+                }
+            }
+        }
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+    let IAST::Object(payload) = &intermediate.find_scheme("Payload").expect("Payload").obj else {
+        panic!("Payload is an object");
+    };
+    let AlgType::Sum(variants) = &payload.value else {
+        panic!("Payload is a sum");
+    };
+    assert!(payload.nullable);
+    assert_eq!(
+        variants.iter().map(|v| v.name.as_str()).collect::<Vec<_>>(),
+        ["object", "array"]
+    );
+    assert!(matches!(
+        &variants[0].typ,
+        IAST::Object(AnnotatedObj {
+            value: AlgType::Product(..),
+            ..
+        })
+    ));
+
+    let files = generate(spec_json);
+    let id = file(&files, "schemes/Id.dart");
+    assert_contains(
+        id,
+        "factory BEAMIdModel.fromJson(dynamic json) => switch (json) {\n\t\tString _ => BEAMIdstringModel_.fromJson(json),\n\t\tint _ => BEAMIdintegerModel_.fromJson(json),\n\t\tnum _ => BEAMIdnumberModel_.fromJson(json),\n",
+        "variants are picked by the JSON type, integers before numbers",
+    );
+    assert_not_contains(id, "BEAMUnionParseMultiError", "no trial parsing");
+    assert_contains(
+        file(&files, "schemes/Payload.dart"),
+        "\t\tMap _ => BEAMPayloadNonNullobjectModel_.fromJson(json),\n\t\tList _ => BEAMPayloadNonNullarrayModel_.fromJson(json),\n",
+        "objects and arrays are told apart",
+    );
+    assert_contains(
+        file(&files, "schemes/Mixed.dart"),
+        "BEAMUnionParseMultiError",
+        "variants of the same JSON type are still tried in turn",
+    );
+}
+
+/// Security schemes become a typed credentials provider, and each endpoint
+/// passes on its effective requirements: the global default, its own
+/// override, or none at all for `security: []`.
+#[test]
+fn security_schemes_and_requirements() {
+    use openapi_parser::parse::intermediate::{self, IntermediateArgs, SecuritySchemeKind};
+    let spec_json = r##"{
+        "openapi": "3.0.0",
+        "info": { "title": "t", "version": "1" },
+        "security": [{ "api_key": [] }],
+        "paths": {
+            "/pets": {
+                "get": { "responses": { "200": { "description": "ok" } } },
+                "post": {
+                    "security": [{ "petstore_auth": ["write:pets"] }, { "basic": [], "bearer": [] }],
+                    "responses": { "200": { "description": "ok" } }
+                }
+            },
+            "/health": {
+                "get": { "security": [], "responses": { "200": { "description": "ok" } } }
+            }
+        },
+        "components": {
+            "securitySchemes": {
+                "api_key": { "type": "apiKey", "name": "X-API-Key", "in": "header" },
+                "query_key": { "type": "apiKey", "name": "token", "in": "query" },
+                "basic": { "type": "http", "scheme": "basic" },
+                "bearer": { "type": "http", "scheme": "bearer", "bearerFormat": "JWT" },
+                "petstore_auth": {
+                    "type": "oauth2",
+                    "flows": {
+                        "implicit": {
+                            "authorizationUrl": "https://example.com/auth",
+                            "scopes": { "write:pets": "modify pets" }
+                        }
+                    }
+                }
+            }
+        }
+    }"##;
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+    assert!(intermediate
+        .security_schemes
+        .iter()
+        .any(|s| s.name == "petstore_auth"
+            && matches!(&s.kind, SecuritySchemeKind::OAuth2 { scopes } if scopes.contains_key("write:pets"))));
+    let route = |path: &str| {
+        intermediate
+            .routes
+            .iter()
+            .find(|r| r.path == path)
+            .expect("route exists")
+    };
+    let pets = route("/pets");
+    assert_eq!(pets.endpoints[0].security.len(), 1, "global default");
+    assert!(pets.endpoints[0].security[0].contains_key("api_key"));
+    assert_eq!(pets.endpoints[1].security.len(), 2, "operation override");
+    assert!(route("/health").endpoints[0].security.is_empty());
+
+    let files = generate(spec_json);
+    let endpoints = file(&files, "endpoints/endpoints.dart");
+    assert_contains(
+        endpoints,
+        "abstract interface class BEAMCredentialsProvider {",
+        "credentials provider",
+    );
+    assert_contains(
+        endpoints,
+        "FutureOr<BEAMBasicCredentials?> basic();",
+        "basic credentials",
+    );
+    assert_contains(
+        endpoints,
+        "FutureOr<String?> petstore_auth(List<String> scopes);",
+        "oauth2 tokens are requested for scopes",
+    );
+    assert_contains(
+        endpoints,
+        "credentials.query[r'token'] = value;",
+        "query api keys",
+    );
+    assert_contains(
+        endpoints,
+        "credentials.headers['Authorization'] = 'Bearer $value';",
+        "bearer tokens",
+    );
+    let pets = file(&files, "endpoints/routes/_pets.dart");
+    assert_contains(pets, "security: const [{r'api_key': []}]", "global default");
+    assert_contains(
+        pets,
+        "security: const [{r'petstore_auth': [r'write:pets']}, {r'basic': [], r'bearer': []}]",
+        "operation override",
+    );
+    assert_not_contains(
+        file(&files, "endpoints/routes/_health.dart"),
+        "security:",
+        "public endpoints send no credentials",
+    );
+}
+
+/// `servers` become `BEAMServer` constructors taking their variables, and
+/// endpoints whose path or operation has servers of its own pass them on.
+#[test]
+fn servers_and_server_variables() {
+    use openapi_parser::parse::intermediate::{self, IntermediateArgs};
+    let spec_json = r##"{
+        "openapi": "3.0.0",
+        "info": { "title": "t", "version": "1" },
+        "servers": [
+            {
+                "url": "https://{region}.example.com:{port}/v1",
+                "description": "Production",
+                "variables": {
+                    "region": { "default": "eu", "enum": ["eu", "us"] },
+                    "port": { "default": "443" }
+                }
+            },
+            { "url": "http://localhost:8080" }
+        ],
+        "paths": {
+            "/pets": {
+                "servers": [{ "url": "https://pets.example.com", "description": "Pets" }],
+                "get": { "responses": { "200": { "description": "ok" } } }
+            },
+            "/uploads": {
+                "get": { "responses": { "200": { "description": "ok" } } },
+                "post": {
+                    "servers": [{ "url": "https://uploads.example.com", "description": "Uploads" }],
+                    "responses": { "200": { "description": "ok" } }
+                }
+            }
+        },
+        "components": {}
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+    assert_eq!(intermediate.servers.len(), 2);
+    assert_eq!(
+        intermediate.servers[0]
+            .variables
+            .iter()
+            .map(|v| (v.name, v.default, v.values.len()))
+            .collect::<Vec<_>>(),
+        [("port", "443", 0), ("region", "eu", 2)]
+    );
+    let route = |path: &str| {
+        intermediate
+            .routes
+            .iter()
+            .find(|r| r.path == path)
+            .expect("route exists")
+    };
+    assert_eq!(
+        route("/pets").endpoints[0].servers[0].url,
+        "https://pets.example.com"
+    );
+    let uploads = route("/uploads");
+    assert!(uploads.endpoints[0].servers.is_empty(), "the API's servers");
+    assert_eq!(
+        uploads.endpoints[1].servers[0].url,
+        "https://uploads.example.com"
+    );
+
+    let files = generate(spec_json);
+    let endpoints = file(&files, "endpoints/endpoints.dart");
+    assert_contains(
+        endpoints,
+        "factory BEAMServer.production({String port = r'443', BEAMServerProductionRegion region = BEAMServerProductionRegion.t_eu}) => BEAMServer('https://${region.toJson()}.example.com:${port}/v1');",
+        "variables become typed parameters",
+    );
+    assert_contains(
+        endpoints,
+        "factory BEAMServer.server1() => BEAMServer('http://localhost:8080');",
+        "servers without description",
+    );
+    assert_contains(
+        endpoints,
+        "static List<BEAMServer> get all => [BEAMServer.production(), BEAMServer.server1()];",
+        "the API's own servers",
+    );
+    assert_contains(
+        file(&files, "endpoints/routes/_pets.dart"),
+        "servers: [BEAMServer.pets()]",
+        "path-level servers",
+    );
+    let uploads = file(&files, "endpoints/routes/_uploads.dart");
+    assert_contains(
+        uploads,
+        "servers: [BEAMServer.uploads()]",
+        "operation-level servers",
+    );
+    assert_eq!(
+        uploads.matches("servers:").count(),
+        1,
+        "only the overriding operation"
+    );
+}
+
+/// Servers whose descriptions give the same constructor name, or one taken
+/// by the fallback of another, get a numeric suffix.
+#[test]
+fn server_constructor_names_are_unique() {
+    let spec_json = r##"{
+        "openapi": "3.0.0",
+        "info": { "title": "t", "version": "1" },
+        "servers": [
+            { "url": "https://a.example.com", "description": "Staging" },
+            { "url": "https://b.example.com", "description": "staging!" },
+            { "url": "https://c.example.com" },
+            { "url": "https://d.example.com", "description": "Server 2" }
+        ],
+        "paths": {},
+        "components": {}
+    }"##;
+    let files = generate(spec_json);
+    let endpoints = file(&files, "endpoints/endpoints.dart");
+    assert_contains(
+        endpoints,
+        "static List<BEAMServer> get all => [BEAMServer.staging(), BEAMServer.staging2(), BEAMServer.server2(), BEAMServer.server22()];",
+        "every server has its own constructor",
+    );
+}
+
+/// Declared response headers are parsed with their schemas and decoded into
+/// a typed headers class, returned by a `WithHeaders` variant of the
+/// endpoint method.
+#[test]
+fn response_headers_are_typed() {
+    use openapi_parser::parse::intermediate::{self, IntermediateArgs};
+    let spec_json = r##"{
+        "openapi": "3.0.0",
+        "info": { "title": "t", "version": "1" },
+        "paths": {
+            "/pets": {
+                "get": {
+                    "responses": {
+                        "200": {
+                            "description": "ok",
+                            "headers": {
+                                "X-Total-Count": { "schema": { "type": "integer" } },
+                                "ETag": { "$ref": "#/components/headers/ETag" },
+                                "Content-Type": { "schema": { "type": "string" } }
+                            },
+                            "content": { "application/json": { "schema": { "type": "string" } } }
+                        }
+                    }
+                },
+                "post": {
+                    "responses": {
+                        "201": {
+                            "description": "created",
+                            "headers": {
+                                "Location": { "required": true, "schema": { "type": "string", "format": "uri" } }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        "components": {
+            "headers": { "ETag": { "schema": { "type": "string" } } }
+        }
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+    let endpoints = &intermediate.routes[0].endpoints;
+    let names = |i: usize, code: &str| {
+        endpoints[i].response_headers[&code.to_string()]
+            .iter()
+            .map(|h| (h.name, h.required))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(0, "200"), [("ETag", false), ("X-Total-Count", false)]);
+    assert_eq!(
+        names(1, "201"),
+        [("Location", true)],
+        "responses without content"
+    );
+
+    let files = generate(spec_json);
+    let pets = file(&files, "endpoints/routes/_pets.dart");
+    assert_contains(
+        pets,
+        "BEAMCachedResponse<BEAMWithHeaders<String, BEAM_petsMethods_getResponseHeaders>> getWithHeaders()",
+        "headers variant of the method",
+    );
+    assert_contains(
+        pets,
+        "statusCodeRef: statusCodeRef).then((json) => BEAMWithHeaders(json, BEAM_petsMethods_getResponseHeaders(statusCodeRef.headers ?? const {})));",
+        "headers are taken from the ref",
+    );
+    assert_contains(
+        pets,
+        "int? get X_Total_Count => switch (raw[r'x-total-count']) {\n    final v? => int.parse(v),",
+        "typed header",
+    );
+    assert_contains(
+        pets,
+        "Uri? get Location => switch (raw[r'location']) {\n    final v? => BEAMFormats.uriFromJson(v),",
+        "formatted header",
+    );
+    assert_contains(
+        file(&files, "endpoints/endpoints.dart"),
+        "abstract interface class BeamResponseHeadersAwareHandler",
+        "handler capability",
+    );
+}
+
+/// Webhooks and the callbacks of operations become inbound operations;
+/// `BEAMInbound.decodeWebhook` turns a request of a webhook, by name, and
+/// `BEAMInbound.decodeCallback` one of a callback, by its operation and
+/// name, into its typed event.
+#[test]
+fn webhooks_and_callbacks_are_decoded_by_a_dispatcher() {
+    use openapi_parser::parse::intermediate::{self, InboundOrigin, IntermediateArgs, Method};
+    let spec_json = r##"{
+        "openapi": "3.1.0",
+        "info": { "title": "t", "version": "1" },
+        "security": [{ "key": [] }],
+        "webhooks": {
+            "newPet": {
+                "post": {
+                    "requestBody": {
+                        "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } } }
+                    },
+                    "responses": { "200": { "description": "ok" } }
+                }
+            }
+        },
+        "paths": {
+            "/subscribe": {
+                "post": {
+                    "responses": { "201": { "description": "ok" } },
+                    "callbacks": {
+                        "onEvent": {
+                            "{$request.body#/callbackUrl}/events": {
+                                "post": {
+                                    "requestBody": {
+                                        "content": {
+                                            "application/json": {
+                                                "schema": { "type": "array", "items": { "$ref": "#/components/schemas/Pet" } }
+                                            }
+                                        }
+                                    },
+                                    "responses": { "200": { "description": "ok" } }
+                                }
+                            }
+                        },
+                        "onDone": { "$ref": "#/components/callbacks/Done" }
+                    }
+                }
+            }
+        },
+        "components": {
+            "schemas": { "Pet": { "type": "object", "properties": { "name": { "type": "string" } } } },
+            "callbacks": {
+                "Done": {
+                    "https://example.com/done": {
+                        "put": { "responses": { "200": { "description": "ok" } } }
+                    }
+                }
+            }
+        }
+    }"##;
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+    let inbound = &intermediate.inbound;
+    assert_eq!(
+        inbound.iter().map(|i| i.name).collect::<Vec<_>>(),
+        ["newPet", "onDone", "onEvent"]
+    );
+    assert!(matches!(inbound[0].origin, InboundOrigin::Webhook));
+    assert!(matches!(
+        inbound[2].origin,
+        InboundOrigin::Callback {
+            path: "/subscribe",
+            method: Method::Post,
+            expression: "{$request.body#/callbackUrl}/events",
+        }
+    ));
+    assert!(inbound[0].endpoint.request.is_some());
+    assert!(inbound.iter().all(|i| i.endpoint.security.is_empty()));
+
+    let files = generate(spec_json);
+    assert_contains(
+        file(&files, "endpoints/endpoints.dart"),
+        "export 'inbound.dart';",
+        "the dispatcher is part of the endpoints",
+    );
+    let dispatcher = file(&files, "endpoints/inbound.dart");
+    assert_contains(
+        dispatcher,
+        "class BEAMWebhook_newPet_post extends BEAMInboundEvent {\n  final BEAMPetModel payload;",
+        "typed webhook payload",
+    );
+    assert_contains(
+        dispatcher,
+        "if (method == BEAMRequestMethod.post && name == r'newPet') {\n      return BEAMWebhook_newPet_post(BEAMPetModel.fromJson(body));",
+        "webhooks are matched by name",
+    );
+    assert_not_contains(
+        dispatcher,
+        "p.endsWith(r'/newPet')",
+        "webhooks aren't matched by path",
+    );
+    assert_contains(
+        dispatcher,
+        "if (method == BEAMRequestMethod.post && path == r'/subscribe' && operation == BEAMRequestMethod.post && name == r'onEvent') {\n      return BEAMCallback_subscribe_post_onEvent_post((body as List).map((e) => BEAMPetModel.fromJson(e)).toList());",
+        "callbacks are matched by their operation and name",
+    );
+    assert_contains(
+        dispatcher,
+        "if (method == BEAMRequestMethod.put && path == r'/subscribe' && operation == BEAMRequestMethod.post && name == r'onDone') {\n      return BEAMCallback_subscribe_post_onDone_put();",
+        "referenced callbacks without payload",
+    );
+}
+
+/// Callbacks sent to bare runtime expressions, with no path of their own,
+/// are each reachable through their name.
+#[test]
+fn callbacks_to_bare_expressions_are_told_apart() {
+    let spec_json = r##"{
+        "openapi": "3.1.0",
+        "info": { "title": "t", "version": "1" },
+        "paths": {
+            "/subscribe": {
+                "post": {
+                    "responses": { "201": { "description": "ok" } },
+                    "callbacks": {
+                        "onStart": {
+                            "{$request.body#/callbackUrl}": {
+                                "post": { "responses": { "200": { "description": "ok" } } }
+                            }
+                        },
+                        "onStop": {
+                            "{$request.body#/stopUrl}": {
+                                "post": { "responses": { "200": { "description": "ok" } } }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        "components": {}
+    }"##;
+    let files = generate(spec_json);
+    let dispatcher = file(&files, "endpoints/inbound.dart");
+    for name in ["onStart", "onStop"] {
+        assert_contains(
+            dispatcher,
+            &format!(
+                "if (method == BEAMRequestMethod.post && path == r'/subscribe' && operation == BEAMRequestMethod.post && name == r'{}') {{\n      return BEAMCallback_subscribe_post_{}_post();",
+                name, name
+            ),
+            "each callback has its own condition",
+        );
+    }
+    assert_not_contains(dispatcher, "endsWith", "callbacks aren't matched by path");
+}
+
+/// Swagger 2.0 documents are converted to OpenAPI 3 up front: definitions,
+/// shared parameters and responses become components, body and form
+/// parameters become request bodies in the media types the operation
+/// consumes, and security definitions become security schemes.
+#[test]
+fn swagger2_documents_are_converted() {
+    use openapi_parser::parse::swagger2;
+
+    let spec_json = r##"{
+        "swagger": "2.0",
+        "info": {"title": "t", "version": "1"},
+        "host": "api.example.com",
+        "basePath": "/v1",
+        "schemes": ["https"],
+        "consumes": ["application/json"],
+        "produces": ["application/json"],
+        "securityDefinitions": {
+            "key": {"type": "apiKey", "name": "X-Key", "in": "header"},
+            "basic": {"type": "basic"},
+            "oauth": {
+                "type": "oauth2",
+                "flow": "accessCode",
+                "authorizationUrl": "https://auth.example.com",
+                "tokenUrl": "https://token.example.com",
+                "scopes": {"read": "read pets"}
+            }
+        },
+        "security": [{"key": []}],
+        "definitions": {
+            "Pet": {
+                "type": "object",
+                "required": ["name"],
+                "properties": {
+                    "name": {"type": "string"},
+                    "owner": {"$ref": "#/definitions/Owner"}
+                }
+            },
+            "Owner": {"type": "object", "properties": {"id": {"type": "integer"}}}
+        },
+        "parameters": {
+            "limit": {"name": "limit", "in": "query", "type": "integer"}
+        },
+        "responses": {
+            "NotFound": {"description": "missing", "schema": {"$ref": "#/definitions/Owner"}}
+        },
+        "paths": {
+            "/pets": {
+                "get": {
+                    "parameters": [{"$ref": "#/parameters/limit"}],
+                    "responses": {
+                        "200": {
+                            "description": "ok",
+                            "schema": {"type": "array", "items": {"$ref": "#/definitions/Pet"}},
+                            "headers": {"X-Total": {"type": "integer"}}
+                        },
+                        "404": {"$ref": "#/responses/NotFound"}
+                    }
+                },
+                "post": {
+                    "security": [{"basic": []}],
+                    "parameters": [
+                        {"name": "pet", "in": "body", "required": true, "schema": {"$ref": "#/definitions/Pet"}}
+                    ],
+                    "responses": {"201": {"description": "ok", "schema": {"$ref": "#/definitions/Pet"}}}
+                }
+            },
+            "/pets/{id}/photo": {
+                "parameters": [{"name": "id", "in": "path", "required": true, "type": "string"}],
+                "post": {
+                    "consumes": ["multipart/form-data"],
+                    "parameters": [
+                        {"name": "file", "in": "formData", "type": "file", "required": true},
+                        {"name": "note", "in": "formData", "type": "string"}
+                    ],
+                    "responses": {"200": {"description": "ok"}}
+                }
+            }
+        }
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    assert!(swagger2::is_swagger2(&raw));
+    let converted = swagger2::to_openapi3(&raw).expect("converts");
+    assert_eq!(converted["servers"][0]["url"], "https://api.example.com/v1");
+    assert_eq!(
+        converted["components"]["schemas"]["Pet"]["properties"]["owner"]["$ref"],
+        "#/components/schemas/Owner"
+    );
+    assert_eq!(
+        converted["components"]["securitySchemes"]["oauth"]["flows"]["authorizationCode"]
+            ["tokenUrl"],
+        "https://token.example.com"
+    );
+    let photo = &converted["paths"]["/pets/{id}/photo"]["post"]["requestBody"];
+    assert_eq!(
+        photo["content"]["multipart/form-data"]["schema"]["properties"]["file"]["format"],
+        "binary"
+    );
+
+    let files = generate(spec_json);
+    let pets = file(&files, "endpoints/routes/_pets.dart");
+    assert_contains(
+        pets,
+        "BEAMCachedResponse<BEAMPetModel> post( {required BEAMPetModel body})",
+        "body parameters become JSON request bodies",
+    );
+    assert_contains(
+        pets,
+        "security: const [{r'basic': []}]",
+        "security definitions become security schemes",
+    );
+    assert_contains(
+        pets,
+        "int? get X_Total",
+        "response headers keep their types",
+    );
+    assert_contains(
+        file(&files, "endpoints/routes/_petsMethods/get.resp.schema.dart"),
+        "404 => BEAM_petsMethods_getResponse_404Model_.fromJson(json),",
+        "shared responses are referenced",
+    );
+    assert_contains(
+        file(&files, "endpoints/routes/_pets__id__photo.dart"),
+        "contentType: 'multipart/form-data'",
+        "form parameters with a file become a multipart body",
+    );
+    assert_contains(
+        file(&files, "endpoints/endpoints.dart"),
+        "factory BEAMServer.server0() => BEAMServer('https://api.example.com/v1');",
+        "host, basePath and schemes become servers",
+    );
+}
+
+/// Swagger 2.0 operation parameters replace path parameters of the same
+/// name and location only, shared body parameters follow the media types
+/// the operation consumes, and `tsv` arrays are reported.
+#[test]
+fn swagger2_operations_override_their_path() {
+    use openapi_parser::parse::swagger2;
+
+    let spec_json = r##"{
+        "swagger": "2.0",
+        "info": {"title": "t", "version": "1"},
+        "consumes": ["application/json"],
+        "definitions": {"Pet": {"type": "object", "properties": {"name": {"type": "string"}}}},
+        "parameters": {
+            "pet": {"name": "pet", "in": "body", "schema": {"$ref": "#/definitions/Pet"}}
+        },
+        "paths": {
+            "/pets/{id}": {
+                "parameters": [
+                    {"name": "id", "in": "path", "required": true, "type": "string"},
+                    {"name": "note", "in": "formData", "type": "string"}
+                ],
+                "post": {
+                    "parameters": [{"name": "note", "in": "query", "type": "integer"}],
+                    "responses": {"204": {"description": "ok"}}
+                },
+                "put": {
+                    "consumes": ["application/xml"],
+                    "parameters": [
+                        {"name": "tags", "in": "query", "type": "array", "items": {"type": "string"}, "collectionFormat": "tsv"},
+                        {"$ref": "#/parameters/pet"}
+                    ],
+                    "responses": {"204": {"description": "ok"}}
+                }
+            }
+        }
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let converted = swagger2::to_openapi3(&raw).expect("converts");
+    let post = &converted["paths"]["/pets/{id}"]["post"];
+    assert_eq!(post["parameters"][0]["in"], "query");
+    assert_eq!(
+        post["requestBody"]["content"]["application/x-www-form-urlencoded"]["schema"]["properties"]
+            ["note"]["type"],
+        "string"
+    );
+    let put = &converted["paths"]["/pets/{id}"]["put"];
+    assert_eq!(
+        put["requestBody"]["content"]["application/xml"]["schema"]["$ref"],
+        "#/components/schemas/Pet"
+    );
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("build tokio runtime");
+    let generated = rt
+        .block_on(openapi_parser::generate_dart(spec_json, Default::default()))
+        .expect("generation succeeds");
+    let unsupported: Vec<_> = generated
+        .diagnostics
+        .iter()
+        .filter(|d| d.code == "unsupported-param-style")
+        .map(|d| d.pointer.as_str())
+        .collect();
+    assert_eq!(unsupported, ["#/paths/~1pets~1{id}/put/parameters/0"]);
+}
+
+/// YAML specs go through the same pipeline as JSON ones, the format being
+/// sniffed from the content, and syntax errors report where they are.
+#[test]
+fn yaml_specs_are_accepted() {
+    use openapi_parser::parse::{read_spec, SpecFormat};
+
+    let spec_yaml = r##"
+openapi: 3.0.0
+info:
+  title: t
+  version: "1"
+paths:
+  /pets:
+    get:
+      responses:
+        200:
+          description: ok
+          content:
+            application/json:
+              schema:
+                $ref: '#/components/schemas/Pet'
+components:
+  schemas:
+    Pet:
+      type: object
+      properties:
+        name: {type: string}
+        mood:
+          type: string
+          enum: [yes, no]
+"##;
+    assert_eq!(SpecFormat::sniff(spec_yaml), SpecFormat::Yaml);
+    assert_eq!(
+        SpecFormat::sniff(" {\"openapi\": \"3.0.0\"}"),
+        SpecFormat::Json
+    );
+    assert_eq!(
+        SpecFormat::from_content_type("application/x-yaml; charset=utf-8"),
+        Some(SpecFormat::Yaml)
+    );
+    assert_eq!(
+        SpecFormat::from_path("https://example.com/openapi.yml?v=2"),
+        Some(SpecFormat::Yaml)
+    );
+
+    let files = generate(spec_yaml);
+    assert_contains(
+        file(&files, "endpoints/routes/_pets.dart"),
+        "BEAMCachedResponse<BEAMPetModel> get()",
+        "integer status codes are read as keys",
+    );
+    assert_contains(
+        file(&files, "schemes/Pet.dart"),
+        "BEAMPet_mood? mood;",
+        "YAML 1.2 keeps `yes` and `no` as strings",
+    );
+
+    let error = read_spec("openapi: 3.0.0\ninfo:\n  title: [t\n  version: 1\n")
+        .expect_err("unterminated flow sequence");
+    assert_contains(&error, "line 4 column 10", "YAML errors are located");
+    let error = read_spec("{\"openapi\": \"3.0.0\",\n \"info\": }").expect_err("missing value");
+    assert_contains(&error, "line 2 column 10", "JSON errors are located");
+}
+
+/// Specs can be loaded from local files, given as paths or `file://` URLs,
+/// and from stdin as well as over HTTP, with the format told by the
+/// extension.
+#[test]
+fn specs_are_loaded_from_files() {
+    use openapi_parser::parse::source::{SpecLoader, SpecSource};
+    use openapi_parser::parse::SpecFormat;
+
+    assert_eq!(SpecSource::parse("-"), Ok(SpecSource::Stdin));
+    assert_eq!(
+        SpecSource::parse("https://example.com/openapi.json"),
+        Ok(SpecSource::Url("https://example.com/openapi.json".into()))
+    );
+    assert_eq!(
+        SpecSource::parse("specs/openapi.yaml"),
+        Ok(SpecSource::File("specs/openapi.yaml".into()))
+    );
+    assert_eq!(
+        SpecSource::parse("file:///tmp/open%20api.yaml"),
+        Ok(SpecSource::File("/tmp/open api.yaml".into()))
+    );
+
+    let path = std::env::temp_dir().join(format!("spec-{}.yml", std::process::id()));
+    std::fs::write(
+        &path,
+        "openapi: 3.0.0\ninfo: {title: t, version: '1'}\npaths: {}\ncomponents:\n  schemas:\n    Pet: {type: object, properties: {name: {type: string}}}\n",
+    )
+    .expect("write spec");
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("build tokio runtime");
+    let loaded = rt.block_on(SpecLoader::default().load(&SpecSource::File(path.clone())));
+    std::fs::remove_file(&path).expect("remove spec");
+    let loaded = loaded.expect("spec loads");
+    assert_eq!(loaded.format, Some(SpecFormat::Yaml));
+    let (spec, _) = loaded.read().expect("spec reads");
+    assert!(spec
+        .components
+        .expect("components")
+        .schemas
+        .contains_key("Pet"));
+
+    let missing =
+        rt.block_on(SpecLoader::default().load(&SpecSource::File("/nonexistent.json".into())));
+    assert!(missing
+        .expect_err("missing file")
+        .contains("/nonexistent.json"));
+}
+
+/// The credentials of the root spec are only sent along to documents it
+/// references on the same origin, not to other hosts, and remote specs
+/// can't reference local files.
+#[test]
+fn credentials_stay_on_the_origin_of_the_spec() {
+    use openapi_parser::parse::source::{SpecLoader, SpecSource};
+
+    let root = SpecSource::Url("https://api.example.com/v1/openapi.yaml".into());
+    let loader = SpecLoader::new(Some(("user".into(), Some("pass".into()))));
+    let same = root.join("./schemas/pet.yaml").expect("joins");
+    assert!(root.same_origin(&same));
+    assert!(loader.scoped_to(&root, &same).basic_auth.is_some());
+    for other in [
+        "https://evil.example.com/pet.yaml",
+        "http://api.example.com/v1/pet.yaml",
+        "https://api.example.com:8443/v1/pet.yaml",
+    ] {
+        let other = root.join(other).expect("joins");
+        assert!(!root.same_origin(&other), "{} is another origin", other);
+        assert!(loader.scoped_to(&root, &other).basic_auth.is_none());
+    }
+    let file = SpecSource::File("pet.yaml".into());
+    assert!(loader.scoped_to(&root, &file).basic_auth.is_none());
+
+    let local = root.join("file:///etc/passwd");
+    assert!(local
+        .expect_err("remote specs can't read local files")
+        .contains("/etc/passwd"));
+    assert!(SpecSource::File("openapi.yaml".into())
+        .join("file:///etc/pet.yaml")
+        .is_ok());
+}
+
+/// Specs split across documents are bundled into one: the targets of
+/// relative `$ref`s are hoisted into the components, path items are
+/// inlined, and names taken already are deduplicated and reported.
+#[test]
+fn external_refs_are_bundled() {
+    use openapi_parser::parse::bundle::bundle;
+    use openapi_parser::parse::source::{SpecLoader, SpecSource};
+
+    let dir = std::env::temp_dir().join(format!("bundle-{}", std::process::id()));
+    let files = [
+        (
+            "openapi.yaml",
+            "openapi: 3.0.0\ninfo: {title: t, version: '1'}\npaths:\n  /users:\n    $ref: ./paths/users.yaml\ncomponents:\n  schemas:\n    Error: {type: object, properties: {message: {type: string}}}\n    Address: {type: object, properties: {street: {type: string}}}\n",
+        ),
+        (
+            "paths/users.yaml",
+            "get:\n  parameters:\n    - $ref: '../schemas/common.yaml#/Limit'\n  responses:\n    200:\n      description: ok\n      content:\n        application/json:\n          schema: {type: array, items: {$ref: '../schemas/user.yaml#/User'}}\n    default:\n      description: error\n      content:\n        application/json:\n          schema: {$ref: '../schemas/common.yaml#/Error'}\n",
+        ),
+        (
+            "schemas/user.yaml",
+            "User:\n  type: object\n  properties:\n    address: {$ref: '#/Address'}\n    friends: {type: array, items: {$ref: '#/User'}}\nAddress: {type: object, properties: {street: {type: string}}}\n",
+        ),
+        (
+            "schemas/common.yaml",
+            "Limit: {name: limit, in: query, schema: {type: integer}}\nError: {type: object, properties: {code: {type: integer}}}\n",
+        ),
+    ];
+    for (path, content) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+        std::fs::write(path, content).expect("write document");
+    }
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("build tokio runtime");
+    let bundled = rt.block_on(bundle(
+        &SpecLoader::default(),
+        &SpecSource::File(dir.join("openapi.yaml")),
+    ));
+    std::fs::remove_dir_all(&dir).expect("remove documents");
+    let bundled = bundled.expect("bundles");
+
+    let document = &bundled.document;
+    let get = &document["paths"]["/users"]["get"];
+    assert_eq!(
+        get["parameters"][0]["$ref"],
+        "#/components/parameters/Limit"
+    );
+    assert_eq!(
+        get["responses"]["default"]["content"]["application/json"]["schema"]["$ref"],
+        "#/components/schemas/Error2"
+    );
+    let user = &document["components"]["schemas"]["User"];
+    assert_eq!(
+        user["properties"]["friends"]["items"]["$ref"],
+        "#/components/schemas/User"
+    );
+    assert_eq!(
+        user["properties"]["address"]["$ref"],
+        "#/components/schemas/Address"
+    );
+
+    let collisions: Vec<_> = bundled
+        .collisions
+        .iter()
+        .map(|c| (c.name.as_str(), c.renamed_to.as_deref()))
+        .collect();
+    assert_eq!(collisions, [("Address", None), ("Error", Some("Error2"))]);
+    assert_contains(
+        &bundled.collisions[1].to_string(),
+        "common.yaml#/Error differs from the one of",
+        "collisions name both components",
+    );
+
+    let files = generate(&serde_json::to_string(document).expect("serializes"));
+    assert_contains(
+        file(&files, "schemes/User.dart"),
+        "List<BEAMUserModel>? friends;",
+        "references within a document resolve",
+    );
+    assert_contains(
+        file(&files, "schemes/Error2.dart"),
+        "int? code;",
+        "the renamed component is generated",
+    );
+}
+
+/// Hoisted components are named after identifier-safe versions of their
+/// pointer, and references of the root to itself by its path become local
+/// ones, even when it references no other document.
+#[test]
+fn bundled_names_are_sanitized() {
+    use openapi_parser::parse::bundle::bundle;
+    use openapi_parser::parse::source::{SpecLoader, SpecSource};
+
+    let dir = std::env::temp_dir().join(format!("bundle-names-{}", std::process::id()));
+    let files = [
+        (
+            "openapi.yaml",
+            "openapi: 3.0.0\ninfo: {title: t, version: '1'}\npaths: {}\ncomponents:\n  schemas:\n    user_profile: {type: string}\n    Self: {$ref: './openapi.yaml#/components/schemas/user_profile'}\n    Profile: {$ref: './defs.yaml#/user~1profile'}\n    Other: {$ref: './defs.yaml#/user%20profile'}\n",
+        ),
+        (
+            "defs.yaml",
+            "user/profile: {type: object, properties: {name: {type: string}}}\nuser profile: {type: integer}\n",
+        ),
+        (
+            "single.yaml",
+            "openapi: 3.0.0\ninfo: {title: t, version: '1'}\npaths: {}\ncomponents:\n  schemas:\n    Pet: {type: string}\n    Alias: {$ref: 'single.yaml#/components/schemas/Pet'}\n",
+        ),
+    ];
+    for (path, content) in files {
+        std::fs::create_dir_all(&dir).expect("create dir");
+        std::fs::write(dir.join(path), content).expect("write document");
+    }
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("build tokio runtime");
+    let bundled = rt.block_on(bundle(
+        &SpecLoader::default(),
+        &SpecSource::File(dir.join("openapi.yaml")),
+    ));
+    let single = rt.block_on(bundle(
+        &SpecLoader::default(),
+        &SpecSource::File(dir.join("single.yaml")),
+    ));
+    std::fs::remove_dir_all(&dir).expect("remove documents");
+
+    let schemas = &bundled.expect("bundles").document["components"]["schemas"];
+    assert_eq!(schemas["Self"]["$ref"], "#/components/schemas/user_profile");
+    assert_eq!(
+        schemas["Other"]["$ref"],
+        "#/components/schemas/user_profile2"
+    );
+    assert_eq!(schemas["user_profile2"]["type"], "integer");
+    assert_eq!(
+        schemas["Profile"]["$ref"],
+        "#/components/schemas/user_profile3"
+    );
+    assert_eq!(schemas["user_profile3"]["type"], "object");
+    assert_eq!(
+        single.expect("bundles").document["components"]["schemas"]["Alias"]["$ref"],
+        "#/components/schemas/Pet"
+    );
+}
+
+/// Relative `$ref`s of a spec read from stdin resolve against the current
+/// directory.
+#[test]
+fn stdin_specs_resolve_refs_against_the_current_directory() {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let dir = std::env::temp_dir().join(format!("bundle-stdin-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("create dir");
+    std::fs::write(
+        dir.join("user.yaml"),
+        "User: {type: object, properties: {name: {type: string}}}\n",
+    )
+    .expect("write document");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_openapi_parser"))
+        .args(["-u", "-", "-o", "out"])
+        .current_dir(&dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("run the cli");
+    child
+        .stdin
+        .take()
+        .expect("stdin")
+        .write_all(
+            b"openapi: 3.0.0\ninfo: {title: t, version: '1'}\npaths: {}\ncomponents:\n  schemas:\n    Owner: {type: object, properties: {user: {$ref: './user.yaml#/User'}}}\n",
+        )
+        .expect("write stdin");
+    let output = child.wait_with_output().expect("the cli finishes");
+    let user = std::fs::read_to_string(dir.join("out/schemes/User.dart"));
+    std::fs::remove_dir_all(&dir).expect("remove documents");
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert_contains(
+        &user.expect("the referenced schema is generated"),
+        "String? name;",
+        "the referenced document is bundled",
+    );
+}
+
+/// `$ref`s are RFC 6901 JSON pointers: escaped and percent-encoded tokens
+/// resolve, and schemas nested in other schemas or in operations become
+/// synthesized schemes of their own.
+#[test]
+fn json_pointer_refs_are_hoisted_into_schemes() {
+    use openapi_parser::parse::intermediate::{
+        self, pointer_tokens, resolve_pointer, IntermediateArgs,
+    };
+
+    let spec_json = r##"{
+        "openapi": "3.0.0",
+        "info": {"title": "t", "version": "1"},
+        "paths": {
+            "/users": {"get": {"responses": {"200": {"description": "ok", "content": {"application/json": {"schema": {
+                "type": "object",
+                "properties": {"id": {"type": "integer"}}
+            }}}}}}},
+            "/me": {"get": {"responses": {"200": {"description": "ok", "content": {"application/json": {"schema": {
+                "$ref": "#/paths/~1users/get/responses/200/content/application~1json/schema"
+            }}}}}}}
+        },
+        "components": {
+            "schemas": {
+                "Order": {
+                    "type": "object",
+                    "properties": {
+                        "items": {"type": "array", "items": {"type": "object", "properties": {"sku": {"type": "string"}}}}
+                    }
+                },
+                "Cart": {
+                    "type": "object",
+                    "properties": {
+                        "lines": {"$ref": "#/components/schemas/Order/properties/items"},
+                        "same": {"$ref": "#/components/schemas/Order/properties/%69tems"},
+                        "slash": {"$ref": "#/components/schemas/a~1b"}
+                    }
+                },
+                "a/b": {"type": "string"}
+            }
+        }
+    }"##;
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    assert_eq!(
+        pointer_tokens("#/a~01/b~1c/%25"),
+        Some(vec!["a~1".to_string(), "b/c".to_string(), "%".to_string()])
+    );
+    assert_eq!(pointer_tokens("#"), Some(vec![]));
+    assert_eq!(pointer_tokens("#name"), None);
+    assert_eq!(
+        resolve_pointer(
+            &raw,
+            "#/components/schemas/Order/properties/items/items/properties/sku/type"
+        ),
+        Some(&serde_json::json!("string"))
+    );
+    assert_eq!(resolve_pointer(&raw, "#/components/schemas/Missing"), None);
+
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+    let mut names: Vec<_> = intermediate.schemes.iter().map(|s| s.name).collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "Cart",
+            "Order",
+            "Order_items",
+            "a/b",
+            "users_get_200_application_json"
+        ]
+    );
+
+    let files = generate(spec_json);
+    let cart = file(&files, "schemes/Cart.dart");
+    assert_contains(
+        cart,
+        "BEAMOrder_itemsModel? lines;",
+        "nested schemas are named",
+    );
+    assert_contains(
+        cart,
+        "BEAMOrder_itemsModel? same;",
+        "percent-encoded pointers resolve to the same scheme",
+    );
+    assert_contains(cart, "BEAMa_bModel? slash;", "escaped names resolve");
+    assert_contains(
+        file(&files, "schemes/Order_items.dart"),
+        "typedef BEAMOrder_itemsModel = List<BEAMOrder_items_Model>;",
+        "the synthesized scheme is generated",
+    );
+    assert_contains(
+        file(&files, "endpoints/routes/_me.dart"),
+        "BEAMCachedResponse<BEAMusers_get_200_application_jsonModel> get()",
+        "schemas of operations can be referenced",
+    );
+}
+
+/// Fallbacks are reported as diagnostics with a stable code, a severity and
+/// the JSON pointer of the node that caused them.
+#[test]
+fn diagnostics_are_located() {
+    use openapi_parser::parse::intermediate::{self, IntermediateArgs, Severity};
+
+    let spec_json = r##"{
+        "openapi": "3.1.0",
+        "info": { "title": "t", "version": "1" },
+        "paths": {
+            "/pets/{id}": {
+                "get": {
+                    "parameters": [
+                        { "name": "id", "in": "path", "required": true, "schema": { "type": "string" } }
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "ok",
+                            "content": { "application/json": { "schema": { "type": "array" } } }
+                        }
+                    }
+                }
+            }
+        },
+        "components": {
+            "schemas": {
+                "Pet": {
+                    "type": "object",
+                    "properties": {
+                        "tags": { "type": "array" },
+                        "extra": {}
+                    }
+                }
+            }
+        }
+    }"##;
+
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let intermediate = intermediate::parse_document(
+        &document,
+        IntermediateArgs {
+            ignore_deprecated_fields: false,
+            strict: false,
+        },
+    )
+    .expect("intermediate parses");
+    let mut found: Vec<_> = intermediate
+        .diagnostics
+        .iter()
+        .map(|d| (d.severity, d.code, d.pointer.as_str()))
+        .collect();
+    found.sort();
+    assert_eq!(
+        found,
+        [
+            (
+                Severity::Info,
+                "any-schema",
+                "#/components/schemas/Pet/properties/extra"
+            ),
+            (
+                Severity::Warning,
+                "list-without-items",
+                "#/components/schemas/Pet/properties/tags"
+            ),
+            (
+                Severity::Warning,
+                "list-without-items",
+                "#/paths/~1pets~1{id}/get/responses/200/content/application~1json/schema"
+            ),
+        ]
+    );
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("build tokio runtime");
+    let generated = rt
+        .block_on(openapi_parser::generate_dart(spec_json, Default::default()))
+        .expect("generation succeeds");
+    assert_eq!(generated.diagnostics, intermediate.diagnostics);
+    let pet = generated
+        .files
+        .iter()
+        .find(|f| f.path.ends_with("schemes/Pet.dart"))
+        .expect("Pet file");
+    assert_contains(&pet.content, "dynamic? extra;", "`{}` accepts any value");
+    assert_not_contains(
+        &pet.content,
+        "UnknownBEAMObject",
+        "`{}` isn't an unknown object",
+    );
+}
+
+/// In strict mode, constructs that can't be represented fail the parse,
+/// naming where they are and the unsupported keyword.
+#[test]
+fn strict_mode_rejects_unsupported_constructs() {
+    use openapi_parser::parse::intermediate::{self, Error, IntermediateArgs};
+
+    let spec_json = r##"{
+        "openapi": "3.1.0",
+        "info": { "title": "t", "version": "1" },
+        "paths": {
+            "/pets": {
+                "post": {
+                    "requestBody": { "content": { "application/octet-stream": {} } },
+                    "responses": { "204": { "description": "ok" } }
+                }
+            }
+        },
+        "components": {
+            "schemas": {
+                "Pet": {
+                    "type": "object",
+                    "patternProperties": { "^x-": { "type": "string" } },
+                    "properties": {
+                        "name": { "type": "string", "not": { "const": "" } },
+                        "kind": { "if": { "type": "string" }, "then": { "minLength": 1 } }
+                    }
+                }
+            }
+        }
+    }"##;
+
+    let raw: serde_json::Value = serde_json::from_str(spec_json).expect("valid json");
+    let spec = oas3::from_json(spec_json).expect("valid spec");
+    let document = intermediate::SpecDocument::new(&spec, &raw);
+    let parse = |strict| {
+        intermediate::parse_document(
+            &document,
+            IntermediateArgs {
+                ignore_deprecated_fields: false,
+                strict,
+            },
+        )
+    };
+
+    let lenient = parse(false).expect("lenient mode only reports");
+    assert!(!lenient.diagnostics.is_empty());
+
+    let Err(Error::Unsupported(diagnostics)) = parse(true) else {
+        panic!("strict mode fails");
+    };
+    let mut found: Vec<_> = diagnostics
+        .iter()
+        .map(|d| (d.code, d.pointer.as_str()))
+        .collect();
+    found.sort();
+    assert_eq!(
+        found,
+        [
+            (
+                "request-body-without-schema",
+                "#/paths/~1pets/post/requestBody"
+            ),
+            (
+                "unsupported-keyword",
+                "#/components/schemas/Pet/patternProperties"
+            ),
+            (
+                "unsupported-keyword",
+                "#/components/schemas/Pet/properties/kind/if"
+            ),
+            (
+                "unsupported-keyword",
+                "#/components/schemas/Pet/properties/kind/then"
+            ),
+            (
+                "unsupported-keyword",
+                "#/components/schemas/Pet/properties/name/not"
+            ),
+            ("untyped-schema", "#/components/schemas/Pet/properties/kind"),
+        ]
+    );
+    let message = Error::Unsupported(diagnostics).to_string();
+    assert_contains(
+        &message,
+        "#/components/schemas/Pet/properties/name/not: `not` isn't supported",
+        "the error names the location and keyword",
+    );
+}